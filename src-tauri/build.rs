@@ -1,3 +1,24 @@
 fn main() {
+    // Short commit hash for `app_version`; left unset outside a git checkout
+    if let Ok(output) = std::process::Command::new("git").args(["rev-parse", "--short", "HEAD"]).output() {
+        if output.status.success() {
+            let hash = String::from_utf8_lossy(&output.stdout);
+            println!("cargo:rustc-env=TIMEWARDEN_GIT_HASH={}", hash.trim());
+        }
+    }
+    // HEAD only changes on checkout; commits move the branch ref it points at,
+    // which lives in its own file or, once packed, in packed-refs. Missing files
+    // would rerun every build, so only existing ones are watched.
+    let git_dir = std::path::Path::new("../.git");
+    let mut watched = vec![git_dir.join("HEAD"), git_dir.join("packed-refs")];
+    if let Ok(head) = std::fs::read_to_string(git_dir.join("HEAD")) {
+        if let Some(reference) = head.trim().strip_prefix("ref: ") {
+            watched.push(git_dir.join(reference));
+        }
+    }
+    for path in watched.iter().filter(|path| path.exists()) {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+
     tauri_build::build()
 }
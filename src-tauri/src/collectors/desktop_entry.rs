@@ -0,0 +1,160 @@
+//! Icon lookup for Linux apps: a window's WM_CLASS is matched against the
+//! installed `.desktop` files, whose `Icon=` key names a themed icon.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Icon sizes searched in the hicolor theme, largest first
+const ICON_SIZES: [&str; 6] = ["256x256", "128x128", "96x96", "64x64", "48x48", "32x32"];
+
+/// XDG data directories, most specific first (`$XDG_DATA_HOME`, then `$XDG_DATA_DIRS`)
+pub fn xdg_data_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    match std::env::var_os("XDG_DATA_HOME") {
+        Some(home) => dirs.push(PathBuf::from(home)),
+        None => {
+            if let Some(home) = std::env::var_os("HOME") {
+                dirs.push(PathBuf::from(home).join(".local/share"));
+            }
+        }
+    }
+    let system = std::env::var("XDG_DATA_DIRS").unwrap_or_else(|_| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(system.split(':').filter(|d| !d.is_empty()).map(PathBuf::from));
+    dirs
+}
+
+/// Value of `key` in the `[Desktop Entry]` group of a desktop file
+fn desktop_entry_value(contents: &str, key: &str) -> Option<String> {
+    let mut in_entry = false;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+        } else if in_entry {
+            if let Some((k, v)) = line.split_once('=') {
+                if k.trim() == key {
+                    return Some(v.trim().to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Find the desktop file for a WM_CLASS. A file named after the class wins,
+/// otherwise the first file whose `StartupWMClass` matches (case-insensitive).
+pub fn find_desktop_file(data_dirs: &[PathBuf], wm_class: &str) -> Option<PathBuf> {
+    let wanted = format!("{}.desktop", wm_class.to_lowercase());
+    let mut by_startup_class = None;
+
+    for dir in data_dirs {
+        let Ok(entries) = std::fs::read_dir(dir.join("applications")) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            if !name.ends_with(".desktop") {
+                continue;
+            }
+            if name == wanted {
+                return Some(path);
+            }
+            if by_startup_class.is_none() {
+                let class = std::fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|contents| desktop_entry_value(&contents, "StartupWMClass"));
+                if class.is_some_and(|c| c.eq_ignore_ascii_case(wm_class)) {
+                    by_startup_class = Some(path);
+                }
+            }
+        }
+    }
+    by_startup_class
+}
+
+/// Resolve an `Icon=` value to a PNG file: absolute paths are used as-is,
+/// names are looked up in the hicolor theme and then `pixmaps`.
+pub fn resolve_icon_path(data_dirs: &[PathBuf], icon: &str) -> Option<PathBuf> {
+    let is_png = |path: &Path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+
+    let direct = Path::new(icon);
+    if direct.is_absolute() {
+        return (is_png(direct) && direct.is_file()).then(|| direct.to_path_buf());
+    }
+
+    let file_name = format!("{}.png", icon);
+    let file_name = file_name.as_str();
+    data_dirs
+        .iter()
+        .flat_map(|dir| {
+            ICON_SIZES
+                .iter()
+                .map(move |size| dir.join("icons/hicolor").join(size).join("apps").join(file_name))
+                .chain(std::iter::once(dir.join("pixmaps").join(file_name)))
+        })
+        .find(|path| path.is_file())
+}
+
+/// PNG bytes of the icon for a WM_CLASS, or None when no desktop entry matches
+pub fn icon_png_for_class(data_dirs: &[PathBuf], wm_class: &str) -> Option<Vec<u8>> {
+    let desktop_file = find_desktop_file(data_dirs, wm_class)?;
+    let contents = std::fs::read_to_string(desktop_file).ok()?;
+    let icon = desktop_entry_value(&contents, "Icon")?;
+    std::fs::read(resolve_icon_path(data_dirs, &icon)?).ok()
+}
+
+/// Icon lookups cached by process name, including misses
+#[derive(Default)]
+pub struct IconCache {
+    icons: Mutex<HashMap<String, Option<Vec<u8>>>>,
+}
+
+impl IconCache {
+    /// Cached icon for `process_name`, resolving it via `wm_class` on first use
+    pub fn get_or_resolve(&self, process_name: &str, wm_class: &str) -> Option<Vec<u8>> {
+        let mut icons = self.icons.lock().unwrap();
+        icons
+            .entry(process_name.to_string())
+            .or_insert_with(|| icon_png_for_class(&xdg_data_dirs(), wm_class))
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wm_class_resolves_to_desktop_icon() {
+        let root = std::env::temp_dir().join(format!("timewarden_desktop_fixture_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let apps = root.join("applications");
+        let icons = root.join("icons/hicolor/48x48/apps");
+        std::fs::create_dir_all(&apps).unwrap();
+        std::fs::create_dir_all(&icons).unwrap();
+
+        std::fs::write(
+            apps.join("org.gnome.Terminal.desktop"),
+            "[Desktop Entry]\nName=Terminal\nIcon=utilities-terminal\nStartupWMClass=Gnome-terminal\n",
+        )
+        .unwrap();
+        std::fs::write(apps.join("firefox.desktop"), "[Desktop Entry]\nName=Firefox\nIcon=firefox\n").unwrap();
+        std::fs::write(icons.join("utilities-terminal.png"), b"\x89PNG terminal").unwrap();
+
+        let dirs = vec![root.clone()];
+        // Matched through StartupWMClass
+        assert_eq!(
+            find_desktop_file(&dirs, "gnome-terminal"),
+            Some(apps.join("org.gnome.Terminal.desktop"))
+        );
+        assert_eq!(icon_png_for_class(&dirs, "Gnome-terminal"), Some(b"\x89PNG terminal".to_vec()));
+        // Matched by file name, but the icon isn't installed
+        assert_eq!(find_desktop_file(&dirs, "Firefox"), Some(apps.join("firefox.desktop")));
+        assert_eq!(icon_png_for_class(&dirs, "Firefox"), None);
+        // No desktop entry at all
+        assert_eq!(icon_png_for_class(&dirs, "xterm"), None);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
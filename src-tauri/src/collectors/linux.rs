@@ -0,0 +1,311 @@
+//! Foreground window and idle time on X11 (XWayland windows included), via
+//! Xlib and the XScreenSaver extension. Both libraries are loaded at runtime,
+//! so a session without an X server just reports nothing.
+
+use crate::collectors::desktop_entry::IconCache;
+use crate::collectors::{procfs, ForegroundCollector};
+use crate::models::AppInfo;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::{c_int, c_uchar, c_ulong};
+use std::sync::Mutex;
+use x11_dl::{xlib, xss};
+
+/// Longest property value read, in 32-bit units
+const MAX_PROPERTY_LENGTH: i64 = 1024;
+
+/// Atoms read on every poll, interned once per connection
+struct Atoms {
+    active_window: xlib::Atom,
+    wm_pid: xlib::Atom,
+    wm_name: xlib::Atom,
+    utf8_string: xlib::Atom,
+}
+
+/// An open X display and the libraries it was opened through
+struct Connection {
+    xlib: xlib::Xlib,
+    /// None when libXss or the server's extension is missing
+    xss: Option<xss::Xss>,
+    display: *mut xlib::Display,
+    root: xlib::Window,
+    atoms: Atoms,
+}
+
+// SAFETY: the display is only used by one thread at a time, behind the collector's mutex
+unsafe impl Send for Connection {}
+
+impl Connection {
+    /// Connect to the display named by `$DISPLAY`
+    fn open() -> Option<Self> {
+        let xlib = xlib::Xlib::open().ok()?;
+        let display = unsafe { (xlib.XOpenDisplay)(std::ptr::null()) };
+        if display.is_null() {
+            return None;
+        }
+        // The default handler exits the process, e.g. when the active window
+        // closes between two property reads
+        unsafe { (xlib.XSetErrorHandler)(Some(ignore_x_error)) };
+        let root = unsafe { (xlib.XDefaultRootWindow)(display) };
+        let xss = xss::Xss::open().ok().filter(|xss| {
+            let (mut event_base, mut error_base) = (0, 0);
+            unsafe { (xss.XScreenSaverQueryExtension)(display, &mut event_base, &mut error_base) != 0 }
+        });
+        let atom = |name: &str| {
+            let name = CString::new(name).expect("atom names have no NUL");
+            unsafe { (xlib.XInternAtom)(display, name.as_ptr(), xlib::False) }
+        };
+        let atoms = Atoms {
+            active_window: atom("_NET_ACTIVE_WINDOW"),
+            wm_pid: atom("_NET_WM_PID"),
+            wm_name: atom("_NET_WM_NAME"),
+            utf8_string: atom("UTF8_STRING"),
+        };
+        Some(Self { xlib, xss, display, root, atoms })
+    }
+
+    /// Read a window property of type `kind`, handing its format, data and
+    /// item count to `read`. None if the property is missing, empty or of
+    /// another type.
+    fn property<T>(
+        &self,
+        window: xlib::Window,
+        property: xlib::Atom,
+        kind: xlib::Atom,
+        read: impl FnOnce(c_int, *const c_uchar, usize) -> T,
+    ) -> Option<T> {
+        let mut actual_kind: xlib::Atom = 0;
+        let mut format: c_int = 0;
+        let mut items: c_ulong = 0;
+        let mut remaining: c_ulong = 0;
+        let mut data: *mut c_uchar = std::ptr::null_mut();
+        let status = unsafe {
+            (self.xlib.XGetWindowProperty)(
+                self.display,
+                window,
+                property,
+                0,
+                MAX_PROPERTY_LENGTH,
+                xlib::False,
+                kind,
+                &mut actual_kind,
+                &mut format,
+                &mut items,
+                &mut remaining,
+                &mut data,
+            )
+        };
+        if data.is_null() {
+            return None;
+        }
+        let value = (status == c_int::from(xlib::Success) && actual_kind == kind && items > 0)
+            .then(|| read(format, data, items as usize));
+        unsafe { (self.xlib.XFree)(data.cast()) };
+        value
+    }
+
+    /// First item of a 32-bit property (Xlib hands those out as C longs)
+    fn property_u32(&self, window: xlib::Window, property: xlib::Atom, kind: xlib::Atom) -> Option<c_ulong> {
+        self.property(window, property, kind, |format, data, _| {
+            (format == 32).then(|| unsafe { *data.cast::<c_ulong>() })
+        })
+        .flatten()
+    }
+
+    /// Raw bytes of an 8-bit property
+    fn property_bytes(&self, window: xlib::Window, property: xlib::Atom, kind: xlib::Atom) -> Option<Vec<u8>> {
+        self.property(window, property, kind, |format, data, items| {
+            (format == 8).then(|| unsafe { std::slice::from_raw_parts(data, items) }.to_vec())
+        })
+        .flatten()
+    }
+
+    fn property_text(&self, window: xlib::Window, property: xlib::Atom, kind: xlib::Atom) -> Option<String> {
+        self.property_bytes(window, property, kind)
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// The window the window manager reports as active
+    fn active_window(&self) -> Option<xlib::Window> {
+        self.property_u32(self.root, self.atoms.active_window, xlib::XA_WINDOW)
+            .filter(|window| *window != 0)
+    }
+
+    /// `_NET_WM_NAME` (UTF-8), falling back to the legacy `WM_NAME`
+    fn window_title(&self, window: xlib::Window) -> Option<String> {
+        self.property_text(window, self.atoms.wm_name, self.atoms.utf8_string)
+            .or_else(|| self.property_text(window, xlib::XA_WM_NAME, xlib::XA_STRING))
+            .filter(|title| !title.is_empty())
+    }
+
+    fn window_pid(&self, window: xlib::Window) -> Option<u32> {
+        self.property_u32(window, self.atoms.wm_pid, xlib::XA_CARDINAL)
+            .and_then(|pid| u32::try_from(pid).ok())
+            .filter(|pid| *pid != 0)
+    }
+
+    fn window_class(&self, window: xlib::Window) -> Option<String> {
+        self.property_bytes(window, xlib::XA_WM_CLASS, xlib::XA_STRING)
+            .and_then(|bytes| parse_wm_class(&bytes))
+    }
+
+    /// Milliseconds since the last input, None without the XScreenSaver extension
+    fn idle_millis(&self) -> Option<u64> {
+        let xss = self.xss.as_ref()?;
+        unsafe {
+            let info = (xss.XScreenSaverAllocInfo)();
+            if info.is_null() {
+                return None;
+            }
+            let idle = ((xss.XScreenSaverQueryInfo)(self.display, self.root, info) != 0).then(|| (*info).idle);
+            (self.xlib.XFree)(info.cast());
+            idle
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        unsafe { (self.xlib.XCloseDisplay)(self.display) };
+    }
+}
+
+/// Class part of a WM_CLASS value (`instance\0class\0`), falling back to the instance
+fn parse_wm_class(bytes: &[u8]) -> Option<String> {
+    let mut parts = bytes.split(|b| *b == 0).filter(|part| !part.is_empty());
+    let instance = parts.next()?;
+    Some(String::from_utf8_lossy(parts.next().unwrap_or(instance)).into_owned())
+}
+
+/// Process name of a window: its `/proc` command name, unless that's missing
+/// (no `_NET_WM_PID`, e.g. remote or sandboxed clients) or was truncated by
+/// the kernel, in which case the WM_CLASS is used
+fn window_process_name(comm: Option<String>, wm_class: Option<String>) -> Option<String> {
+    match (comm, wm_class) {
+        (Some(comm), Some(class)) if comm.len() >= procfs::COMM_MAX_LEN => Some(class),
+        (Some(comm), _) => Some(comm),
+        (None, class) => class,
+    }
+}
+
+unsafe extern "C" fn ignore_x_error(_display: *mut xlib::Display, _event: *mut xlib::XErrorEvent) -> c_int {
+    0
+}
+
+pub struct X11Collector {
+    /// Opened on first use, and retried while there is no display
+    connection: Mutex<Option<Connection>>,
+    /// WM_CLASS of the windows seen for each process name, for icon lookups
+    wm_classes: Mutex<HashMap<String, String>>,
+    icons: IconCache,
+}
+
+impl X11Collector {
+    pub fn new() -> Self {
+        Self {
+            connection: Mutex::new(None),
+            wm_classes: Mutex::new(HashMap::new()),
+            icons: IconCache::default(),
+        }
+    }
+
+    fn with_connection<T>(&self, f: impl FnOnce(&Connection) -> Option<T>) -> Option<T> {
+        let mut connection = self.connection.lock().unwrap();
+        if connection.is_none() {
+            *connection = Connection::open();
+        }
+        connection.as_ref().and_then(f)
+    }
+}
+
+impl Default for X11Collector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ForegroundCollector for X11Collector {
+    fn get_foreground_app(&self) -> Option<AppInfo> {
+        self.with_connection(|connection| {
+            let window = connection.active_window()?;
+            let pid = connection.window_pid(window);
+            let wm_class = connection.window_class(window);
+            let process_name = window_process_name(pid.and_then(procfs::process_comm), wm_class.clone())?;
+            if let Some(class) = wm_class {
+                self.wm_classes.lock().unwrap().insert(process_name.clone(), class);
+            }
+            Some(AppInfo {
+                process_name,
+                app_title: connection.window_title(window),
+                bundle_id: None,
+                process_path: pid.and_then(procfs::process_exe_path),
+            })
+        })
+    }
+
+    fn get_idle_seconds(&self) -> u64 {
+        self.with_connection(Connection::idle_millis)
+            .map_or(0, |idle| idle / 1000)
+    }
+
+    /// Looked up through the WM_CLASS last seen for the process, or the
+    /// process name itself if none of its windows has been focused yet
+    fn get_app_icon(&self, process_name: &str) -> Option<Vec<u8>> {
+        let wm_class = self.wm_classes.lock().unwrap().get(process_name).cloned();
+        self.icons
+            .get_or_resolve(process_name, wm_class.as_deref().unwrap_or(process_name))
+    }
+
+    fn name(&self) -> &'static str {
+        "x11"
+    }
+
+    fn supports_window_titles(&self) -> bool {
+        true
+    }
+
+    fn supports_icons(&self) -> bool {
+        true
+    }
+
+    fn supports_idle(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = X11Collector::new().capabilities();
+        assert_eq!(capabilities.collector, "x11");
+        assert!(capabilities.window_titles);
+        assert!(capabilities.icons);
+        assert!(capabilities.idle);
+        assert!(!capabilities.bundle_id);
+    }
+
+    #[test]
+    fn test_parse_wm_class() {
+        assert_eq!(parse_wm_class(b"gnome-terminal-server\0Gnome-terminal\0").as_deref(), Some("Gnome-terminal"));
+        assert_eq!(parse_wm_class(b"xterm\0").as_deref(), Some("xterm"));
+        assert_eq!(parse_wm_class(b"\0Firefox\0").as_deref(), Some("Firefox"));
+        assert_eq!(parse_wm_class(b"\0\0"), None);
+    }
+
+    #[test]
+    fn test_window_process_name() {
+        let name = |comm: Option<&str>, class: Option<&str>| {
+            window_process_name(comm.map(str::to_string), class.map(str::to_string))
+        };
+        assert_eq!(name(Some("firefox"), Some("Firefox")).as_deref(), Some("firefox"));
+        // Truncated by the kernel
+        assert_eq!(name(Some("gnome-terminal-"), Some("Gnome-terminal")).as_deref(), Some("Gnome-terminal"));
+        assert_eq!(name(Some("gnome-terminal-"), None).as_deref(), Some("gnome-terminal-"));
+        // No _NET_WM_PID
+        assert_eq!(name(None, Some("XTerm")).as_deref(), Some("XTerm"));
+        assert_eq!(name(None, None), None);
+    }
+}
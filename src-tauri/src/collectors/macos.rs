@@ -1,6 +1,24 @@
 use crate::collectors::ForegroundCollector;
 use crate::models::AppInfo;
 
+use objc2_app_kit::NSWorkspace;
+use objc2_core_foundation::{CFRetained, CFString, CFType};
+use objc2_core_graphics::{CGEventSource, CGEventSourceStateID, CGEventType};
+use std::ptr::NonNull;
+
+/// `kCGAnyInputEventType`: any keyboard, mouse or tablet event
+const ANY_INPUT_EVENT_TYPE: CGEventType = CGEventType(u32::MAX);
+
+/// `kAXErrorSuccess`
+const AX_ERROR_SUCCESS: i32 = 0;
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXIsProcessTrusted() -> u8;
+    fn AXUIElementCreateApplication(pid: i32) -> *mut CFType;
+    fn AXUIElementCopyAttributeValue(element: &CFType, attribute: &CFString, value: *mut *mut CFType) -> i32;
+}
+
 pub struct MacOSCollector;
 
 impl MacOSCollector {
@@ -9,12 +27,77 @@ impl MacOSCollector {
     }
 }
 
+impl Default for MacOSCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ForegroundCollector for MacOSCollector {
     fn get_foreground_app(&self) -> Option<AppInfo> {
-        None // Implementation in Phase 2
+        // Polls run on worker threads without a pool of their own
+        objc2::rc::autoreleasepool(|_| {
+            let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+            let bundle_id = app.bundleIdentifier().map(|id| id.to_string());
+            // Apps without a bundle may have no localized name either
+            let process_name = app.localizedName().map(|name| name.to_string()).or_else(|| bundle_id.clone())?;
+            let pid = app.processIdentifier();
+            Some(AppInfo {
+                process_name,
+                app_title: (pid > 0).then(|| focused_window_title(pid)).flatten(),
+                bundle_id,
+                process_path: app.executableURL().and_then(|url| url.path()).map(|path| path.to_string()),
+            })
+        })
     }
-    
+
     fn get_idle_seconds(&self) -> u64 {
-        0 // Implementation in Phase 2
+        let idle = CGEventSource::seconds_since_last_event_type(
+            CGEventSourceStateID::CombinedSessionState,
+            ANY_INPUT_EVENT_TYPE,
+        );
+        idle.max(0.0).round() as u64
+    }
+
+    fn name(&self) -> &'static str {
+        "macos"
+    }
+
+    fn supports_bundle_id(&self) -> bool {
+        true
+    }
+
+    fn supports_window_titles(&self) -> bool {
+        true
+    }
+
+    fn supports_idle(&self) -> bool {
+        true
+    }
+}
+
+/// Title of the focused window of process `pid`, via the Accessibility API.
+/// None without Accessibility permission, which is never prompted for here,
+/// or when the app exposes no titled window.
+fn focused_window_title(pid: i32) -> Option<String> {
+    if unsafe { AXIsProcessTrusted() } == 0 {
+        return None;
+    }
+    // SAFETY: the element is returned at +1 (create rule)
+    let app = unsafe { CFRetained::from_raw(NonNull::new(AXUIElementCreateApplication(pid))?) };
+    let window = copy_attribute(&app, "AXFocusedWindow")?;
+    let title = copy_attribute(&window, "AXTitle")?.downcast::<CFString>().ok()?.to_string();
+    (!title.is_empty()).then_some(title)
+}
+
+/// An Accessibility attribute of `element`, None if it's missing or the call fails
+fn copy_attribute(element: &CFType, attribute: &'static str) -> Option<CFRetained<CFType>> {
+    let attribute = CFString::from_static_str(attribute);
+    let mut value: *mut CFType = std::ptr::null_mut();
+    let error = unsafe { AXUIElementCopyAttributeValue(element, &attribute, &mut value) };
+    if error != AX_ERROR_SUCCESS {
+        return None;
     }
+    // SAFETY: copied values are returned at +1 (copy rule)
+    NonNull::new(value).map(|value| unsafe { CFRetained::from_raw(value) })
 }
@@ -0,0 +1,199 @@
+//! Scripted collector for tests and for running the app without OS tracking
+//! (`TIMEWARDEN_MOCK`).
+
+use crate::collectors::{process_matches, ForegroundCollector};
+use crate::models::AppInfo;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// One scripted poll: the foreground app plus idle/call state and background processes
+#[derive(Debug, Clone, Default)]
+pub struct MockReading {
+    pub app: Option<AppInfo>,
+    pub idle_seconds: u64,
+    pub in_call: bool,
+    /// Process names running besides the foreground app
+    pub running: Vec<String>,
+    /// Input events per minute, None to act like a collector that can't count them
+    pub input_rate: Option<f64>,
+}
+
+impl MockReading {
+    /// `process_name` in the foreground with recent input
+    pub fn app(process_name: &str) -> Self {
+        Self {
+            app: Some(AppInfo {
+                process_name: process_name.to_string(),
+                app_title: None,
+                bundle_id: None,
+                process_path: None,
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// No foreground app
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    pub fn with_idle(self, idle_seconds: u64) -> Self {
+        Self { idle_seconds, ..self }
+    }
+
+    pub fn with_call(self, in_call: bool) -> Self {
+        Self { in_call, ..self }
+    }
+
+    pub fn with_input_rate(self, input_rate: f64) -> Self {
+        Self { input_rate: Some(input_rate), ..self }
+    }
+
+    pub fn with_running(self, process_name: &str) -> Self {
+        let mut running = self.running;
+        running.push(process_name.to_string());
+        Self { running, ..self }
+    }
+}
+
+/// Replays scripted readings. Each `get_foreground_app` call advances to the
+/// next reading; idle and call state come from the reading last advanced to.
+/// Once the script runs out the last reading keeps repeating.
+#[derive(Debug, Default)]
+pub struct MockCollector {
+    script: Mutex<VecDeque<MockReading>>,
+    current: Mutex<MockReading>,
+}
+
+impl MockCollector {
+    pub fn new(script: impl IntoIterator<Item = MockReading>) -> Self {
+        Self {
+            script: Mutex::new(script.into_iter().collect()),
+            current: Mutex::new(MockReading::default()),
+        }
+    }
+
+    /// Parse a `TIMEWARDEN_MOCK` value: comma-separated process names, one per
+    /// poll, where an empty entry means no foreground app
+    pub fn from_env_script(script: &str) -> Self {
+        Self::new(script.split(',').map(|name| match name.trim() {
+            "" => MockReading::none(),
+            name => MockReading::app(name),
+        }))
+    }
+
+    /// Append a reading to the end of the script
+    pub fn push(&self, reading: MockReading) {
+        self.script.lock().unwrap().push_back(reading);
+    }
+}
+
+impl ForegroundCollector for MockCollector {
+    fn get_foreground_app(&self) -> Option<AppInfo> {
+        let mut current = self.current.lock().unwrap();
+        if let Some(next) = self.script.lock().unwrap().pop_front() {
+            *current = next;
+        }
+        current.app.clone()
+    }
+
+    fn get_idle_seconds(&self) -> u64 {
+        self.current.lock().unwrap().idle_seconds
+    }
+
+    fn is_in_call(&self) -> bool {
+        self.current.lock().unwrap().in_call
+    }
+
+    fn is_any_process_running(&self, patterns: &[String]) -> bool {
+        let current = self.current.lock().unwrap();
+        current
+            .app
+            .iter()
+            .map(|app| app.process_name.as_str())
+            .chain(current.running.iter().map(String::as_str))
+            .any(|process| process_matches(process, patterns))
+    }
+
+    fn get_input_rate(&self) -> Option<f64> {
+        self.current.lock().unwrap().input_rate
+    }
+
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn supports_window_titles(&self) -> bool {
+        true
+    }
+
+    fn supports_idle(&self) -> bool {
+        true
+    }
+
+    fn supports_call_detection(&self) -> bool {
+        true
+    }
+
+    fn supports_process_enumeration(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sessionizer::{Sessionizer, SessionizerConfig};
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_scripted_sequence_produces_sessions() {
+        let collector = MockCollector::new([
+            MockReading::app("Code.exe"),
+            MockReading::app("Code.exe"),
+            MockReading::app("Code.exe").with_idle(400),
+            MockReading::app("Code.exe").with_idle(500),
+            MockReading::app("Slack.exe"),
+        ]);
+        let mut sessionizer = Sessionizer::new(SessionizerConfig::default());
+        let start = Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap();
+
+        for minute in 0..6 {
+            let app = collector.get_foreground_app();
+            let idle = collector.get_idle_seconds();
+            let in_call = collector.is_in_call();
+            sessionizer.update_at(app, idle, in_call, start + Duration::minutes(minute));
+        }
+
+        let sessions: Vec<_> = sessionizer
+            .take_pending_sessions()
+            .into_iter()
+            .map(|s| (s.app_id, s.is_idle, s.duration_seconds))
+            .collect();
+        assert_eq!(
+            sessions,
+            vec![
+                ("Code.exe".to_string(), false, Some(120)),
+                ("Idle".to_string(), true, Some(120)),
+            ]
+        );
+        // The script ran out, so Slack stays in the foreground
+        assert_eq!(collector.get_foreground_app().unwrap().process_name, "Slack.exe");
+    }
+
+    #[test]
+    fn test_env_script() {
+        let collector = MockCollector::from_env_script("Code.exe,,Slack.exe");
+        assert_eq!(collector.get_foreground_app().unwrap().process_name, "Code.exe");
+        assert!(collector.get_foreground_app().is_none());
+        assert_eq!(collector.get_foreground_app().unwrap().process_name, "Slack.exe");
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = MockCollector::default().capabilities();
+        assert_eq!(capabilities.collector, "mock");
+        assert!(capabilities.window_titles && capabilities.idle);
+        assert!(!capabilities.bundle_id && !capabilities.icons);
+    }
+}
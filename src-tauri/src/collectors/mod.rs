@@ -1,8 +1,141 @@
 use crate::models::AppInfo;
+use serde::{Deserialize, Serialize};
+
+/// Which input devices count as activity when computing idle time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IdleInputMode {
+    /// Any keyboard or mouse input resets idle (default)
+    #[default]
+    Combined,
+    KeyboardOnly,
+    MouseOnly,
+}
+
+/// Pick the idle reading for the configured input mode
+pub fn select_idle_seconds(mode: IdleInputMode, keyboard_idle: u64, mouse_idle: u64) -> u64 {
+    match mode {
+        IdleInputMode::Combined => keyboard_idle.min(mouse_idle),
+        IdleInputMode::KeyboardOnly => keyboard_idle,
+        IdleInputMode::MouseOnly => mouse_idle,
+    }
+}
+
+/// Why a tick's collector reading couldn't be trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectorFailure {
+    /// No foreground app while the user wasn't idle. Collectors report errors
+    /// (e.g. a denied permission) this way too.
+    NoReading,
+    /// `get_foreground_app` took longer than `perf::SLOW_CALL_THRESHOLD`
+    TimedOut,
+}
+
+impl CollectorFailure {
+    /// Reason stored in `collector_errors`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CollectorFailure::NoReading => "no_reading",
+            CollectorFailure::TimedOut => "timed_out",
+        }
+    }
+}
+
+/// What the active collector can report, so the UI can hide unsupported columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CollectorCapabilities {
+    pub collector: &'static str,
+    pub bundle_id: bool,
+    pub window_titles: bool,
+    pub icons: bool,
+    pub idle: bool,
+    pub call_detection: bool,
+    pub process_enumeration: bool,
+}
+
+/// Whether a process name contains any of `patterns` (case-insensitive)
+pub fn process_matches(process_name: &str, patterns: &[String]) -> bool {
+    let process = process_name.to_lowercase();
+    patterns.iter().any(|pattern| process.contains(&pattern.to_lowercase()))
+}
 
 pub trait ForegroundCollector: Send + Sync {
     fn get_foreground_app(&self) -> Option<AppInfo>;
     fn get_idle_seconds(&self) -> u64;
+
+    /// Choose which input devices count as activity.
+    /// Collectors that can't tell devices apart ignore this.
+    fn set_idle_input_mode(&self, _mode: IdleInputMode) {}
+
+    /// True while an audio/video call appears to be running
+    /// (microphone or camera in use). Collectors that can't tell return false.
+    fn is_in_call(&self) -> bool {
+        false
+    }
+
+    /// True if any running process, foreground or not, matches one of `patterns`
+    /// (see `process_matches`). Collectors that can't enumerate processes return false.
+    fn is_any_process_running(&self, _patterns: &[String]) -> bool {
+        false
+    }
+
+    /// PNG bytes of the icon of `process_name`, as reported in `AppInfo`.
+    /// Collectors that can't look up icons return None.
+    fn get_app_icon(&self, _process_name: &str) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Keyboard and mouse events per minute since the previous call.
+    /// Collectors that can't count input events return None.
+    fn get_input_rate(&self) -> Option<f64> {
+        None
+    }
+
+    /// Short name of the collector implementation
+    fn name(&self) -> &'static str {
+        "unknown"
+    }
+
+    /// Whether `AppInfo::bundle_id` is filled in
+    fn supports_bundle_id(&self) -> bool {
+        false
+    }
+
+    /// Whether `AppInfo::app_title` is filled in
+    fn supports_window_titles(&self) -> bool {
+        false
+    }
+
+    /// Whether app icons can be looked up
+    fn supports_icons(&self) -> bool {
+        false
+    }
+
+    /// Whether `get_idle_seconds` reflects real input
+    fn supports_idle(&self) -> bool {
+        false
+    }
+
+    /// Whether `is_in_call` can detect calls
+    fn supports_call_detection(&self) -> bool {
+        false
+    }
+
+    /// Whether `is_any_process_running` can see running processes
+    fn supports_process_enumeration(&self) -> bool {
+        false
+    }
+
+    fn capabilities(&self) -> CollectorCapabilities {
+        CollectorCapabilities {
+            collector: self.name(),
+            bundle_id: self.supports_bundle_id(),
+            window_titles: self.supports_window_titles(),
+            icons: self.supports_icons(),
+            idle: self.supports_idle(),
+            call_detection: self.supports_call_detection(),
+            process_enumeration: self.supports_process_enumeration(),
+        }
+    }
 }
 
 #[cfg(target_os = "windows")]
@@ -11,13 +144,52 @@ pub mod windows;
 #[cfg(target_os = "macos")]
 pub mod macos;
 
+#[cfg(target_os = "linux")]
+pub mod linux;
+
+#[cfg(target_os = "linux")]
+pub mod desktop_entry;
+
+#[cfg(target_os = "linux")]
+pub mod procfs;
+
+pub mod mock;
+
+pub mod perf;
+
+/// Set to a comma-separated list of process names to replay them instead of
+/// reading the OS (see `mock::MockCollector::from_env_script`)
+pub const MOCK_ENV_VAR: &str = "TIMEWARDEN_MOCK";
+
 pub fn create_collector() -> std::sync::Arc<dyn ForegroundCollector> {
+    if let Some(script) = std::env::var(MOCK_ENV_VAR).ok().filter(|s| !s.is_empty()) {
+        println!("[Collector] Using mock collector from {}", MOCK_ENV_VAR);
+        return std::sync::Arc::new(mock::MockCollector::from_env_script(&script));
+    }
+
     #[cfg(target_os = "windows")]
     { std::sync::Arc::new(windows::WindowsCollector::new()) }
     
     #[cfg(target_os = "macos")]
     { std::sync::Arc::new(macos::MacOSCollector::new()) }
     
-    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    #[cfg(target_os = "linux")]
+    { std::sync::Arc::new(linux::X11Collector::new()) }
+    
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
     { panic!("Unsupported platform") }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_idle_seconds() {
+        // Reading: keyboard untouched for 10 minutes, mouse scrolled 20s ago
+        assert_eq!(select_idle_seconds(IdleInputMode::Combined, 600, 20), 20);
+        assert_eq!(select_idle_seconds(IdleInputMode::KeyboardOnly, 600, 20), 600);
+        assert_eq!(select_idle_seconds(IdleInputMode::MouseOnly, 600, 20), 20);
+        assert_eq!(select_idle_seconds(IdleInputMode::Combined, 5, 90), 5);
+    }
+}
@@ -0,0 +1,99 @@
+//! Latency tracking for collector calls, to spot a collector that slows down
+//! the polling loop (e.g. UI Automation hanging on some windows).
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Number of recent calls kept for the rolling statistics
+pub const PERF_WINDOW: usize = 200;
+
+/// Calls slower than this count towards a slow-collector warning
+pub const SLOW_CALL_THRESHOLD: Duration = Duration::from_millis(250);
+
+/// Consecutive slow calls before a warning is logged
+pub const SLOW_CALL_WARN_AFTER: u32 = 3;
+
+/// Latency of recent `get_foreground_app` calls, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct CollectorPerfStats {
+    pub samples: usize,
+    pub avg_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Rolling window of collector call durations
+#[derive(Debug, Default)]
+pub struct CollectorPerf {
+    samples: VecDeque<Duration>,
+    /// Slow calls since the last fast one
+    consecutive_slow: u32,
+}
+
+impl CollectorPerf {
+    /// Record one call. Returns true when the call is the
+    /// `SLOW_CALL_WARN_AFTER`th slow call in a row, so callers warn once per streak.
+    pub fn record(&mut self, elapsed: Duration) -> bool {
+        if self.samples.len() == PERF_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(elapsed);
+
+        if elapsed < SLOW_CALL_THRESHOLD {
+            self.consecutive_slow = 0;
+            return false;
+        }
+        self.consecutive_slow += 1;
+        self.consecutive_slow == SLOW_CALL_WARN_AFTER
+    }
+
+    pub fn stats(&self) -> CollectorPerfStats {
+        if self.samples.is_empty() {
+            return CollectorPerfStats::default();
+        }
+        let mut sorted: Vec<Duration> = self.samples.iter().copied().collect();
+        sorted.sort();
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let total: Duration = sorted.iter().sum();
+        // Nearest-rank percentile
+        let p95_index = (sorted.len() * 95).div_ceil(100) - 1;
+        CollectorPerfStats {
+            samples: sorted.len(),
+            avg_ms: ms(total) / sorted.len() as f64,
+            p95_ms: ms(sorted[p95_index]),
+            max_ms: ms(sorted[sorted.len() - 1]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_and_slow_streak() {
+        let mut perf = CollectorPerf::default();
+        assert_eq!(perf.stats(), CollectorPerfStats::default());
+
+        for ms in 1..=20 {
+            assert!(!perf.record(Duration::from_millis(ms)));
+        }
+        let stats = perf.stats();
+        assert_eq!(stats.samples, 20);
+        assert!((stats.avg_ms - 10.5).abs() < 1e-9);
+        assert!((stats.p95_ms - 19.0).abs() < 1e-9);
+        assert!((stats.max_ms - 20.0).abs() < 1e-9);
+
+        // Warns once on the third slow call in a row, and again after a fast call resets the streak
+        let slow = SLOW_CALL_THRESHOLD;
+        assert!(!perf.record(slow));
+        assert!(!perf.record(slow));
+        assert!(perf.record(slow));
+        assert!(!perf.record(slow));
+        assert!(!perf.record(Duration::ZERO));
+        assert!(!perf.record(slow));
+        assert!(!perf.record(slow));
+        assert!(perf.record(slow));
+    }
+}
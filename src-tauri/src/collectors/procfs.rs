@@ -0,0 +1,41 @@
+//! Process details read from `/proc` on Linux.
+
+/// Longest command name the kernel keeps in `/proc/<pid>/comm`
+pub const COMM_MAX_LEN: usize = 15;
+
+/// Full path of a process's executable, via the `/proc/<pid>/exe` symlink.
+/// None if the process is gone or belongs to another user.
+pub fn process_exe_path(pid: u32) -> Option<String> {
+    let path = std::fs::read_link(format!("/proc/{}/exe", pid)).ok()?;
+    Some(path.to_string_lossy().into_owned())
+}
+
+/// Command name of a process from `/proc/<pid>/comm` (at most `COMM_MAX_LEN`
+/// bytes, as truncated by the kernel). None if the process is gone.
+pub fn process_comm(pid: u32) -> Option<String> {
+    let comm = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    let comm = comm.trim_end_matches('\n');
+    (!comm.is_empty()).then(|| comm.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_own_exe_path() {
+        let expected = std::env::current_exe().unwrap();
+        assert_eq!(process_exe_path(std::process::id()), Some(expected.to_string_lossy().into_owned()));
+        assert_eq!(process_exe_path(u32::MAX), None);
+    }
+
+    #[test]
+    fn test_own_comm() {
+        let exe = std::env::current_exe().unwrap();
+        let name = exe.file_name().unwrap().to_string_lossy();
+        let comm = process_comm(std::process::id()).unwrap();
+        assert!(!comm.is_empty() && comm.len() <= COMM_MAX_LEN);
+        assert!(name.starts_with(&comm));
+        assert_eq!(process_comm(u32::MAX), None);
+    }
+}
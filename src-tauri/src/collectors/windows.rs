@@ -1,20 +1,65 @@
-use crate::collectors::ForegroundCollector;
+use crate::collectors::{ForegroundCollector, IdleInputMode};
 use crate::models::AppInfo;
+use std::sync::Mutex;
+
+#[cfg(target_os = "windows")]
+use crate::collectors::{process_matches, select_idle_seconds};
+#[cfg(target_os = "windows")]
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
 #[cfg(target_os = "windows")]
 use windows::{
-    Win32::Foundation::HWND,
-    Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId},
-    Win32::System::Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ},
-    Win32::System::ProcessStatus::GetModuleBaseNameW,
+    core::{HSTRING, PWSTR},
+    Win32::Foundation::{ERROR_SUCCESS, HWND, LPARAM, LRESULT, WPARAM},
+    Win32::System::Registry::{
+        RegCloseKey, RegEnumKeyExW, RegOpenKeyExW, RegQueryValueExW, HKEY, HKEY_CURRENT_USER, KEY_READ,
+    },
+    Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, GetForegroundWindow, GetMessageW, GetWindowTextW, GetWindowThreadProcessId,
+        SetWindowsHookExW, HHOOK, MSG, WH_KEYBOARD_LL, WH_MOUSE_LL, WM_KEYDOWN, WM_LBUTTONDOWN, WM_MBUTTONDOWN,
+        WM_MOUSEHWHEEL, WM_MOUSEWHEEL, WM_RBUTTONDOWN, WM_SYSKEYDOWN, WM_XBUTTONDOWN,
+    },
+    Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ,
+    },
+    Win32::System::ProcessStatus::{EnumProcesses, GetModuleBaseNameW},
+    Win32::System::SystemInformation::GetTickCount,
     Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO},
 };
 
-pub struct WindowsCollector;
+/// Tick counts of the last keyboard/mouse event seen by the low-level hooks
+#[cfg(target_os = "windows")]
+static LAST_KEYBOARD_TICK: AtomicU32 = AtomicU32::new(0);
+#[cfg(target_os = "windows")]
+static LAST_MOUSE_TICK: AtomicU32 = AtomicU32::new(0);
+#[cfg(target_os = "windows")]
+static HOOKS_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Key presses, clicks and wheel turns since the last input rate sample, and
+/// the tick count of that sample
+#[cfg(target_os = "windows")]
+static INPUT_EVENTS: AtomicU32 = AtomicU32::new(0);
+#[cfg(target_os = "windows")]
+static LAST_RATE_SAMPLE_TICK: AtomicU32 = AtomicU32::new(0);
+
+pub struct WindowsCollector {
+    idle_mode: Mutex<IdleInputMode>,
+}
 
 impl WindowsCollector {
     pub fn new() -> Self {
-        Self
+        #[cfg(target_os = "windows")]
+        install_input_hooks();
+
+        Self {
+            idle_mode: Mutex::new(IdleInputMode::default()),
+        }
+    }
+}
+
+impl Default for WindowsCollector {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -44,47 +89,274 @@ impl ForegroundCollector for WindowsCollector {
                 return None;
             }
 
-            // Get process name
-            let process_name = get_process_name(process_id).unwrap_or_else(|| "Unknown".to_string());
+            // Get process name and executable path
+            let (process_name, process_path) =
+                get_process_info(process_id).unwrap_or_else(|| ("Unknown".to_string(), None));
 
             Some(AppInfo {
                 process_name,
                 app_title,
                 bundle_id: None,
+                process_path,
             })
         }
     }
 
     fn get_idle_seconds(&self) -> u64 {
-        unsafe {
-            let mut last_input = LASTINPUTINFO {
-                cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
-                dwTime: 0,
-            };
+        let mode = *self.idle_mode.lock().unwrap();
 
-            if GetLastInputInfo(&mut last_input).as_bool() {
-                let tick_count = windows::Win32::System::SystemInformation::GetTickCount();
-                let idle_ms = tick_count.saturating_sub(last_input.dwTime);
-                (idle_ms / 1000) as u64
-            } else {
-                0
+        // Without hooks we can't tell devices apart, so use the combined reading
+        if mode == IdleInputMode::Combined || !HOOKS_INSTALLED.load(Ordering::Relaxed) {
+            return last_input_idle_seconds();
+        }
+
+        let now = unsafe { GetTickCount() };
+        let keyboard_idle = now.saturating_sub(LAST_KEYBOARD_TICK.load(Ordering::Relaxed)) / 1000;
+        let mouse_idle = now.saturating_sub(LAST_MOUSE_TICK.load(Ordering::Relaxed)) / 1000;
+        select_idle_seconds(mode, keyboard_idle as u64, mouse_idle as u64)
+    }
+
+    fn set_idle_input_mode(&self, mode: IdleInputMode) {
+        *self.idle_mode.lock().unwrap() = mode;
+    }
+
+    fn is_in_call(&self) -> bool {
+        CALL_CAPABILITIES.iter().any(|capability| capability_in_use(capability))
+    }
+
+    fn is_any_process_running(&self, patterns: &[String]) -> bool {
+        running_process_ids().into_iter().any(|process_id| {
+            unsafe { get_process_info(process_id) }
+                .is_some_and(|(process_name, _)| process_matches(&process_name, patterns))
+        })
+    }
+
+    fn get_input_rate(&self) -> Option<f64> {
+        if !HOOKS_INSTALLED.load(Ordering::Relaxed) {
+            return None;
+        }
+        let now = unsafe { GetTickCount() };
+        let elapsed_ms = now.wrapping_sub(LAST_RATE_SAMPLE_TICK.swap(now, Ordering::Relaxed));
+        let events = INPUT_EVENTS.swap(0, Ordering::Relaxed);
+        (elapsed_ms > 0).then(|| f64::from(events) * 60_000.0 / f64::from(elapsed_ms))
+    }
+
+    fn name(&self) -> &'static str {
+        "windows"
+    }
+
+    fn supports_window_titles(&self) -> bool {
+        true
+    }
+
+    fn supports_idle(&self) -> bool {
+        true
+    }
+
+    fn supports_call_detection(&self) -> bool {
+        true
+    }
+
+    fn supports_process_enumeration(&self) -> bool {
+        true
+    }
+}
+
+/// IDs of all running processes, growing the buffer until it holds them all
+#[cfg(target_os = "windows")]
+fn running_process_ids() -> Vec<u32> {
+    let mut ids = vec![0u32; 1024];
+    loop {
+        let capacity = (ids.len() * std::mem::size_of::<u32>()) as u32;
+        let mut returned = 0u32;
+        if unsafe { EnumProcesses(ids.as_mut_ptr(), capacity, &mut returned) }.is_err() {
+            return Vec::new();
+        }
+        if returned < capacity {
+            ids.truncate(returned as usize / std::mem::size_of::<u32>());
+            return ids;
+        }
+        ids.resize(ids.len() * 2, 0);
+    }
+}
+
+/// Device capabilities whose use indicates an ongoing call
+#[cfg(target_os = "windows")]
+const CALL_CAPABILITIES: [&str; 2] = ["microphone", "webcam"];
+
+#[cfg(target_os = "windows")]
+const CONSENT_STORE: &str = r"Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore";
+
+/// Whether any app is currently using a capability. Windows records each app's
+/// use in the consent store; `LastUsedTimeStop` stays 0 while the device is held.
+/// Packaged apps are direct children of the capability key, desktop apps sit
+/// under `NonPackaged`.
+#[cfg(target_os = "windows")]
+fn capability_in_use(capability: &str) -> bool {
+    let Some(root) = RegKey::open(HKEY_CURRENT_USER, &format!(r"{}\{}", CONSENT_STORE, capability)) else {
+        return false;
+    };
+    root.subkeys().iter().any(|name| {
+        let Some(key) = RegKey::open(root.0, name) else {
+            return false;
+        };
+        if name == "NonPackaged" {
+            key.subkeys()
+                .iter()
+                .any(|app| RegKey::open(key.0, app).is_some_and(|k| k.is_in_use()))
+        } else {
+            key.is_in_use()
+        }
+    })
+}
+
+/// Registry key handle closed on drop
+#[cfg(target_os = "windows")]
+struct RegKey(HKEY);
+
+#[cfg(target_os = "windows")]
+impl RegKey {
+    fn open(parent: HKEY, path: &str) -> Option<Self> {
+        let mut key = HKEY::default();
+        let status = unsafe { RegOpenKeyExW(parent, &HSTRING::from(path), 0, KEY_READ, &mut key) };
+        (status == ERROR_SUCCESS).then_some(Self(key))
+    }
+
+    fn subkeys(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut buf = [0u16; 512];
+        for index in 0.. {
+            let mut len = buf.len() as u32;
+            let status = unsafe {
+                RegEnumKeyExW(self.0, index, PWSTR(buf.as_mut_ptr()), &mut len, None, PWSTR::null(), None, None)
+            };
+            if status != ERROR_SUCCESS {
+                break;
             }
+            names.push(String::from_utf16_lossy(&buf[..len as usize]));
+        }
+        names
+    }
+
+    fn read_u64(&self, value: &str) -> Option<u64> {
+        let mut data = 0u64;
+        let mut size = std::mem::size_of::<u64>() as u32;
+        let status = unsafe {
+            RegQueryValueExW(
+                self.0,
+                &HSTRING::from(value),
+                None,
+                None,
+                Some(&mut data as *mut u64 as *mut u8),
+                Some(&mut size),
+            )
+        };
+        (status == ERROR_SUCCESS).then_some(data)
+    }
+
+    /// Started using the capability and hasn't stopped yet
+    fn is_in_use(&self) -> bool {
+        self.read_u64("LastUsedTimeStart").unwrap_or(0) > 0 && self.read_u64("LastUsedTimeStop") == Some(0)
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for RegKey {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = RegCloseKey(self.0);
+        }
+    }
+}
+
+/// Idle time from any input, via `GetLastInputInfo`
+#[cfg(target_os = "windows")]
+fn last_input_idle_seconds() -> u64 {
+    unsafe {
+        let mut last_input = LASTINPUTINFO {
+            cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+            dwTime: 0,
+        };
+
+        if GetLastInputInfo(&mut last_input).as_bool() {
+            let tick_count = GetTickCount();
+            let idle_ms = tick_count.saturating_sub(last_input.dwTime);
+            (idle_ms / 1000) as u64
+        } else {
+            0
         }
     }
 }
 
+/// Install low-level keyboard and mouse hooks on a dedicated message-loop thread
+/// so the last input time of each device can be tracked separately.
 #[cfg(target_os = "windows")]
-unsafe fn get_process_name(process_id: u32) -> Option<String> {
+fn install_input_hooks() {
+    static START: std::sync::Once = std::sync::Once::new();
+    START.call_once(|| {
+        std::thread::spawn(|| unsafe {
+            let now = GetTickCount();
+            LAST_KEYBOARD_TICK.store(now, Ordering::Relaxed);
+            LAST_MOUSE_TICK.store(now, Ordering::Relaxed);
+            LAST_RATE_SAMPLE_TICK.store(now, Ordering::Relaxed);
+
+            let keyboard = SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_hook), None, 0);
+            let mouse = SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook), None, 0);
+            if keyboard.is_err() || mouse.is_err() {
+                eprintln!("[Collector] Failed to install input hooks, using combined idle");
+                return;
+            }
+            HOOKS_INSTALLED.store(true, Ordering::Relaxed);
+
+            // Low-level hooks are called on this thread, so keep pumping messages
+            let mut msg = MSG::default();
+            while GetMessageW(&mut msg, None, 0, 0).as_bool() {}
+        });
+    });
+}
+
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn keyboard_hook(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    LAST_KEYBOARD_TICK.store(GetTickCount(), Ordering::Relaxed);
+    // Key repeat sends more key-downs while held; those still reflect typing
+    if matches!(wparam.0 as u32, WM_KEYDOWN | WM_SYSKEYDOWN) {
+        INPUT_EVENTS.fetch_add(1, Ordering::Relaxed);
+    }
+    CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+}
+
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn mouse_hook(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    LAST_MOUSE_TICK.store(GetTickCount(), Ordering::Relaxed);
+    // Movement arrives as a stream of events, so only clicks and wheel turns count
+    if matches!(
+        wparam.0 as u32,
+        WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN | WM_XBUTTONDOWN | WM_MOUSEWHEEL | WM_MOUSEHWHEEL
+    ) {
+        INPUT_EVENTS.fetch_add(1, Ordering::Relaxed);
+    }
+    CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+}
+
+/// Executable base name of a process, plus its full path when it can be read
+#[cfg(target_os = "windows")]
+unsafe fn get_process_info(process_id: u32) -> Option<(String, Option<String>)> {
     let handle = OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, process_id).ok()?;
     
     let mut name_buf = [0u16; 260];
     let len = GetModuleBaseNameW(handle, None, &mut name_buf);
+
+    let mut path_buf = [0u16; 1024];
+    let mut path_len = path_buf.len() as u32;
+    let process_path = QueryFullProcessImageNameW(handle, PROCESS_NAME_WIN32, PWSTR(path_buf.as_mut_ptr()), &mut path_len)
+        .ok()
+        .map(|()| String::from_utf16_lossy(&path_buf[..path_len as usize]));
     
     // Close the handle
     let _ = windows::Win32::Foundation::CloseHandle(handle);
 
     if len > 0 {
-        Some(String::from_utf16_lossy(&name_buf[..len as usize]))
+        Some((String::from_utf16_lossy(&name_buf[..len as usize]), process_path))
     } else {
         None
     }
@@ -99,4 +371,23 @@ impl ForegroundCollector for WindowsCollector {
     fn get_idle_seconds(&self) -> u64 {
         0
     }
+
+    fn set_idle_input_mode(&self, mode: IdleInputMode) {
+        *self.idle_mode.lock().unwrap() = mode;
+    }
+}
+
+#[cfg(all(test, target_os = "windows"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = WindowsCollector::new().capabilities();
+        assert_eq!(capabilities.collector, "windows");
+        assert!(capabilities.window_titles);
+        assert!(capabilities.idle);
+        assert!(!capabilities.bundle_id);
+        assert!(!capabilities.icons);
+    }
 }
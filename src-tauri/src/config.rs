@@ -0,0 +1,302 @@
+use crate::collectors::IdleInputMode;
+use crate::scheduler::SchedulerConfig;
+use crate::sessionizer::debounce::DEFAULT_REQUIRED_TICKS;
+use crate::sessionizer::{AppIdSource, SessionKey, SessionizerConfig};
+use crate::storage::{Database, DbError};
+use crate::timezone::{DayAttribution, ReportTimezone};
+use chrono::Weekday;
+use std::collections::HashMap;
+
+/// Keys of settings stored in the `settings` table
+pub mod keys {
+    pub const REPORT_TIMEZONE: &str = "report_timezone";
+    pub const DAY_ATTRIBUTION: &str = "day_attribution";
+    pub const IDLE_THRESHOLD_SECONDS: &str = "idle_threshold_seconds";
+    pub const SOFT_IDLE_SECONDS: &str = "soft_idle_seconds";
+    pub const IDLE_HYSTERESIS_SECONDS: &str = "idle_hysteresis_seconds";
+    pub const IDLE_THRESHOLD_BY_WEEKDAY: &str = "idle_threshold_by_weekday";
+    pub const MIN_PERSIST_SECONDS: &str = "min_persist_seconds";
+    pub const CALL_KEEPS_ACTIVE: &str = "call_keeps_active";
+    pub const SESSION_KEY: &str = "session_key";
+    /// Replaced by `SESSION_KEY`; `true` is read as `SessionKey::ProcessAndTitle`
+    pub const SPLIT_ON_TITLE_CHANGE: &str = "split_on_title_change";
+    pub const INTERRUPTION_MAX_SECONDS: &str = "interruption_max_seconds";
+    pub const MAX_IDLE_SESSION_SECONDS: &str = "max_idle_session_seconds";
+    pub const MISSING_READING_TOLERANCE: &str = "missing_reading_tolerance";
+    pub const FOREGROUND_DEBOUNCE_TICKS: &str = "foreground_debounce_ticks";
+    pub const IDLE_INPUT_MODE: &str = "idle_input_mode";
+    pub const APP_ID_SOURCES: &str = "app_id_sources";
+    pub const TITLE_CAPTURE_ALLOWLIST: &str = "title_capture_allowlist";
+    pub const TREAT_AS_ACTIVE: &str = "treat_as_active";
+    pub const NOTIFY_NEW_APPS: &str = "notify_new_apps";
+    pub const RECORD_TAPE: &str = "record_tape";
+    pub const GROUP_NOTIFICATIONS: &str = "group_notifications";
+    pub const RECORD_COLLECTOR_ERRORS: &str = "record_collector_errors";
+    pub const LOG_HEARTBEAT_SECS: &str = "log_heartbeat_secs";
+}
+
+/// User settings loaded from the database at startup.
+/// Missing or invalid settings keep their defaults.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub report_timezone: ReportTimezone,
+    pub day_attribution: DayAttribution,
+    pub idle_threshold_seconds: u64,
+    pub soft_idle_seconds: u64,
+    pub idle_hysteresis_seconds: u64,
+    pub idle_threshold_by_weekday: HashMap<Weekday, u64>,
+    pub min_persist_seconds: i64,
+    pub call_keeps_active: bool,
+    pub session_key: SessionKey,
+    pub interruption_max_seconds: u64,
+    pub max_idle_session_seconds: u64,
+    pub missing_reading_tolerance: u32,
+    pub foreground_debounce_ticks: u32,
+    pub idle_input_mode: IdleInputMode,
+    /// Per-process source of `app_id`; unlisted processes use the process name
+    pub app_id_sources: HashMap<String, AppIdSource>,
+    pub title_capture_allowlist: Option<Vec<String>>,
+    /// Processes that hold off idle while running, foreground or not
+    pub treat_as_active: Vec<String>,
+    /// Notify the first time a never-seen app shows up in a session
+    pub notify_new_apps: bool,
+    /// Append each tick's collector readings to a tape for replay
+    pub record_tape: bool,
+    /// Combine a tick's schedule alerts into a single notification
+    pub group_notifications: bool,
+    /// Count ticks and record collector failures for reliability stats
+    pub record_collector_errors: bool,
+    /// Seconds between unchanged compliance logs; None uses each schedule's check interval
+    pub log_heartbeat_secs: Option<u32>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let sessionizer = SessionizerConfig::default();
+        Self {
+            report_timezone: ReportTimezone::default(),
+            day_attribution: DayAttribution::default(),
+            idle_threshold_seconds: sessionizer.idle_threshold_seconds,
+            soft_idle_seconds: sessionizer.soft_idle_seconds,
+            idle_hysteresis_seconds: sessionizer.idle_hysteresis_seconds,
+            idle_threshold_by_weekday: sessionizer.idle_threshold_by_weekday,
+            min_persist_seconds: sessionizer.min_persist_seconds,
+            call_keeps_active: sessionizer.call_keeps_active,
+            session_key: sessionizer.session_key,
+            interruption_max_seconds: sessionizer.interruption_max_seconds,
+            max_idle_session_seconds: sessionizer.max_idle_session_seconds,
+            missing_reading_tolerance: sessionizer.missing_reading_tolerance,
+            foreground_debounce_ticks: DEFAULT_REQUIRED_TICKS,
+            idle_input_mode: IdleInputMode::default(),
+            app_id_sources: HashMap::new(),
+            title_capture_allowlist: None,
+            treat_as_active: Vec::new(),
+            notify_new_apps: false,
+            record_tape: false,
+            group_notifications: false,
+            record_collector_errors: true,
+            log_heartbeat_secs: SchedulerConfig::default().log_heartbeat_secs,
+        }
+    }
+}
+
+/// Keep `current` if a setting is missing or can't be read
+fn or_default<T>(key: &str, value: Result<Option<T>, DbError>, current: T) -> T {
+    match value {
+        Ok(Some(value)) => value,
+        Ok(None) => current,
+        Err(e) => {
+            eprintln!("[Config] Ignoring setting {}: {}", key, e);
+            current
+        }
+    }
+}
+
+impl Config {
+    /// Load settings from the database on top of the defaults
+    pub fn load(db: &Database) -> Self {
+        let defaults = Self::default();
+
+        let report_timezone = db
+            .get_setting(keys::REPORT_TIMEZONE)
+            .map_err(DbError::from)
+            .and_then(|name| {
+                name.map(|name| ReportTimezone::parse(&name).map_err(DbError::InvalidInput))
+                    .transpose()
+            });
+
+        let split_on_title_change = or_default(
+            keys::SPLIT_ON_TITLE_CHANGE,
+            db.get_setting_parsed(keys::SPLIT_ON_TITLE_CHANGE),
+            false,
+        );
+        let legacy_session_key = if split_on_title_change {
+            SessionKey::ProcessAndTitle
+        } else {
+            defaults.session_key
+        };
+
+        Self {
+            report_timezone: or_default(keys::REPORT_TIMEZONE, report_timezone, defaults.report_timezone),
+            day_attribution: or_default(
+                keys::DAY_ATTRIBUTION,
+                db.get_setting_json(keys::DAY_ATTRIBUTION),
+                defaults.day_attribution,
+            ),
+            idle_threshold_seconds: or_default(
+                keys::IDLE_THRESHOLD_SECONDS,
+                db.get_setting_parsed(keys::IDLE_THRESHOLD_SECONDS),
+                defaults.idle_threshold_seconds,
+            ),
+            soft_idle_seconds: or_default(
+                keys::SOFT_IDLE_SECONDS,
+                db.get_setting_parsed(keys::SOFT_IDLE_SECONDS),
+                defaults.soft_idle_seconds,
+            ),
+            idle_hysteresis_seconds: or_default(
+                keys::IDLE_HYSTERESIS_SECONDS,
+                db.get_setting_parsed(keys::IDLE_HYSTERESIS_SECONDS),
+                defaults.idle_hysteresis_seconds,
+            ),
+            idle_threshold_by_weekday: or_default(
+                keys::IDLE_THRESHOLD_BY_WEEKDAY,
+                db.get_setting_json(keys::IDLE_THRESHOLD_BY_WEEKDAY),
+                defaults.idle_threshold_by_weekday,
+            ),
+            min_persist_seconds: or_default(
+                keys::MIN_PERSIST_SECONDS,
+                db.get_setting_parsed(keys::MIN_PERSIST_SECONDS),
+                defaults.min_persist_seconds,
+            ),
+            call_keeps_active: or_default(
+                keys::CALL_KEEPS_ACTIVE,
+                db.get_setting_parsed(keys::CALL_KEEPS_ACTIVE),
+                defaults.call_keeps_active,
+            ),
+            session_key: or_default(keys::SESSION_KEY, db.get_setting_json(keys::SESSION_KEY), legacy_session_key),
+            interruption_max_seconds: or_default(
+                keys::INTERRUPTION_MAX_SECONDS,
+                db.get_setting_parsed(keys::INTERRUPTION_MAX_SECONDS),
+                defaults.interruption_max_seconds,
+            ),
+            max_idle_session_seconds: or_default(
+                keys::MAX_IDLE_SESSION_SECONDS,
+                db.get_setting_parsed(keys::MAX_IDLE_SESSION_SECONDS),
+                defaults.max_idle_session_seconds,
+            ),
+            missing_reading_tolerance: or_default(
+                keys::MISSING_READING_TOLERANCE,
+                db.get_setting_parsed(keys::MISSING_READING_TOLERANCE),
+                defaults.missing_reading_tolerance,
+            ),
+            foreground_debounce_ticks: or_default(
+                keys::FOREGROUND_DEBOUNCE_TICKS,
+                db.get_setting_parsed(keys::FOREGROUND_DEBOUNCE_TICKS),
+                defaults.foreground_debounce_ticks,
+            ),
+            idle_input_mode: or_default(
+                keys::IDLE_INPUT_MODE,
+                db.get_setting_json(keys::IDLE_INPUT_MODE),
+                defaults.idle_input_mode,
+            ),
+            app_id_sources: or_default(
+                keys::APP_ID_SOURCES,
+                db.get_setting_json(keys::APP_ID_SOURCES),
+                defaults.app_id_sources,
+            ),
+            title_capture_allowlist: or_default(
+                keys::TITLE_CAPTURE_ALLOWLIST,
+                db.get_setting_json(keys::TITLE_CAPTURE_ALLOWLIST),
+                defaults.title_capture_allowlist,
+            ),
+            treat_as_active: or_default(
+                keys::TREAT_AS_ACTIVE,
+                db.get_setting_json(keys::TREAT_AS_ACTIVE),
+                defaults.treat_as_active,
+            ),
+            notify_new_apps: or_default(
+                keys::NOTIFY_NEW_APPS,
+                db.get_setting_parsed(keys::NOTIFY_NEW_APPS),
+                defaults.notify_new_apps,
+            ),
+            record_tape: or_default(
+                keys::RECORD_TAPE,
+                db.get_setting_parsed(keys::RECORD_TAPE),
+                defaults.record_tape,
+            ),
+            group_notifications: or_default(
+                keys::GROUP_NOTIFICATIONS,
+                db.get_setting_parsed(keys::GROUP_NOTIFICATIONS),
+                defaults.group_notifications,
+            ),
+            record_collector_errors: or_default(
+                keys::RECORD_COLLECTOR_ERRORS,
+                db.get_setting_parsed(keys::RECORD_COLLECTOR_ERRORS),
+                defaults.record_collector_errors,
+            ),
+            log_heartbeat_secs: or_default(
+                keys::LOG_HEARTBEAT_SECS,
+                db.get_setting_parsed(keys::LOG_HEARTBEAT_SECS).map(|secs| secs.map(Some)),
+                defaults.log_heartbeat_secs,
+            ),
+        }
+    }
+
+    /// Sessionizer settings derived from this config
+    pub fn sessionizer_config(&self) -> SessionizerConfig {
+        SessionizerConfig {
+            idle_threshold_seconds: self.idle_threshold_seconds,
+            soft_idle_seconds: self.soft_idle_seconds,
+            idle_hysteresis_seconds: self.idle_hysteresis_seconds,
+            idle_threshold_by_weekday: self.idle_threshold_by_weekday.clone(),
+            min_persist_seconds: self.min_persist_seconds,
+            call_keeps_active: self.call_keeps_active,
+            session_key: self.session_key,
+            interruption_max_seconds: self.interruption_max_seconds,
+            max_idle_session_seconds: self.max_idle_session_seconds,
+            missing_reading_tolerance: self.missing_reading_tolerance,
+        }
+    }
+
+    /// Scheduler settings derived from this config
+    pub fn scheduler_config(&self) -> SchedulerConfig {
+        SchedulerConfig {
+            log_heartbeat_secs: self.log_heartbeat_secs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_applies_stored_settings() {
+        let db = Database::in_memory();
+        db.set_setting(keys::REPORT_TIMEZONE, "Europe/Berlin").unwrap();
+        db.set_setting(keys::IDLE_THRESHOLD_SECONDS, "120").unwrap();
+        db.set_setting_json(keys::IDLE_INPUT_MODE, &IdleInputMode::KeyboardOnly).unwrap();
+        // Invalid values fall back to the default
+        db.set_setting(keys::FOREGROUND_DEBOUNCE_TICKS, "lots").unwrap();
+        db.set_setting(keys::LOG_HEARTBEAT_SECS, "300").unwrap();
+
+        let config = Config::load(&db);
+        assert_eq!(config.report_timezone.name(), Some("Europe/Berlin"));
+        assert_eq!(config.sessionizer_config().idle_threshold_seconds, 120);
+        assert_eq!(config.idle_input_mode, IdleInputMode::KeyboardOnly);
+        assert_eq!(config.foreground_debounce_ticks, DEFAULT_REQUIRED_TICKS);
+        assert_eq!(config.title_capture_allowlist, None);
+        assert_eq!(config.scheduler_config().log_heartbeat_secs, Some(300));
+        assert_eq!(Config::default().log_heartbeat_secs, None);
+    }
+
+    #[test]
+    fn test_legacy_split_on_title_change_maps_to_session_key() {
+        let db = Database::in_memory();
+        db.set_setting(keys::SPLIT_ON_TITLE_CHANGE, "true").unwrap();
+        assert_eq!(Config::load(&db).session_key, SessionKey::ProcessAndTitle);
+
+        db.set_setting_json(keys::SESSION_KEY, &SessionKey::ProcessAndDocument).unwrap();
+        assert_eq!(Config::load(&db).session_key, SessionKey::ProcessAndDocument);
+    }
+}
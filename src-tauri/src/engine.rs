@@ -0,0 +1,769 @@
+//! The tracking engine: polls the collector, turns readings into sessions,
+//! persists them and evaluates schedules. It has no Tauri dependency, so it can
+//! be embedded as a library or driven tick by tick in tests.
+
+use crate::collectors::perf::{CollectorPerf, SLOW_CALL_THRESHOLD};
+use crate::collectors::{CollectorFailure, ForegroundCollector};
+use crate::feed::SessionFeed;
+use crate::models::{AppInfo, NeglectReminder, Schedule, Session};
+use crate::recovery;
+use crate::scheduler::engine::end_notice_due;
+use crate::scheduler::{neglect, SchedulerEngine};
+use crate::sessionizer::{self, AppIdSource, ForegroundDebouncer, SessionState, Sessionizer};
+use crate::storage::{self, Database};
+use crate::tape::TapeRecorder;
+use crate::webhooks::{ViolationPayload, WebhookClient};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// How often neglect reminders are checked
+const NEGLECT_CHECK_INTERVAL_SECS: i64 = 60;
+
+/// A schedule that was violated long enough to warrant a notification
+#[derive(Debug, Clone)]
+pub struct ScheduleAlert {
+    pub schedule: Schedule,
+    pub current_app: String,
+}
+
+/// What happened during one tick, for the embedding layer to act on
+#[derive(Debug, Clone, Default)]
+pub struct TickOutcome {
+    /// Sessions completed by this tick
+    pub completed_sessions: Vec<Session>,
+    /// Apps seen in a session for the first time
+    pub new_apps: Vec<String>,
+    /// Schedules whose grace period ran out
+    pub alerts: Vec<ScheduleAlert>,
+    /// Neglected apps to remind the user about
+    pub neglect_reminders: Vec<NeglectReminder>,
+    /// Schedules whose last day passed, now disabled
+    pub ended_schedules: Vec<Schedule>,
+}
+
+/// Owns the collector, sessionizer, scheduler and database
+pub struct TrackingEngine {
+    pub collector: Arc<dyn ForegroundCollector>,
+    pub sessionizer: Arc<Mutex<Sessionizer>>,
+    pub database: Arc<Mutex<Database>>,
+    pub scheduler_engine: Arc<SchedulerEngine>,
+    /// Suppresses brief foreground flicker before readings reach the sessionizer
+    pub debouncer: Arc<Mutex<ForegroundDebouncer>>,
+    /// Processes whose `app_id` comes from somewhere other than the process name
+    pub app_id_sources: Arc<std::sync::Mutex<HashMap<String, AppIdSource>>>,
+    /// Processes whose window titles are recorded (None = all)
+    pub title_capture_allowlist: Arc<std::sync::Mutex<Option<Vec<String>>>>,
+    /// Processes that keep the user counted as active while they run, even in
+    /// the background (e.g. a render or a build)
+    pub treat_as_active: Arc<std::sync::Mutex<Vec<String>>>,
+    /// Completed sessions for in-process subscribers
+    pub session_feed: SessionFeed,
+    /// Latency of recent `get_foreground_app` calls
+    pub collector_perf: std::sync::Mutex<CollectorPerf>,
+    /// Count each tick, and record collector failures, for reliability stats
+    pub record_collector_errors: AtomicBool,
+    /// Sends schedules' violation webhooks
+    webhooks: WebhookClient,
+    /// Appends each tick's readings while record mode is on
+    tape: std::sync::Mutex<Option<TapeRecorder>>,
+    /// Last neglect reminder check, and when each reminder last fired
+    neglect_checked: std::sync::Mutex<Option<DateTime<Utc>>>,
+    neglect_reminded: std::sync::Mutex<HashMap<i64, DateTime<Utc>>>,
+    /// Local day schedule end dates were last checked
+    ends_checked: std::sync::Mutex<Option<NaiveDate>>,
+    /// Cancelled by `stop` to end the polling loop
+    shutdown: CancellationToken,
+    /// Handle to the polling loop task while it runs
+    polling_task: std::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl TrackingEngine {
+    pub fn new(
+        collector: Arc<dyn ForegroundCollector>,
+        sessionizer: Sessionizer,
+        database: Database,
+        scheduler_engine: Arc<SchedulerEngine>,
+        debouncer: ForegroundDebouncer,
+    ) -> Self {
+        Self {
+            collector,
+            sessionizer: Arc::new(Mutex::new(sessionizer)),
+            database: Arc::new(Mutex::new(database)),
+            scheduler_engine,
+            debouncer: Arc::new(Mutex::new(debouncer)),
+            app_id_sources: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            title_capture_allowlist: Arc::new(std::sync::Mutex::new(None)),
+            treat_as_active: Arc::new(std::sync::Mutex::new(Vec::new())),
+            session_feed: SessionFeed::default(),
+            collector_perf: std::sync::Mutex::new(CollectorPerf::default()),
+            record_collector_errors: AtomicBool::new(true),
+            webhooks: WebhookClient::default(),
+            tape: std::sync::Mutex::new(None),
+            neglect_checked: std::sync::Mutex::new(None),
+            neglect_reminded: std::sync::Mutex::new(HashMap::new()),
+            ends_checked: std::sync::Mutex::new(None),
+            shutdown: CancellationToken::new(),
+            polling_task: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// One iteration of the polling loop: session tracking plus schedule checks.
+    /// `now` drives session boundaries; schedule timers use the wall clock.
+    pub async fn tick(&self, now: DateTime<Utc>) -> TickOutcome {
+        let mut outcome = TickOutcome::default();
+
+        let started = std::time::Instant::now();
+        let foreground = self.collector.get_foreground_app();
+        let latency = started.elapsed();
+        self.record_collector_latency(latency);
+        let had_reading = foreground.is_some();
+        let raw_app = foreground.map(|info| {
+            // A tick that panicked while holding the lock must not poison every later tick
+            let sources = self.app_id_sources.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            // Resolved before redaction, which may drop the title it derives from
+            let info = sessionizer::app_id::resolve_app_id(info, &sources);
+            let allowlist = self
+                .title_capture_allowlist
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            sessionizer::privacy::redact_title(info, allowlist.as_deref())
+        });
+        let idle = if self.treat_as_active_running() { 0 } else { self.collector.get_idle_seconds() };
+        let in_call = self.collector.is_in_call();
+        let input_rate = self.collector.get_input_rate();
+        self.record_tape(now, raw_app.as_ref(), idle, in_call);
+        let app = self.debouncer.lock().await.filter(raw_app);
+
+        // Session tracking
+        let mut sessionizer = self.sessionizer.lock().await;
+        // Sampled before the update so a switch credits the input to the app it left
+        sessionizer.record_input_rate(input_rate);
+        let session_completed = sessionizer.update_at(app.clone(), idle, in_call, now);
+
+        if self.record_collector_errors.load(Ordering::Relaxed) {
+            // No app while idle is expected, e.g. on a locked screen
+            let failure = if latency >= SLOW_CALL_THRESHOLD {
+                Some(CollectorFailure::TimedOut)
+            } else if !had_reading && !matches!(sessionizer.current_state(), SessionState::Idle { .. }) {
+                Some(CollectorFailure::NoReading)
+            } else {
+                None
+            };
+            if let Err(e) = self.database.lock().await.record_collector_tick(now, failure) {
+                eprintln!("[DB Error] Failed to record collector tick: {}", e);
+            }
+        }
+
+        if session_completed {
+            let sessions = sessionizer.take_pending_sessions();
+            let mut db = self.database.lock().await;
+
+            // Reconnect once and resume from the first unsaved session if the DB goes bad mid-batch
+            let mut saved = 0;
+            let new_apps = &mut outcome.new_apps;
+            let result = db.with_reconnect(|db| {
+                while let Some(session) = sessions.get(saved) {
+                    match db.insert_or_merge_session(session) {
+                        Ok((id, merged)) => {
+                            println!(
+                                "[DB] {} session {} | {} | {} | {}s",
+                                if merged { "Merged" } else { "Saved" },
+                                id,
+                                session.app_id,
+                                if session.is_idle { "IDLE" } else { "ACTIVE" },
+                                session.duration_seconds.unwrap_or(0)
+                            );
+                            if !session.is_idle {
+                                if let Err(e) = db.update_totals_cache(session) {
+                                    eprintln!("[DB Error] Failed to update totals cache: {}", e);
+                                }
+                                match db.record_app_seen(&session.app_id, session.start_time) {
+                                    Ok(true) => new_apps.push(session.app_id.clone()),
+                                    Ok(false) => {}
+                                    Err(e) => eprintln!("[DB Error] Failed to record app: {}", e),
+                                }
+                            }
+                        }
+                        Err(e) if storage::db::is_recoverable(&e) => return Err(e),
+                        Err(e) => {
+                            eprintln!("[DB Error] Failed to save session: {}", e);
+                        }
+                    }
+                    saved += 1;
+                }
+                Ok(())
+            });
+            if let Err(e) = result {
+                eprintln!(
+                    "[DB Error] Gave up on {} unsaved session(s): {}",
+                    sessions.len() - saved,
+                    e
+                );
+            }
+            drop(db);
+
+            for session in &sessions {
+                self.session_feed.publish(session);
+            }
+            outcome.completed_sessions = sessions;
+        }
+        drop(sessionizer); // Release lock before scheduler check
+
+        // Schedule compliance checking (every 5 seconds to reduce overhead)
+        if idle.is_multiple_of(5) {
+            if let Some(ref current_app) = app {
+                let db = self.database.lock().await;
+                if let Ok(schedules) = db.get_enabled_schedules() {
+                    drop(db); // Release lock before evaluation
+
+                    for schedule in schedules {
+                        let (should_notify, _) = self.scheduler_engine.evaluate(&schedule, current_app);
+
+                        // Log compliance on transitions plus a periodic heartbeat
+                        let local_now = self.scheduler_engine.timezone().now_local();
+                        if self.scheduler_engine.is_within_schedule_at(&schedule, local_now) {
+                            let window = self.scheduler_engine.active_window_at(&schedule, local_now);
+                            let is_compliant = self.scheduler_engine.is_app_compliant(&schedule, window, current_app);
+                            if self.scheduler_engine.should_log(&schedule, is_compliant) {
+                                let db = self.database.lock().await;
+                                let _ = db.insert_compliance_log_at(
+                                    schedule.id.unwrap_or(0),
+                                    is_compliant,
+                                    Some(&current_app.process_name),
+                                    now,
+                                );
+                            }
+                        }
+
+                        if should_notify {
+                            println!(
+                                "[Schedule] Non-compliant: {} (expected {:?})",
+                                current_app.process_name, schedule.expected_apps
+                            );
+                            let db = self.database.lock().await;
+                            if let Err(e) = db.insert_notification_log_at(
+                                schedule.id.unwrap_or(0),
+                                Some(&current_app.process_name),
+                                now,
+                            ) {
+                                eprintln!("[DB Error] Failed to log notification: {}", e);
+                            }
+                            drop(db);
+                            if let Some(url) = schedule.webhook_url.clone().filter(|url| !url.is_empty()) {
+                                self.webhooks.spawn(
+                                    url,
+                                    ViolationPayload {
+                                        schedule_id: schedule.id.unwrap_or(0),
+                                        schedule_name: schedule.name.clone(),
+                                        current_app: current_app.process_name.clone(),
+                                        timestamp: now,
+                                    },
+                                );
+                            }
+                            outcome.alerts.push(ScheduleAlert {
+                                schedule,
+                                current_app: current_app.process_name.clone(),
+                            });
+                        }
+                    }
+
+                    // Persist grace/notification state so it survives a restart
+                    let dirty_states = self.scheduler_engine.take_dirty_states();
+                    if !dirty_states.is_empty() {
+                        let db = self.database.lock().await;
+                        for (schedule_id, schedule_state) in dirty_states {
+                            if let Err(e) = db.save_schedule_state(schedule_id, &schedule_state) {
+                                eprintln!("[DB Error] Failed to save schedule state: {}", e);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        outcome.neglect_reminders = self.check_neglect_reminders(now).await;
+        outcome.ended_schedules = self.check_schedule_ends(now).await;
+
+        // Debug: Print current app every 5 seconds
+        if idle.is_multiple_of(5) {
+            if let Some(ref info) = app {
+                println!("[Tracking] {} | Idle: {}s", info.process_name, idle);
+            }
+        }
+
+        outcome
+    }
+
+    /// Add a `get_foreground_app` duration to the rolling stats, warning when
+    /// the collector is repeatedly slow
+    fn record_collector_latency(&self, elapsed: Duration) {
+        let mut perf = self.collector_perf.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        if perf.record(elapsed) {
+            eprintln!(
+                "[Collector] {} is slow: {}ms (threshold {}ms)",
+                self.collector.name(),
+                elapsed.as_millis(),
+                SLOW_CALL_THRESHOLD.as_millis()
+            );
+        }
+    }
+
+    /// Start recording readings to `recorder`, or stop with None
+    pub fn set_tape(&self, recorder: Option<TapeRecorder>) {
+        *self.tape.lock().unwrap_or_else(std::sync::PoisonError::into_inner) = recorder;
+    }
+
+    /// Whether a `treat_as_active` process is running, which holds off idle
+    fn treat_as_active_running(&self) -> bool {
+        let patterns = self
+            .treat_as_active
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        !patterns.is_empty() && self.collector.is_any_process_running(&patterns)
+    }
+
+    /// Append this tick's readings to the tape, if recording. A failed write
+    /// stops recording rather than failing every later tick.
+    fn record_tape(&self, now: DateTime<Utc>, app: Option<&AppInfo>, idle: u64, in_call: bool) {
+        let mut tape = self.tape.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some(recorder) = tape.as_mut() {
+            if let Err(e) = recorder.record(now, app, idle, in_call) {
+                eprintln!("[Tape] Stopped recording: {}", e);
+                *tape = None;
+            }
+        }
+    }
+
+    /// Per-app totals of the local day containing `now`, including the session
+    /// still being tracked up to `now`
+    pub async fn app_totals_today_live(&self, now: DateTime<Utc>) -> Result<Vec<(String, i64)>, rusqlite::Error> {
+        // Locked in `tick`'s order and held while querying, so a tick can't
+        // persist the in-progress session in between and count it twice
+        let sessionizer = self.sessionizer.lock().await;
+        let in_progress = sessionizer.in_progress_at(now);
+        let db = self.database.lock().await;
+        let today = db.report_timezone().to_local(now).date();
+        db.get_day_app_totals_live(today, in_progress.as_ref())
+    }
+
+    /// Neglect reminders due at `now`, checked at most once a minute
+    async fn check_neglect_reminders(&self, now: DateTime<Utc>) -> Vec<NeglectReminder> {
+        {
+            let mut checked = self.neglect_checked.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            if checked.is_some_and(|at| (now - at).num_seconds() < NEGLECT_CHECK_INTERVAL_SECS) {
+                return Vec::new();
+            }
+            *checked = Some(now);
+        }
+
+        // The app in front now counts as used now, though its session isn't saved yet
+        let in_progress = self.sessionizer.lock().await.in_progress_at(now);
+        let db = self.database.lock().await;
+        let reminders = match db.get_all_neglect_reminders() {
+            Ok(reminders) => reminders,
+            Err(e) => {
+                eprintln!("[DB Error] Failed to load neglect reminders: {}", e);
+                return Vec::new();
+            }
+        };
+
+        let local_time = self.scheduler_engine.timezone().to_local(now).time();
+        let mut reminded = self.neglect_reminded.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut due = Vec::new();
+        for reminder in reminders {
+            let id = reminder.id.unwrap_or(0);
+            let last_used = match db.app_usage_bounds(&reminder.app_id) {
+                Ok(bounds) => bounds.map(|(_, last)| last).max(
+                    in_progress
+                        .as_ref()
+                        .filter(|session| session.app_id == reminder.app_id)
+                        .and_then(|session| session.end_time),
+                ),
+                Err(e) => {
+                    eprintln!("[DB Error] Failed to read last use of {}: {}", reminder.app_id, e);
+                    continue;
+                }
+            };
+            if neglect::reminder_due(&reminder, last_used, reminded.get(&id).copied(), now, local_time) {
+                reminded.insert(id, now);
+                due.push(reminder);
+            }
+        }
+        due
+    }
+
+    /// Disable schedules whose `active_until` day has passed, once per local day.
+    /// Returns them so the embedder can send each a one-time end notice.
+    async fn check_schedule_ends(&self, now: DateTime<Utc>) -> Vec<Schedule> {
+        let today = self.scheduler_engine.timezone().to_local(now).date();
+        {
+            let mut checked = self.ends_checked.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            if *checked == Some(today) {
+                return Vec::new();
+            }
+            *checked = Some(today);
+        }
+
+        let db = self.database.lock().await;
+        let schedules = match db.get_enabled_schedules() {
+            Ok(schedules) => schedules,
+            Err(e) => {
+                eprintln!("[DB Error] Failed to load schedules: {}", e);
+                return Vec::new();
+            }
+        };
+
+        let mut ended = Vec::new();
+        for schedule in schedules.into_iter().filter(|s| end_notice_due(s, today)) {
+            match db.mark_schedule_ended(schedule.id.unwrap_or(0)) {
+                Ok(()) => ended.push(schedule),
+                Err(e) => eprintln!("[DB Error] Failed to end schedule {}: {}", schedule.name, e),
+            }
+        }
+        ended
+    }
+
+    /// Spawn the polling loop on `runtime`, ticking once per `period` and handing
+    /// each outcome to `on_tick`. A panicking tick is recovered from and the loop
+    /// carries on. Does nothing if the loop is already running.
+    pub fn start<F>(self: &Arc<Self>, runtime: &tokio::runtime::Handle, period: Duration, on_tick: F)
+    where
+        F: Fn(TickOutcome) + Send + Sync + 'static,
+    {
+        let mut polling_task = self.polling_task.lock().unwrap();
+        if polling_task.is_some() {
+            return;
+        }
+
+        let engine = self.clone();
+        *polling_task = Some(runtime.spawn(async move {
+            let shutdown = engine.shutdown.clone();
+            run_until_cancelled(period, shutdown.clone(), || {
+                let engine = engine.clone();
+                let shutdown = shutdown.clone();
+                let on_tick = &on_tick;
+                async move {
+                    let tick = {
+                        let engine = engine.clone();
+                        async move { engine.tick(Utc::now()).await }
+                    };
+                    match recovery::run_tick_catching_panics(tick).await {
+                        Some(outcome) => on_tick(outcome),
+                        None => {
+                            recovery::recover_from_panic(
+                                &engine.sessionizer,
+                                &engine.database,
+                                recovery::PANIC_RESTART_DELAY,
+                                &shutdown,
+                            )
+                            .await
+                        }
+                    }
+                }
+            })
+            .await;
+            println!("[Tracking] Polling loop stopped");
+        }));
+    }
+
+    /// Stop the polling loop and wait for the current tick to finish
+    pub async fn stop(&self) {
+        self.shutdown.cancel();
+        let task = self.polling_task.lock().unwrap().take();
+        if let Some(task) = task {
+            let _ = task.await;
+        }
+    }
+}
+
+/// Call `tick` once per `period` until `shutdown` is cancelled
+async fn run_until_cancelled<F, Fut>(period: Duration, shutdown: CancellationToken, mut tick: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut interval = tokio::time::interval(period);
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => tick().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::mock::{MockCollector, MockReading};
+    use crate::sessionizer::SessionizerConfig;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn engine_with_script(script: Vec<MockReading>) -> TrackingEngine {
+        TrackingEngine::new(
+            Arc::new(MockCollector::new(script)),
+            Sessionizer::new(SessionizerConfig::default()),
+            Database::in_memory(),
+            Arc::new(SchedulerEngine::new()),
+            ForegroundDebouncer::new(1),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_ticks_persist_sessions() {
+        let engine = engine_with_script(vec![
+            MockReading::app("Code.exe"),
+            MockReading::app("Code.exe"),
+            MockReading::app("Code.exe"),
+            MockReading::app("Slack.exe"),
+            MockReading::app("Slack.exe"),
+            MockReading::none(),
+        ]);
+        let start = Utc::now() - chrono::Duration::hours(1);
+
+        let mut completed = Vec::new();
+        let mut new_apps = Vec::new();
+        for secs in 0..6 {
+            let outcome = engine.tick(start + chrono::Duration::seconds(secs * 10)).await;
+            completed.extend(outcome.completed_sessions);
+            new_apps.extend(outcome.new_apps);
+        }
+
+        assert_eq!(completed.len(), 2);
+        assert_eq!(new_apps, vec!["Code.exe".to_string(), "Slack.exe".to_string()]);
+
+        let db = engine.database.lock().await;
+        let sessions = db.get_sessions_in_range(start, Utc::now()).unwrap();
+        let stored: Vec<_> = sessions
+            .iter()
+            .map(|s| (s.app_id.as_str(), s.duration_seconds))
+            .collect();
+        assert_eq!(stored, vec![("Code.exe", Some(30)), ("Slack.exe", Some(20))]);
+    }
+
+    #[tokio::test]
+    async fn test_capped_idle_chunks_persist_as_separate_rows() {
+        let engine = TrackingEngine::new(
+            Arc::new(MockCollector::new(vec![
+                MockReading::app("Code.exe"),
+                MockReading::app("Code.exe").with_idle(300),
+                MockReading::app("Code.exe").with_idle(3_660),
+                MockReading::app("Code.exe"),
+            ])),
+            Sessionizer::new(SessionizerConfig {
+                max_idle_session_seconds: 3_600,
+                ..SessionizerConfig::default()
+            }),
+            Database::in_memory(),
+            Arc::new(SchedulerEngine::new()),
+            ForegroundDebouncer::new(1),
+        );
+        let start = DateTime::from_timestamp((Utc::now() - chrono::Duration::days(1)).timestamp(), 0).unwrap();
+        let at = |secs: i64| start + chrono::Duration::seconds(secs);
+
+        // Idle from 300s, with input back 2.5 hours later
+        for secs in [0, 300, 3_960, 9_300] {
+            engine.tick(at(secs)).await;
+        }
+
+        let db = engine.database.lock().await;
+        let idle: Vec<_> = db
+            .get_sessions_in_range(start, Utc::now())
+            .unwrap()
+            .into_iter()
+            .filter(|s| s.is_idle)
+            .map(|s| (s.start_time, s.duration_seconds))
+            .collect();
+        assert_eq!(
+            idle,
+            vec![(at(300), Some(3_600)), (at(3_900), Some(3_600)), (at(7_500), Some(1_800))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_live_totals_include_in_progress_session() {
+        let engine = engine_with_script(vec![
+            MockReading::app("Code.exe"),
+            MockReading::app("Code.exe"),
+            MockReading::app("Code.exe"),
+            MockReading::app("Slack.exe"),
+            MockReading::app("Slack.exe"),
+        ]);
+        engine
+            .database
+            .lock()
+            .await
+            .set_report_timezone(crate::timezone::ReportTimezone::parse("UTC").unwrap());
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2024, 3, 4, 12, 0, 0).unwrap();
+        for secs in 0..5 {
+            engine.tick(start + chrono::Duration::seconds(secs * 10)).await;
+        }
+
+        let now = start + chrono::Duration::seconds(100);
+        let stored = engine.database.lock().await.get_day_app_totals(now.date_naive()).unwrap();
+        assert_eq!(stored, vec![("Code.exe".to_string(), 30)]);
+        // Slack has been in front since the switch at 30s
+        let live = engine.app_totals_today_live(now).await.unwrap();
+        assert_eq!(live, vec![("Slack.exe".to_string(), 70), ("Code.exe".to_string(), 30)]);
+    }
+
+    #[tokio::test]
+    async fn test_app_in_front_is_not_neglected() {
+        let engine = engine_with_script(vec![MockReading::app("Obsidian.exe"); 8]);
+        let start = Utc::now() - chrono::Duration::hours(1);
+        {
+            let db = engine.database.lock().await;
+            let last_week = start - chrono::Duration::days(7);
+            db.insert_session(&Session {
+                id: None,
+                app_id: "Obsidian.exe".to_string(),
+                app_name: None,
+                start_time: last_week,
+                end_time: Some(last_week + chrono::Duration::minutes(30)),
+                duration_seconds: Some(1_800),
+                is_idle: false,
+                manual: false,
+                process_path: None,
+                interruptions: 0,
+                input_rate: None,
+            })
+            .unwrap();
+            db.insert_neglect_reminder(&NeglectReminder {
+                id: None,
+                app_id: "Obsidian.exe".to_string(),
+                remind_after_secs: 600,
+                window_start: chrono::NaiveTime::MIN,
+                window_end: chrono::NaiveTime::from_hms_opt(23, 59, 59).unwrap(),
+                enabled: true,
+            })
+            .unwrap();
+        }
+
+        // Checked on the first tick and again a minute later, before anything is saved
+        for secs in 0..8 {
+            let outcome = engine.tick(start + chrono::Duration::seconds(secs * 10)).await;
+            assert!(outcome.neglect_reminders.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_treat_as_active_holds_off_idle() {
+        let reading = || MockReading::app("explorer.exe").with_idle(900);
+        let engine = engine_with_script(vec![
+            reading().with_running("Blender.exe"),
+            reading().with_running("Blender.exe"),
+            reading(),
+        ]);
+        *engine.treat_as_active.lock().unwrap() = vec!["blender".to_string()];
+        let start = Utc::now() - chrono::Duration::hours(1);
+
+        // The render keeps the session going despite no input
+        assert!(engine.tick(start).await.completed_sessions.is_empty());
+        assert!(engine.tick(start + chrono::Duration::seconds(10)).await.completed_sessions.is_empty());
+
+        // Render done: idle is declared
+        let completed = engine.tick(start + chrono::Duration::seconds(20)).await.completed_sessions;
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].app_id, "explorer.exe");
+        assert_eq!(completed[0].duration_seconds, Some(20));
+    }
+
+    #[tokio::test]
+    async fn test_start_and_stop() {
+        let engine = Arc::new(engine_with_script(Vec::new()));
+        let ticks = Arc::new(AtomicU32::new(0));
+
+        engine.start(&tokio::runtime::Handle::current(), Duration::from_millis(10), {
+            let ticks = ticks.clone();
+            move |_| {
+                ticks.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        tokio::time::timeout(Duration::from_secs(1), engine.stop())
+            .await
+            .expect("polling loop did not stop");
+        let ticks_at_stop = ticks.load(Ordering::SeqCst);
+        assert!(ticks_at_stop > 0);
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(ticks.load(Ordering::SeqCst), ticks_at_stop);
+    }
+
+    /// Takes `delay` to answer every foreground query
+    struct SlowCollector {
+        delay: Duration,
+    }
+
+    impl ForegroundCollector for SlowCollector {
+        fn get_foreground_app(&self) -> Option<AppInfo> {
+            std::thread::sleep(self.delay);
+            None
+        }
+
+        fn get_idle_seconds(&self) -> u64 {
+            0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ticks_record_collector_reliability() {
+        let code = || MockReading::app("Code.exe");
+        let engine = engine_with_script(vec![
+            code(),
+            MockReading::none(),
+            code(),
+            code(),
+            MockReading::none(),
+            code(),
+            code(),
+            code(),
+            MockReading::none(),
+            code(),
+        ]);
+        let start = Utc::now() - chrono::Duration::hours(1);
+        for secs in 0..10 {
+            engine.tick(start + chrono::Duration::seconds(secs * 10)).await;
+        }
+
+        let end = start + chrono::Duration::seconds(100);
+        let reliability = engine.database.lock().await.collector_reliability(start, end).unwrap();
+        assert_eq!(reliability.ticks, 10);
+        assert_eq!(reliability.failures, 3);
+        assert_eq!(reliability.success_rate, Some(0.7));
+        assert_eq!(reliability.failures_by_reason, vec![("no_reading".to_string(), 3)]);
+
+        // Nothing is recorded once turned off
+        engine.record_collector_errors.store(false, Ordering::Relaxed);
+        engine.tick(start + chrono::Duration::seconds(100)).await;
+        let later = engine.database.lock().await.collector_reliability(start, end).unwrap();
+        assert_eq!(later.ticks, 10);
+    }
+
+    #[tokio::test]
+    async fn test_tick_records_collector_latency() {
+        let engine = TrackingEngine::new(
+            Arc::new(SlowCollector { delay: Duration::from_millis(30) }),
+            Sessionizer::new(SessionizerConfig::default()),
+            Database::in_memory(),
+            Arc::new(SchedulerEngine::new()),
+            ForegroundDebouncer::new(1),
+        );
+        for _ in 0..3 {
+            engine.tick(Utc::now()).await;
+        }
+
+        let stats = engine.collector_perf.lock().unwrap().stats();
+        assert_eq!(stats.samples, 3);
+        assert!(stats.avg_ms >= 30.0);
+        assert!(stats.p95_ms >= 30.0);
+        assert!(stats.max_ms >= stats.avg_ms);
+    }
+}
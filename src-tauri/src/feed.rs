@@ -0,0 +1,73 @@
+//! Completed sessions broadcast to in-process subscribers, so the tracker can be
+//! consumed as a library and not only through the Tauri frontend.
+
+use crate::models::Session;
+use tokio::sync::broadcast;
+
+/// Sessions buffered per subscriber before slow receivers start lagging
+pub const SESSION_FEED_CAPACITY: usize = 64;
+
+/// Publishes each completed session to every current subscriber
+#[derive(Debug, Clone)]
+pub struct SessionFeed {
+    sender: broadcast::Sender<Session>,
+}
+
+impl SessionFeed {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Send a completed session to subscribers. Sessions published while nobody
+    /// is subscribed are dropped.
+    pub fn publish(&self, session: &Session) {
+        let _ = self.sender.send(session.clone());
+    }
+
+    /// Receive every session completed from now on
+    pub fn subscribe_sessions(&self) -> broadcast::Receiver<Session> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for SessionFeed {
+    fn default() -> Self {
+        Self::new(SESSION_FEED_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AppInfo;
+    use crate::sessionizer::{Sessionizer, SessionizerConfig};
+    use chrono::{Duration, Utc};
+
+    #[tokio::test]
+    async fn test_subscriber_receives_completed_session() {
+        let feed = SessionFeed::default();
+        let mut receiver = feed.subscribe_sessions();
+
+        let mut sessionizer = Sessionizer::new(SessionizerConfig::default());
+        let app = |name: &str| {
+            Some(AppInfo {
+                process_name: name.to_string(),
+                app_title: None,
+                bundle_id: None,
+                process_path: None,
+            })
+        };
+        let start = Utc::now();
+        sessionizer.update_at(app("Code.exe"), 0, false, start);
+        assert!(sessionizer.update_at(app("Slack.exe"), 0, false, start + Duration::seconds(30)));
+        for session in sessionizer.take_pending_sessions() {
+            feed.publish(&session);
+        }
+
+        let session = receiver.recv().await.unwrap();
+        assert_eq!(session.app_id, "Code.exe");
+        assert_eq!(session.duration_seconds, Some(30));
+        assert!(receiver.try_recv().is_err());
+    }
+}
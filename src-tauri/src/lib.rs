@@ -1,202 +1,1098 @@
 pub mod collectors;
+pub mod config;
+pub mod engine;
+pub mod feed;
 pub mod models;
+pub mod notifications;
+pub mod recovery;
 pub mod scheduler;
 pub mod sessionizer;
 pub mod storage;
+pub mod tape;
+pub mod timezone;
+pub mod version;
+pub mod webhooks;
 
-use tauri::{Manager, WebviewWindow};
+use tauri::{Emitter, Manager, WebviewWindow};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
 use tauri_plugin_notification::NotificationExt;
 
-use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
 
-use collectors::{create_collector, ForegroundCollector};
+use collectors::{create_collector, CollectorCapabilities, ForegroundCollector, IdleInputMode};
+use collectors::perf::CollectorPerfStats;
+use config::{keys, Config};
+use engine::{TickOutcome, TrackingEngine};
 use models::Schedule;
+use notifications::{HeldNotifications, NotificationContent, NotificationHealth};
 use scheduler::SchedulerEngine;
-use sessionizer::{Sessionizer, SessionizerConfig};
-use storage::Database;
+use sessionizer::{AppIdSource, ForegroundDebouncer, SessionKey, Sessionizer};
+use storage::{resolve_db_path, Database, DbError, DbLocation};
+use storage::export::DurationRounding;
+use storage::instance::{self, InstanceLock};
+use timezone::{DayAttribution, ReportTimezone};
 
-/// Shared application state
+/// Shared application state: the tracking engine plus what only the Tauri layer needs
 pub struct AppState {
-    pub sessionizer: Arc<Mutex<Sessionizer>>,
-    pub collector: Arc<dyn ForegroundCollector>,
-    pub database: Arc<Mutex<Database>>,
-    pub scheduler_engine: Arc<SchedulerEngine>,
+    pub engine: Arc<TrackingEngine>,
+    /// Whether to notify when a never-seen app appears
+    pub notify_new_apps: Arc<AtomicBool>,
+    /// Coalesce a tick's schedule alerts into one notification
+    pub group_notifications: Arc<AtomicBool>,
+    /// Failed schedule notifications, escalated to the UI once
+    pub notification_health: Arc<std::sync::Mutex<NotificationHealth>>,
+    /// Single-instance lock on the database, held until exit
+    pub instance_lock: std::sync::Mutex<Option<InstanceLock>>,
+    /// No notifications are shown before this time (schedules are still checked and logged)
+    pub notifications_muted_until: Arc<std::sync::Mutex<Option<chrono::DateTime<chrono::Utc>>>>,
+    /// One-time notices raised while muted, shown on the first tick after the mute ends
+    pub held_notifications: Arc<std::sync::Mutex<HeldNotifications>>,
 }
 
-// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+/// Backend version, build commit and database schema version
 #[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
+async fn app_version(state: tauri::State<'_, AppState>) -> Result<version::AppVersion, String> {
+    let db = state.engine.database.lock().await;
+    version::AppVersion::current(&db).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 async fn get_current_app(state: tauri::State<'_, AppState>) -> Result<Option<String>, String> {
-    let app = state.collector.get_foreground_app();
+    let app = state.engine.collector.get_foreground_app();
     Ok(app.map(|a| a.process_name))
 }
 
+/// Latency of recent collector polls (avg, p95, max in ms)
+#[tauri::command]
+async fn get_collector_perf(state: tauri::State<'_, AppState>) -> Result<CollectorPerfStats, String> {
+    let perf = state.engine.collector_perf.lock().map_err(|e| e.to_string())?;
+    Ok(perf.stats())
+}
+
+/// Share of polling ticks over a range where the collector didn't fail
+#[tauri::command]
+async fn get_collector_reliability(
+    state: tauri::State<'_, AppState>,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<models::CollectorReliability, String> {
+    let db = state.engine.database.lock().await;
+    db.collector_reliability(start, end).map_err(|e| e.to_string())
+}
+
+/// Turn counting ticks and recording collector failures on or off
+#[tauri::command]
+async fn set_record_collector_errors(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.engine.record_collector_errors.store(enabled, Ordering::Relaxed);
+    let db = state.engine.database.lock().await;
+    db.set_setting_display(keys::RECORD_COLLECTOR_ERRORS, enabled)
+        .map_err(|e| e.to_string())
+}
+
+/// Which collector is active and what it can report
+#[tauri::command]
+async fn collector_capabilities(state: tauri::State<'_, AppState>) -> Result<CollectorCapabilities, String> {
+    Ok(state.engine.collector.capabilities())
+}
+
+/// PNG bytes of an app's icon, None when the collector can't find one
+#[tauri::command]
+async fn get_app_icon(state: tauri::State<'_, AppState>, process_name: String) -> Result<Option<Vec<u8>>, String> {
+    Ok(state.engine.collector.get_app_icon(&process_name))
+}
+
 #[tauri::command]
 async fn get_idle_seconds(state: tauri::State<'_, AppState>) -> Result<u64, String> {
-    Ok(state.collector.get_idle_seconds())
+    Ok(state.engine.collector.get_idle_seconds())
+}
+
+/// Choose which input devices count as activity for idle detection
+#[tauri::command]
+async fn set_idle_input_mode(state: tauri::State<'_, AppState>, mode: IdleInputMode) -> Result<(), String> {
+    state.engine.collector.set_idle_input_mode(mode);
+    let db = state.engine.database.lock().await;
+    db.set_setting_json(keys::IDLE_INPUT_MODE, &mode)
+        .map_err(|e| e.to_string())
+}
+
+/// Override the idle threshold (seconds) on specific weekdays; other days use the default
+#[tauri::command]
+async fn set_idle_threshold_by_weekday(
+    state: tauri::State<'_, AppState>,
+    thresholds: std::collections::HashMap<chrono::Weekday, u64>,
+) -> Result<(), String> {
+    let db = state.engine.database.lock().await;
+    db.set_setting_json(keys::IDLE_THRESHOLD_BY_WEEKDAY, &thresholds)
+        .map_err(|e| e.to_string())?;
+    drop(db); // The polling loop locks the sessionizer before the database
+    state.engine.sessionizer.lock().await.set_idle_threshold_by_weekday(thresholds);
+    Ok(())
+}
+
+/// Keep sessions active (not idle) while a call is running, even without input
+#[tauri::command]
+async fn set_call_keeps_active(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.engine.sessionizer.lock().await.set_call_keeps_active(enabled);
+    let db = state.engine.database.lock().await;
+    db.set_setting_display(keys::CALL_KEEPS_ACTIVE, enabled)
+        .map_err(|e| e.to_string())
+}
+
+/// Choose what counts as the same session within one app (process, title or document)
+#[tauri::command]
+async fn set_session_key(state: tauri::State<'_, AppState>, key: SessionKey) -> Result<(), String> {
+    state.engine.sessionizer.lock().await.set_session_key(key);
+    let db = state.engine.database.lock().await;
+    db.set_setting_json(keys::SESSION_KEY, &key)
+        .map_err(|e| e.to_string())
+}
+
+/// Set how many consecutive ticks a new foreground app must persist before it counts
+#[tauri::command]
+async fn set_foreground_debounce_ticks(state: tauri::State<'_, AppState>, ticks: u32) -> Result<(), String> {
+    state.engine.debouncer.lock().await.set_required_ticks(ticks);
+    let db = state.engine.database.lock().await;
+    db.set_setting_display(keys::FOREGROUND_DEBOUNCE_TICKS, ticks)
+        .map_err(|e| e.to_string())
+}
+
+/// Take the `app_id` of the listed processes from their window title instead
+/// of the executable name (e.g. PWAs sharing one browser executable)
+#[tauri::command]
+async fn set_app_id_sources(
+    state: tauri::State<'_, AppState>,
+    sources: std::collections::HashMap<String, AppIdSource>,
+) -> Result<(), String> {
+    let db = state.engine.database.lock().await;
+    db.set_setting_json(keys::APP_ID_SOURCES, &sources)
+        .map_err(|e| e.to_string())?;
+    *state.engine.app_id_sources.lock().unwrap() = sources;
+    Ok(())
+}
+
+/// Only record window titles for matching processes (`None` records all titles)
+#[tauri::command]
+async fn set_title_capture_allowlist(
+    state: tauri::State<'_, AppState>,
+    patterns: Option<Vec<String>>,
+) -> Result<(), String> {
+    let db = state.engine.database.lock().await;
+    db.set_setting_json(keys::TITLE_CAPTURE_ALLOWLIST, &patterns)
+        .map_err(|e| e.to_string())?;
+    *state.engine.title_capture_allowlist.lock().unwrap() = patterns;
+    Ok(())
+}
+
+/// Keep counting the user as active while a matching process runs, even in the background
+#[tauri::command]
+async fn set_treat_as_active(state: tauri::State<'_, AppState>, patterns: Vec<String>) -> Result<(), String> {
+    let db = state.engine.database.lock().await;
+    db.set_setting_json(keys::TREAT_AS_ACTIVE, &patterns)
+        .map_err(|e| e.to_string())?;
+    *state.engine.treat_as_active.lock().unwrap() = patterns;
+    Ok(())
 }
 
 #[tauri::command]
 async fn get_today_sessions(state: tauri::State<'_, AppState>) -> Result<Vec<models::Session>, String> {
-    let db = state.database.lock().await;
-    db.get_today_sessions().map_err(|e| e.to_string())
+    let mut db = state.engine.database.lock().await;
+    db.with_reconnect(|db| db.get_today_sessions())
+        .map_err(|e| e.to_string())
+}
+
+/// Notify the first time a never-seen app is used
+#[tauri::command]
+async fn set_notify_new_apps(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.notify_new_apps.store(enabled, Ordering::Relaxed);
+    let db = state.engine.database.lock().await;
+    db.set_setting_display(keys::NOTIFY_NEW_APPS, enabled)
+        .map_err(|e| e.to_string())
+}
+
+/// Show one combined notification per tick instead of one per violated schedule
+#[tauri::command]
+async fn set_group_notifications(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.group_notifications.store(enabled, Ordering::Relaxed);
+    let db = state.engine.database.lock().await;
+    db.set_setting_display(keys::GROUP_NOTIFICATIONS, enabled)
+        .map_err(|e| e.to_string())
+}
+
+/// Silence every notification for `minutes`; returns when the mute ends
+#[tauri::command]
+fn mute_notifications(state: tauri::State<'_, AppState>, minutes: u32) -> chrono::DateTime<chrono::Utc> {
+    let until = chrono::Utc::now() + chrono::Duration::minutes(i64::from(minutes));
+    *state.notifications_muted_until.lock().unwrap() = Some(until);
+    until
+}
+
+/// End a mute started with `mute_notifications`
+#[tauri::command]
+fn unmute_notifications(state: tauri::State<'_, AppState>) {
+    *state.notifications_muted_until.lock().unwrap() = None;
+}
+
+/// Record each tick's collector readings to a tape for replay
+#[tauri::command]
+async fn set_record_mode(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    let tape = if enabled {
+        let location = get_db_path(&app_handle).map_err(|e| e.to_string())?;
+        Some(open_tape(&location.path).ok_or("Failed to open tape file")?)
+    } else {
+        None
+    };
+    state.engine.set_tape(tape);
+    let db = state.engine.database.lock().await;
+    db.set_setting_display(keys::RECORD_TAPE, enabled)
+        .map_err(|e| e.to_string())
+}
+
+/// Apps seen for the first time at or after `since`
+#[tauri::command]
+async fn get_new_apps_since(
+    state: tauri::State<'_, AppState>,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<(String, chrono::DateTime<chrono::Utc>)>, String> {
+    let db = state.engine.database.lock().await;
+    db.new_apps_since(since).map_err(|e| e.to_string())
+}
+
+/// Completed sessions buffered in the sessionizer but not yet saved
+#[tauri::command]
+async fn get_pending_sessions(state: tauri::State<'_, AppState>) -> Result<Vec<models::Session>, String> {
+    Ok(state.engine.sessionizer.lock().await.peek_pending_sessions())
+}
+
+/// Today's per-app totals with the session still in progress counted up to now
+#[tauri::command]
+async fn get_app_totals_today_live(state: tauri::State<'_, AppState>) -> Result<Vec<(String, i64)>, String> {
+    state
+        .engine
+        .app_totals_today_live(chrono::Utc::now())
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 async fn get_app_totals_today(state: tauri::State<'_, AppState>) -> Result<Vec<(String, i64)>, String> {
-    use chrono::{Utc, TimeZone};
-    
-    let db = state.database.lock().await;
-    let today_start = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap();
-    let today_end = Utc::now().date_naive().and_hms_opt(23, 59, 59).unwrap();
-    
-    db.get_app_totals(
-        Utc.from_utc_datetime(&today_start),
-        Utc.from_utc_datetime(&today_end),
-    ).map_err(|e| e.to_string())
+    let mut db = state.engine.database.lock().await;
+    db.with_reconnect(|db| db.get_today_app_totals())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_report_timezone(state: tauri::State<'_, AppState>) -> Result<Option<String>, String> {
+    let db = state.engine.database.lock().await;
+    Ok(db.report_timezone().name().map(|name| name.to_string()))
+}
+
+/// Pin reports and schedules to an IANA timezone, or `None` for system local
+#[tauri::command]
+async fn set_report_timezone(state: tauri::State<'_, AppState>, timezone: Option<String>) -> Result<(), String> {
+    let tz = match timezone {
+        Some(name) => ReportTimezone::parse(&name)?,
+        None => ReportTimezone::Local,
+    };
+    let mut db = state.engine.database.lock().await;
+    let saved = match tz.name() {
+        Some(name) => db.set_setting(keys::REPORT_TIMEZONE, name),
+        None => db.delete_setting(keys::REPORT_TIMEZONE),
+    };
+    saved.map_err(|e| e.to_string())?;
+    db.set_report_timezone(tz);
+    drop(db); // Don't hold the database while updating other tracking state
+    state.engine.scheduler_engine.set_timezone(tz);
+    state.engine.sessionizer.lock().await.set_timezone(tz);
+    Ok(())
+}
+
+/// Choose which day owns sessions that cross midnight
+#[tauri::command]
+async fn set_day_attribution(state: tauri::State<'_, AppState>, mode: DayAttribution) -> Result<(), String> {
+    let mut db = state.engine.database.lock().await;
+    db.set_setting_json(keys::DAY_ATTRIBUTION, &mode)
+        .map_err(|e| e.to_string())?;
+    db.set_day_attribution(mode);
+    Ok(())
+}
+
+/// Raw value of a persisted setting
+#[tauri::command]
+async fn get_setting(state: tauri::State<'_, AppState>, key: String) -> Result<Option<String>, String> {
+    let db = state.engine.database.lock().await;
+    db.get_setting(&key).map_err(|e| e.to_string())
+}
+
+/// Persist a setting. Takes effect on the next start unless a dedicated command applies it.
+#[tauri::command]
+async fn set_setting(state: tauri::State<'_, AppState>, key: String, value: String) -> Result<(), String> {
+    let db = state.engine.database.lock().await;
+    db.set_setting(&key, &value).map_err(|e| e.to_string())
+}
+
+/// Count sessions by length (defaults to <1m, 1-5m, 5-15m, >=15m buckets)
+#[tauri::command]
+async fn get_session_length_histogram(
+    state: tauri::State<'_, AppState>,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    buckets: Option<Vec<i64>>,
+    include_idle: Option<bool>,
+) -> Result<Vec<(i64, i64)>, String> {
+    let buckets = buckets.unwrap_or_else(|| storage::reports::DEFAULT_LENGTH_BUCKETS.to_vec());
+    let db = state.engine.database.lock().await;
+    db.session_length_histogram(start, end, &buckets, include_idle.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+/// Mean session length per app between `start` and `end`, most sessions first
+#[tauri::command]
+async fn get_avg_session_length_per_app(
+    state: tauri::State<'_, AppState>,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<(String, f64)>, String> {
+    let db = state.engine.database.lock().await;
+    db.avg_session_length_per_app(start, end).map_err(|e| e.to_string())
+}
+
+/// Daily compliance rate of a schedule between `start` and `end`
+#[tauri::command]
+async fn get_compliance_trend(
+    state: tauri::State<'_, AppState>,
+    schedule_id: i64,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<(chrono::NaiveDate, f64)>, String> {
+    let db = state.engine.database.lock().await;
+    db.compliance_trend(schedule_id, start, end)
+        .map_err(|e| e.to_string())
+}
+
+/// Compliant and total log counts of a schedule per local hour of day
+#[tauri::command]
+async fn get_compliance_by_hour(
+    state: tauri::State<'_, AppState>,
+    schedule_id: i64,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<[(u32, u32); 24], String> {
+    let db = state.engine.database.lock().await;
+    db.compliance_by_hour(schedule_id, start, end)
+        .map_err(|e| e.to_string())
+}
+
+/// Summary stats of a schedule between `start` and `end`
+#[tauri::command]
+async fn get_schedule_stats(
+    state: tauri::State<'_, AppState>,
+    schedule_id: i64,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<models::ScheduleStats, String> {
+    let db = state.engine.database.lock().await;
+    db.schedule_stats(schedule_id, start, end)
+        .map_err(|e| e.to_string())
+}
+
+/// 0-100 focus score of a local day
+#[tauri::command]
+async fn get_focus_score(state: tauri::State<'_, AppState>, day: chrono::NaiveDate) -> Result<f64, String> {
+    let db = state.engine.database.lock().await;
+    db.focus_score(day).map_err(|e| e.to_string())
+}
+
+/// Seconds from a local day's first activity to its first session of at least
+/// `min_focus_secs`; None without one
+#[tauri::command]
+async fn get_time_to_first_focus(
+    state: tauri::State<'_, AppState>,
+    day: chrono::NaiveDate,
+    min_focus_secs: i64,
+) -> Result<Option<i64>, String> {
+    let db = state.engine.database.lock().await;
+    db.time_to_first_focus(day, min_focus_secs).map_err(|e| e.to_string())
+}
+
+/// Idle time per recorded idle reason in a time window
+#[tauri::command]
+async fn get_idle_breakdown(
+    state: tauri::State<'_, AppState>,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<(String, i64)>, String> {
+    let db = state.engine.database.lock().await;
+    db.idle_breakdown(start, end).map_err(|e| e.to_string())
+}
+
+/// Session count and active seconds on one weekday across a range
+#[tauri::command]
+async fn get_weekday_stats(
+    state: tauri::State<'_, AppState>,
+    weekday: chrono::Weekday,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<(i64, i64), String> {
+    let db = state.engine.database.lock().await;
+    db.stats_for_weekday(weekday, start, end).map_err(|e| e.to_string())
+}
+
+/// Average active seconds per day over the `days` days ending on `ending`
+#[tauri::command]
+async fn get_rolling_daily_average(
+    state: tauri::State<'_, AppState>,
+    days: u32,
+    ending: chrono::NaiveDate,
+) -> Result<f64, String> {
+    let db = state.engine.database.lock().await;
+    db.rolling_daily_average(days, ending).map_err(|e| e.to_string())
+}
+
+/// Consecutive days with activity up to and including `ending`
+#[tauri::command]
+async fn get_active_day_streak(state: tauri::State<'_, AppState>, ending: chrono::NaiveDate) -> Result<u32, String> {
+    let db = state.engine.database.lock().await;
+    db.active_day_streak(ending).map_err(|e| e.to_string())
+}
+
+/// Apps most often used right before a distraction, most first
+#[tauri::command]
+async fn get_distraction_precursors(
+    state: tauri::State<'_, AppState>,
+    distraction_pattern: String,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<(String, i64)>, String> {
+    let db = state.engine.database.lock().await;
+    db.distraction_precursors(&distraction_pattern, start, end)
+        .map_err(|e| e.to_string())
+}
+
+/// Active seconds over a range that followed at least one active schedule
+#[tauri::command]
+async fn get_compliant_active_seconds(
+    state: tauri::State<'_, AppState>,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<i64, String> {
+    let db = state.engine.database.lock().await;
+    db.compliant_active_seconds(start, end).map_err(|e| e.to_string())
+}
+
+/// Compliance of each schedule that applied on a past local day
+#[tauri::command]
+async fn get_day_schedule_report(
+    state: tauri::State<'_, AppState>,
+    day: chrono::NaiveDate,
+) -> Result<Vec<models::DayScheduleResult>, String> {
+    let db = state.engine.database.lock().await;
+    db.day_schedule_report(day).map_err(|e| e.to_string())
+}
+
+/// Active time per app inside a schedule's windows over a range
+#[tauri::command]
+async fn get_apps_during_schedule(
+    state: tauri::State<'_, AppState>,
+    schedule_id: i64,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<(String, i64)>, String> {
+    let db = state.engine.database.lock().await;
+    db.apps_during_schedule(schedule_id, start, end).map_err(|e| e.to_string())
+}
+
+/// Idle seconds inside a schedule's windows between `start` and `end`
+#[tauri::command]
+async fn get_idle_during_schedule(
+    state: tauri::State<'_, AppState>,
+    schedule_id: i64,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<i64, String> {
+    let db = state.engine.database.lock().await;
+    db.idle_during_schedule(schedule_id, start, end).map_err(|e| e.to_string())
+}
+
+/// Each day's longest active session
+#[tauri::command]
+async fn get_deepest_focus_by_day(
+    state: tauri::State<'_, AppState>,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<(chrono::NaiveDate, models::Session)>, String> {
+    let db = state.engine.database.lock().await;
+    db.deepest_focus_by_day(start, end).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_app_usage_bounds(
+    state: tauri::State<'_, AppState>,
+    app_id: String,
+) -> Result<Option<storage::sessions::UsageBounds>, String> {
+    let db = state.engine.database.lock().await;
+    db.app_usage_bounds(&app_id).map_err(|e| e.to_string())
+}
+
+/// Backfill a session for time the tracker missed
+#[tauri::command]
+async fn add_manual_session(
+    state: tauri::State<'_, AppState>,
+    app_id: String,
+    app_name: Option<String>,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<i64, String> {
+    let db = state.engine.database.lock().await;
+    db.insert_manual_session(&app_id, app_name.as_deref(), start, end)
+        .map_err(|e| e.to_string())
+}
+
+/// Per-app totals for each local day from `start` to `end` (inclusive)
+#[tauri::command]
+async fn get_daily_totals(
+    state: tauri::State<'_, AppState>,
+    start: chrono::NaiveDate,
+    end: chrono::NaiveDate,
+) -> Result<Vec<(chrono::NaiveDate, storage::totals_cache::AppTotals)>, String> {
+    let db = state.engine.database.lock().await;
+    db.get_daily_totals(start, end).map_err(|e| e.to_string())
+}
+
+/// Recompute the daily totals cache from all sessions. Returns the number of days cached.
+#[tauri::command]
+async fn rebuild_totals_cache(state: tauri::State<'_, AppState>) -> Result<usize, String> {
+    let db = state.engine.database.lock().await;
+    db.rebuild_totals_cache().map_err(|e| e.to_string())
+}
+
+/// Id pairs of completed sessions that overlap in time
+#[tauri::command]
+async fn find_overlapping_sessions(state: tauri::State<'_, AppState>) -> Result<Vec<(i64, i64)>, String> {
+    let db = state.engine.database.lock().await;
+    db.find_overlapping_sessions().map_err(|e| e.to_string())
+}
+
+/// Truncate overlapping sessions at the next session's start. Returns how many were adjusted.
+#[tauri::command]
+async fn fix_overlapping_sessions(state: tauri::State<'_, AppState>) -> Result<usize, String> {
+    let db = state.engine.database.lock().await;
+    db.fix_overlapping_sessions().map_err(|e| e.to_string())
+}
+
+/// Merge all sessions of one app into another. Returns the number of sessions moved.
+#[tauri::command]
+async fn rename_app(
+    state: tauri::State<'_, AppState>,
+    from: String,
+    to: String,
+    include_compliance_logs: Option<bool>,
+) -> Result<usize, String> {
+    let db = state.engine.database.lock().await;
+    db.rename_app(&from, &to, include_compliance_logs.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn export_sessions_csv(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    rounding: Option<DurationRounding>,
+) -> Result<usize, String> {
+    let file = std::fs::File::create(&path).map_err(|e| e.to_string())?;
+    let mut writer = std::io::BufWriter::new(file);
+    let db = state.engine.database.lock().await;
+    db.export_sessions_csv_to_writer(&mut writer, start, end, rounding)
+        .map_err(|e| e.to_string())
+}
+
+/// Render a local day's activity as an SVG timeline strip
+#[tauri::command]
+async fn render_day_timeline_svg(state: tauri::State<'_, AppState>, day: chrono::NaiveDate) -> Result<String, String> {
+    let db = state.engine.database.lock().await;
+    db.render_day_timeline_svg(day).map_err(|e| e.to_string())
+}
+
+/// Export per-app totals for a range as CSV, optionally rounded for billing
+#[tauri::command]
+async fn export_app_totals_csv(
+    state: tauri::State<'_, AppState>,
+    path: String,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    rounding: Option<DurationRounding>,
+) -> Result<usize, String> {
+    let file = std::fs::File::create(&path).map_err(|e| e.to_string())?;
+    let mut writer = std::io::BufWriter::new(file);
+    let db = state.engine.database.lock().await;
+    db.export_app_totals_csv_to_writer(&mut writer, start, end, rounding)
+        .map_err(|e| e.to_string())
+}
+
+/// Back up the live database to `path`, consistent even while tracking writes
+#[tauri::command]
+async fn backup_db(state: tauri::State<'_, AppState>, path: String) -> Result<(), String> {
+    let db = state.engine.database.lock().await;
+    db.backup_to(std::path::Path::new(&path)).map_err(|e| e.to_string())
+}
+
+/// Move the database into `dir` and keep using it from there, also on later
+/// launches. Refuses if `dir` already holds a database. Returns the new path.
+#[tauri::command]
+async fn move_data_dir(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    dir: String,
+) -> Result<String, String> {
+    let app_data_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    let dir = std::path::PathBuf::from(dir);
+    let new_path = dir.join(storage::db::DB_FILE_NAME);
+
+    let mut db = state.engine.database.lock().await;
+    db.move_data_to(&new_path, || storage::db::redirect_data_dir(&app_data_dir, &dir))
+        .map_err(|e| e.to_string())?;
+
+    // Guard the new location; the old lock goes once the new one is held
+    let mut lock = state.instance_lock.lock().unwrap();
+    if lock.is_some() {
+        *lock = Some(InstanceLock::acquire(&new_path).map_err(|e| e.to_string())?);
+    }
+    Ok(new_path.display().to_string())
+}
+
+/// Export a local day's sessions as an iCalendar (.ics) document
+#[tauri::command]
+async fn export_day_ics(
+    state: tauri::State<'_, AppState>,
+    day: chrono::NaiveDate,
+    include_idle: bool,
+) -> Result<String, String> {
+    let db = state.engine.database.lock().await;
+    db.export_day_ics(day, include_idle).map_err(|e| e.to_string())
+}
+
+/// Sessions as CSV, only those in `project` and/or whose app contains `app_pattern`
+#[tauri::command]
+async fn export_sessions_csv_filtered(
+    state: tauri::State<'_, AppState>,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    project: Option<String>,
+    app_pattern: Option<String>,
+) -> Result<String, String> {
+    let db = state.engine.database.lock().await;
+    db.export_sessions_csv_filtered(start, end, project, app_pattern)
+        .map_err(|e| e.to_string())
+}
+
+/// Per-app daily totals as a CSV matrix (days x top apps, rest as "Other")
+#[tauri::command]
+async fn export_pivot_csv(
+    state: tauri::State<'_, AppState>,
+    start: chrono::NaiveDate,
+    end: chrono::NaiveDate,
+    top_n_apps: usize,
+) -> Result<String, String> {
+    let db = state.engine.database.lock().await;
+    db.export_pivot_csv(start, end, top_n_apps).map_err(|e| e.to_string())
+}
+
+/// Project time per day for the week from `week_start`, optionally rounded for billing
+#[tauri::command]
+async fn get_weekly_timesheet(
+    state: tauri::State<'_, AppState>,
+    week_start: chrono::NaiveDate,
+    rounding: Option<DurationRounding>,
+) -> Result<models::TimesheetGrid, String> {
+    let db = state.engine.database.lock().await;
+    db.weekly_timesheet(week_start, rounding).map_err(|e| e.to_string())
+}
+
+/// The weekly timesheet as CSV (projects x days, with totals)
+#[tauri::command]
+async fn export_timesheet_csv(
+    state: tauri::State<'_, AppState>,
+    week_start: chrono::NaiveDate,
+    rounding: Option<DurationRounding>,
+) -> Result<String, String> {
+    let db = state.engine.database.lock().await;
+    db.export_timesheet_csv(week_start, rounding).map_err(|e| e.to_string())
+}
+
+/// Export schedules, settings, goals, project rules and neglect reminders as a JSON backup
+#[tauri::command]
+async fn export_config_bundle(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let db = state.engine.database.lock().await;
+    db.export_config_bundle().map_err(|e| e.to_string())
+}
+
+/// Restore the configuration saved by `export_config_bundle`.
+/// Settings take effect on the next start.
+#[tauri::command]
+async fn import_config_bundle(state: tauri::State<'_, AppState>, json: String) -> Result<(), String> {
+    let db = state.engine.database.lock().await;
+    db.import_config_bundle(&json).map_err(|e| e.to_string())
 }
 
 // ===== Schedule CRUD Commands =====
 
 #[tauri::command]
 async fn get_all_schedules(state: tauri::State<'_, AppState>) -> Result<Vec<Schedule>, String> {
-    let db = state.database.lock().await;
-    db.get_all_schedules().map_err(|e| e.to_string())
+    let mut db = state.engine.database.lock().await;
+    db.with_reconnect(|db| db.get_all_schedules())
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 async fn create_schedule(state: tauri::State<'_, AppState>, schedule: Schedule) -> Result<i64, String> {
-    let db = state.database.lock().await;
+    let db = state.engine.database.lock().await;
     db.insert_schedule(&schedule).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 async fn update_schedule(state: tauri::State<'_, AppState>, schedule: Schedule) -> Result<(), String> {
-    let db = state.database.lock().await;
+    let db = state.engine.database.lock().await;
     db.update_schedule(&schedule).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 async fn delete_schedule(state: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
-    let db = state.database.lock().await;
+    let db = state.engine.database.lock().await;
     db.delete_schedule(id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 async fn toggle_schedule(state: tauri::State<'_, AppState>, id: i64, enabled: bool) -> Result<(), String> {
-    let db = state.database.lock().await;
+    let db = state.engine.database.lock().await;
     db.toggle_schedule(id, enabled).map_err(|e| e.to_string())
 }
 
-/// Start the background polling loop with scheduler integration
-fn start_polling_loop(app_state: Arc<AppState>, app_handle: tauri::AppHandle) {
-    std::thread::spawn(move || {
-        let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
-        rt.block_on(async {
-            let mut interval = tokio::time::interval(Duration::from_secs(1));
-            loop {
-                interval.tick().await;
-                
-                let app = app_state.collector.get_foreground_app();
-                let idle = app_state.collector.get_idle_seconds();
-                
-                // Session tracking
-                let mut sessionizer = app_state.sessionizer.lock().await;
-                let session_completed = sessionizer.update(app.clone(), idle);
-                
-                if session_completed {
-                    let sessions = sessionizer.take_pending_sessions();
-                    let db = app_state.database.lock().await;
-                    
-                    for session in sessions {
-                        match db.insert_session(&session) {
-                            Ok(id) => {
-                                println!(
-                                    "[DB] Saved session {} | {} | {} | {}s",
-                                    id,
-                                    session.app_id,
-                                    if session.is_idle { "IDLE" } else { "ACTIVE" },
-                                    session.duration_seconds.unwrap_or(0)
-                                );
-                            }
-                            Err(e) => {
-                                eprintln!("[DB Error] Failed to save session: {}", e);
-                            }
-                        }
-                    }
-                }
-                drop(sessionizer); // Release lock before scheduler check
-                
-                // Schedule compliance checking (every 5 seconds to reduce overhead)
-                if idle % 5 == 0 {
-                    if let Some(ref current_app) = app {
-                        let db = app_state.database.lock().await;
-                        if let Ok(schedules) = db.get_enabled_schedules() {
-                            drop(db); // Release lock before evaluation
-                            
-                            for schedule in schedules {
-                                let (should_notify, is_compliant) = 
-                                    app_state.scheduler_engine.evaluate(&schedule, &current_app.process_name);
-                                
-                                // Log compliance
-                                if !is_compliant {
-                                    let db = app_state.database.lock().await;
-                                    let _ = db.insert_compliance_log(
-                                        schedule.id.unwrap_or(0),
-                                        is_compliant,
-                                        Some(&current_app.process_name),
-                                    );
-                                }
-                                
-                                // Send notification if needed
-                                if should_notify {
-                                    let _ = app_handle
-                                        .notification()
-                                        .builder()
-                                        .title("Timewarden - Schedule Alert")
-                                        .body(format!(
-                                            "You're using {} during '{}'. Expected: {}",
-                                            current_app.process_name,
-                                            schedule.name,
-                                            schedule.expected_apps.join(", ")
-                                        ))
-                                        .show();
-                                    
-                                    println!(
-                                        "[Schedule] Non-compliant: {} (expected {:?})",
-                                        current_app.process_name, schedule.expected_apps
-                                    );
-                                }
-                            }
-                        }
-                    }
-                }
-                
-                // Debug: Print current app every 5 seconds
-                if idle % 5 == 0 {
-                    if let Some(ref info) = app {
-                        println!("[Tracking] {} | Idle: {}s", info.process_name, idle);
-                    }
-                }
+/// Enable or disable every schedule with a tag. Returns how many changed.
+#[tauri::command]
+async fn toggle_schedules_by_tag(
+    state: tauri::State<'_, AppState>,
+    tag: String,
+    enabled: bool,
+) -> Result<usize, String> {
+    let db = state.engine.database.lock().await;
+    db.set_enabled_by_tag(&tag, enabled).map_err(|e| e.to_string())
+}
+
+/// Suggest (unsaved, disabled) schedules from the past two weeks of usage
+#[tauri::command]
+async fn suggest_schedules(state: tauri::State<'_, AppState>) -> Result<Vec<Schedule>, String> {
+    let db = state.engine.database.lock().await;
+    db.suggest_schedules().map_err(|e| e.to_string())
+}
+
+// ===== Goal Commands =====
+
+#[tauri::command]
+async fn get_all_goals(state: tauri::State<'_, AppState>) -> Result<Vec<models::Goal>, String> {
+    let db = state.engine.database.lock().await;
+    db.get_all_goals().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn create_goal(state: tauri::State<'_, AppState>, goal: models::Goal) -> Result<i64, String> {
+    let db = state.engine.database.lock().await;
+    db.insert_goal(&goal).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_goal(state: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
+    let db = state.engine.database.lock().await;
+    db.delete_goal(id).map_err(|e| e.to_string())
+}
+
+/// Whether a goal was met on each applicable day in a date range
+#[tauri::command]
+async fn get_goal_adherence(
+    state: tauri::State<'_, AppState>,
+    goal_id: i64,
+    start: chrono::NaiveDate,
+    end: chrono::NaiveDate,
+) -> Result<Vec<(chrono::NaiveDate, bool)>, String> {
+    let db = state.engine.database.lock().await;
+    db.goal_adherence(goal_id, start, end).map_err(|e| e.to_string())
+}
+
+// ===== Neglect Reminder Commands =====
+
+#[tauri::command]
+async fn get_all_neglect_reminders(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<models::NeglectReminder>, String> {
+    let db = state.engine.database.lock().await;
+    db.get_all_neglect_reminders().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn create_neglect_reminder(
+    state: tauri::State<'_, AppState>,
+    reminder: models::NeglectReminder,
+) -> Result<i64, String> {
+    let db = state.engine.database.lock().await;
+    db.insert_neglect_reminder(&reminder).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_neglect_reminder(state: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
+    let db = state.engine.database.lock().await;
+    db.delete_neglect_reminder(id).map_err(|e| e.to_string())
+}
+
+// ===== Project Commands =====
+
+#[tauri::command]
+async fn get_all_project_rules(state: tauri::State<'_, AppState>) -> Result<Vec<models::ProjectRule>, String> {
+    let db = state.engine.database.lock().await;
+    db.get_all_project_rules().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn create_project_rule(state: tauri::State<'_, AppState>, rule: models::ProjectRule) -> Result<i64, String> {
+    let db = state.engine.database.lock().await;
+    db.insert_project_rule(&rule).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_project_rule(state: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
+    let db = state.engine.database.lock().await;
+    db.delete_project_rule(id).map_err(|e| e.to_string())
+}
+
+/// Active time per project in a range, unmatched sessions under "No Project"
+#[tauri::command]
+async fn get_project_totals(
+    state: tauri::State<'_, AppState>,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<(String, i64)>, String> {
+    let db = state.engine.database.lock().await;
+    db.get_project_totals(start, end).map_err(|e| e.to_string())
+}
+
+/// Compliance events of all schedules in a time window, newest first
+#[tauri::command]
+async fn get_all_compliance_logs(
+    state: tauri::State<'_, AppState>,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+    limit: Option<u32>,
+) -> Result<Vec<models::ComplianceLogDto>, String> {
+    let db = state.engine.database.lock().await;
+    db.get_all_compliance_logs(start, end, limit.unwrap_or(500))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_schedule_status(
+    state: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<models::ScheduleStatusDto, String> {
+    let db = state.engine.database.lock().await;
+    let schedule = db
+        .get_all_schedules()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|s| s.id == Some(id))
+        .ok_or_else(|| format!("Schedule {} not found", id))?;
+    Ok(state.engine.scheduler_engine.status(&schedule))
+}
+
+/// Gate-by-gate trace of whether the schedule would notify for the current app
+/// right now (None without a foreground app, which is never evaluated)
+#[tauri::command]
+async fn explain_schedule(
+    state: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<Option<models::EvalExplanation>, String> {
+    let db = state.engine.database.lock().await;
+    let schedule = db
+        .get_all_schedules()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|s| s.id == Some(id))
+        .ok_or_else(|| format!("Schedule {} not found", id))?;
+    drop(db);
+    Ok(state
+        .engine
+        .collector
+        .get_foreground_app()
+        .map(|app| state.engine.scheduler_engine.explain(&schedule, &app, chrono::Utc::now())))
+}
+
+/// Seconds until the schedule's current window ends, or None outside it
+#[tauri::command]
+async fn get_window_remaining(state: tauri::State<'_, AppState>, id: i64) -> Result<Option<i64>, String> {
+    let db = state.engine.database.lock().await;
+    let schedule = db
+        .get_all_schedules()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|s| s.id == Some(id))
+        .ok_or_else(|| format!("Schedule {} not found", id))?;
+    let scheduler = &state.engine.scheduler_engine;
+    Ok(scheduler
+        .window_remaining(&schedule, scheduler.timezone().now_local())
+        .map(|remaining| remaining.num_seconds()))
+}
+
+/// Enabled schedules active right now that the foreground app is violating
+#[tauri::command]
+async fn get_current_violations(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<models::ScheduleViolation>, String> {
+    let Some(app) = state.engine.collector.get_foreground_app() else {
+        return Ok(Vec::new());
+    };
+    let db = state.engine.database.lock().await;
+    let schedules = db.get_all_schedules().map_err(|e| e.to_string())?;
+    Ok(state
+        .engine
+        .scheduler_engine
+        .current_violations(&schedules, &app))
+}
+
+/// Pairs of schedule ids that overlap with contradictory expected apps
+#[tauri::command]
+async fn get_schedule_conflicts(state: tauri::State<'_, AppState>) -> Result<Vec<(i64, i64)>, String> {
+    let db = state.engine.database.lock().await;
+    let schedules = db.get_all_schedules().map_err(|e| e.to_string())?;
+    Ok(state.engine.scheduler_engine.find_conflicts(&schedules))
+}
+
+/// All schedules ordered by how relevant they are right now
+#[tauri::command]
+async fn get_schedules_ranked(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<models::RankedSchedule>, String> {
+    let app = state.engine.collector.get_foreground_app();
+    let db = state.engine.database.lock().await;
+    let schedules = db.get_all_schedules().map_err(|e| e.to_string())?;
+    Ok(state
+        .engine
+        .scheduler_engine
+        .rank_schedules(&schedules, app.as_ref()))
+}
+
+/// Track notification failures; after repeated failures tell the frontend once
+fn report_notification_result(
+    app_state: &AppState,
+    app_handle: &tauri::AppHandle,
+    result: Result<(), tauri_plugin_notification::Error>,
+) {
+    let mut health = app_state.notification_health.lock().unwrap();
+    match result {
+        Ok(()) => health.record_success(),
+        Err(e) => {
+            eprintln!("[Notify] Failed to show notification: {}", e);
+            if health.record_failure() {
+                eprintln!(
+                    "[Notify] Notifications keep failing ({} so far). Check that notifications are enabled for Timewarden.",
+                    health.total_failures()
+                );
+                let _ = app_handle.emit("notifications-unavailable", health.total_failures());
             }
+        }
+    }
+}
+
+/// Show notifications for what happened during a tick. While muted, schedule
+/// alerts are dropped and everything else is held until the mute ends.
+fn notify_tick_outcome(app_state: &AppState, app_handle: &tauri::AppHandle, outcome: TickOutcome) {
+    let mut notices = Vec::new();
+    if app_state.notify_new_apps.load(Ordering::Relaxed) {
+        for app_id in outcome.new_apps {
+            println!("[Apps] First time seeing {}", app_id);
+            notices.push(NotificationContent {
+                title: "Timewarden - New App".to_string(),
+                body: format!("{} was used for the first time", app_id),
+            });
+        }
+    }
+    for reminder in outcome.neglect_reminders {
+        notices.push(NotificationContent {
+            title: "Timewarden - Reminder".to_string(),
+            body: format!("You haven't used {} in a while", reminder.app_id),
+        });
+    }
+    for schedule in outcome.ended_schedules {
+        notices.push(NotificationContent {
+            title: "Timewarden - Schedule Ended".to_string(),
+            body: format!("'{}' schedule has ended", schedule.name),
         });
-    });
+    }
+
+    let muted_until = *app_state.notifications_muted_until.lock().unwrap();
+    let muted = notifications::is_muted(muted_until, chrono::Utc::now());
+    let notices = app_state.held_notifications.lock().unwrap().release(notices, muted);
+    if muted {
+        return;
+    }
+
+    let grouped = app_state.group_notifications.load(Ordering::Relaxed);
+    let alerts = notifications::schedule_alert_notifications(&outcome.alerts, grouped);
+    for content in alerts.into_iter().chain(notices) {
+        let shown = app_handle
+            .notification()
+            .builder()
+            .title(content.title)
+            .body(content.body)
+            .show();
+        report_notification_result(app_state, app_handle, shown);
+    }
 }
 
-/// Get the database path
-fn get_db_path(app_handle: &tauri::AppHandle) -> PathBuf {
-    let app_data = app_handle
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data dir");
-    app_data.join("timewarden.db")
+/// Get the database path, falling back to a temp/current dir if the app data dir is unavailable
+fn get_db_path(app_handle: &tauri::AppHandle) -> Result<DbLocation, DbError> {
+    let app_data = match app_handle.path().app_data_dir() {
+        Ok(dir) => Some(dir),
+        Err(e) => {
+            eprintln!("[Setup] Failed to get app data dir: {}", e);
+            None
+        }
+    };
+    resolve_db_path(app_data)
+}
+
+/// Resolve the database location, take the single-instance lock and open it.
+/// The lock is skipped when `ALLOW_MULTIPLE_ENV_VAR` is set.
+fn open_database(
+    app_handle: &tauri::AppHandle,
+) -> Result<(Database, DbLocation, Option<InstanceLock>), DbError> {
+    let location = get_db_path(app_handle)?;
+    let lock = if std::env::var_os(instance::ALLOW_MULTIPLE_ENV_VAR).is_some() {
+        println!("[Setup] {} set, skipping the single-instance lock", instance::ALLOW_MULTIPLE_ENV_VAR);
+        None
+    } else {
+        Some(InstanceLock::acquire(&location.path)?)
+    };
+    let database = Database::new(location.path.clone())?;
+    Ok((database, location, lock))
+}
+
+/// Open the replay tape that lives next to the database
+fn open_tape(db_path: &std::path::Path) -> Option<tape::TapeRecorder> {
+    let path = db_path.with_file_name(tape::TAPE_FILE_NAME);
+    match tape::TapeRecorder::open(&path) {
+        Ok(recorder) => {
+            println!("[Tape] Recording readings to {:?}", path);
+            Some(recorder)
+        }
+        Err(e) => {
+            eprintln!("[Tape] Failed to open {:?}: {}", path, e);
+            None
+        }
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -204,33 +1100,97 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
-            let db_path = get_db_path(app.handle());
-            let database = Database::new(db_path).expect("Failed to initialize database");
-            
-            let collector = create_collector();
-            let sessionizer = Arc::new(Mutex::new(Sessionizer::new(SessionizerConfig::default())));
-            let database = Arc::new(Mutex::new(database));
-            let scheduler_engine = Arc::new(SchedulerEngine::new());
+            let (mut database, db_location, instance_lock) = match open_database(app.handle()) {
+                Ok(opened) => opened,
+                Err(e) => {
+                    eprintln!("[Setup] Failed to open database: {}", e);
+                    let message = match e {
+                        DbError::AlreadyRunning(_) => {
+                            "Timewarden is already running. Look for it in the system tray.".to_string()
+                        }
+                        e => format!(
+                            "Timewarden couldn't find a place to store its data and will now close.\n\n{}",
+                            e
+                        ),
+                    };
+                    let handle = app.handle().clone();
+                    app.dialog()
+                        .message(message)
+                        .title("Timewarden")
+                        .kind(MessageDialogKind::Error)
+                        .show(move |_| handle.exit(1));
+                    return Ok(());
+                }
+            };
+
+            if db_location.is_fallback {
+                println!("[Setup] Using fallback database location {:?}", db_location.path);
+                let _ = app
+                    .notification()
+                    .builder()
+                    .title("Timewarden - Storage Warning")
+                    .body(format!(
+                        "App data folder unavailable. Data is being saved to {}",
+                        db_location.path.display()
+                    ))
+                    .show();
+            }
             
-            let app_state = Arc::new(AppState {
-                sessionizer: sessionizer.clone(),
-                collector: collector.clone(),
-                database: database.clone(),
-                scheduler_engine: scheduler_engine.clone(),
-            });
+            let config = Config::load(&database);
+            database.set_report_timezone(config.report_timezone);
+            database.set_day_attribution(config.day_attribution);
 
-            // Start background polling with app handle for notifications
-            start_polling_loop(app_state, app.handle().clone());
+            let collector = create_collector();
+            collector.set_idle_input_mode(config.idle_input_mode);
+            let scheduler_engine = Arc::new(SchedulerEngine::with_config(config.scheduler_config()));
+            scheduler_engine.set_timezone(config.report_timezone);
+            match database.load_schedule_states() {
+                Ok(states) => scheduler_engine.restore_states(states),
+                Err(e) => eprintln!("[DB Error] Failed to load schedule state: {}", e),
+            }
 
-            // Manage state for commands
-            app.manage(AppState {
-                sessionizer,
+            let mut sessionizer = Sessionizer::new(config.sessionizer_config());
+            sessionizer.set_timezone(config.report_timezone);
+            let engine = Arc::new(TrackingEngine::new(
                 collector,
+                sessionizer,
                 database,
                 scheduler_engine,
+                ForegroundDebouncer::new(config.foreground_debounce_ticks),
+            ));
+            *engine.app_id_sources.lock().unwrap() = config.app_id_sources;
+            *engine.title_capture_allowlist.lock().unwrap() = config.title_capture_allowlist;
+            *engine.treat_as_active.lock().unwrap() = config.treat_as_active;
+            engine.record_collector_errors.store(config.record_collector_errors, Ordering::Relaxed);
+            if config.record_tape {
+                engine.set_tape(open_tape(&db_location.path));
+            }
+
+            // Manage state before the first tick so notifications can reach it
+            app.manage(AppState {
+                engine: engine.clone(),
+                notify_new_apps: Arc::new(AtomicBool::new(config.notify_new_apps)),
+                group_notifications: Arc::new(AtomicBool::new(config.group_notifications)),
+                notification_health: Arc::new(std::sync::Mutex::new(NotificationHealth::default())),
+                instance_lock: std::sync::Mutex::new(instance_lock),
+                notifications_muted_until: Arc::new(std::sync::Mutex::new(None)),
+                held_notifications: Arc::new(std::sync::Mutex::new(HeldNotifications::default())),
             });
 
+            // Start background polling with app handle for notifications
+            let app_handle = app.handle().clone();
+            engine.start(
+                tauri::async_runtime::handle().inner(),
+                Duration::from_secs(1),
+                move |outcome| {
+                    if let Some(state) = app_handle.try_state::<AppState>() {
+                        notify_tick_outcome(&state, &app_handle, outcome);
+                    }
+                },
+            );
+
             // System Tray
             use tauri::menu::{Menu, MenuItem};
             use tauri::tray::TrayIconBuilder;
@@ -274,17 +1234,108 @@ pub fn run() {
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
-            greet,
+            app_version,
             get_current_app,
             get_idle_seconds,
+            collector_capabilities,
+            get_app_icon,
+            get_collector_perf,
+            get_collector_reliability,
+            set_record_collector_errors,
+            set_idle_input_mode,
+            set_idle_threshold_by_weekday,
+            set_call_keeps_active,
+            set_session_key,
+            set_foreground_debounce_ticks,
+            set_app_id_sources,
+            set_title_capture_allowlist,
+            set_treat_as_active,
             get_today_sessions,
             get_app_totals_today,
+            get_app_totals_today_live,
+            get_pending_sessions,
+            get_new_apps_since,
+            set_notify_new_apps,
+            set_group_notifications,
+            mute_notifications,
+            unmute_notifications,
+            set_record_mode,
+            get_report_timezone,
+            set_report_timezone,
+            set_day_attribution,
+            get_setting,
+            set_setting,
+            get_app_usage_bounds,
+            get_session_length_histogram,
+            get_avg_session_length_per_app,
+            get_compliance_trend,
+            get_compliance_by_hour,
+            get_deepest_focus_by_day,
+            get_idle_breakdown,
+            get_weekday_stats,
+            get_rolling_daily_average,
+            get_active_day_streak,
+            get_apps_during_schedule,
+            get_compliant_active_seconds,
+            get_day_schedule_report,
+            get_distraction_precursors,
+            get_idle_during_schedule,
+            get_focus_score,
+            get_time_to_first_focus,
+            get_schedule_stats,
+            add_manual_session,
+            get_daily_totals,
+            rebuild_totals_cache,
+            rename_app,
+            find_overlapping_sessions,
+            fix_overlapping_sessions,
+            export_sessions_csv,
+            export_app_totals_csv,
+            export_pivot_csv,
+            get_weekly_timesheet,
+            export_timesheet_csv,
+            export_sessions_csv_filtered,
+            backup_db,
+            move_data_dir,
+            export_day_ics,
+            render_day_timeline_svg,
+            export_config_bundle,
+            import_config_bundle,
             get_all_schedules,
             create_schedule,
             update_schedule,
             delete_schedule,
-            toggle_schedule
+            toggle_schedule,
+            toggle_schedules_by_tag,
+            get_schedule_status,
+            get_window_remaining,
+            explain_schedule,
+            get_all_compliance_logs,
+            get_current_violations,
+            get_schedules_ranked,
+            get_schedule_conflicts,
+            suggest_schedules,
+            get_all_goals,
+            create_goal,
+            delete_goal,
+            get_goal_adherence,
+            get_all_neglect_reminders,
+            create_neglect_reminder,
+            delete_neglect_reminder,
+            get_all_project_rules,
+            create_project_rule,
+            delete_project_rule,
+            get_project_totals
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Stop the polling loop and wait for the current tick to finish
+                let Some(state) = app_handle.try_state::<AppState>() else {
+                    return;
+                };
+                tauri::async_runtime::block_on(state.engine.stop());
+            }
+        });
 }
@@ -1,11 +1,13 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, NaiveTime, Utc, Weekday};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc, Weekday};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AppInfo {
     pub process_name: String,
     pub app_title: Option<String>,
     pub bundle_id: Option<String>,
+    /// Full path of the executable, when the collector can tell
+    pub process_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -17,6 +19,38 @@ pub struct Session {
     pub end_time: Option<DateTime<Utc>>,
     pub duration_seconds: Option<i64>,
     pub is_idle: bool,
+    /// Entered by the user rather than collected
+    pub manual: bool,
+    /// Full path of the executable, when the collector reported it
+    pub process_path: Option<String>,
+    /// Times another app briefly took focus during the session
+    pub interruptions: u32,
+    /// Smoothed keyboard and mouse events per minute, when the collector can measure it
+    pub input_rate: Option<f64>,
+}
+
+/// Reserved `app_id` (and title) of idle sessions. It is only a label:
+/// aggregations must go by `Session::is_idle`, so a real app that happens to be
+/// called "Idle" is still counted as active time.
+pub const IDLE_APP_ID: &str = "Idle";
+
+impl Session {
+    /// An idle session from `start_time`, open while `end_time` is None
+    pub fn idle(start_time: DateTime<Utc>, end_time: Option<DateTime<Utc>>) -> Self {
+        Self {
+            id: None,
+            app_id: IDLE_APP_ID.to_string(),
+            app_name: Some(IDLE_APP_ID.to_string()),
+            start_time,
+            end_time,
+            duration_seconds: end_time.map(|end| (end - start_time).num_seconds()),
+            is_idle: true,
+            manual: false,
+            process_path: None,
+            interruptions: 0,
+            input_rate: None,
+        }
+    }
 }
 
 /// A schedule defines when certain apps should be used
@@ -25,12 +59,77 @@ pub struct Schedule {
     pub id: Option<i64>,
     pub name: String,
     pub start_time: NaiveTime,          // e.g., 09:00
-    pub end_time: NaiveTime,            // e.g., 17:00
+    pub end_time: Option<NaiveTime>,    // e.g., 17:00 (None = until end of local day)
     pub days: Vec<Weekday>,             // Mon-Sun
     pub expected_apps: Vec<String>,     // List of allowed app names
     pub check_interval_secs: u32,       // Default: 300 (5 min)
     pub grace_period_secs: u32,         // Default: 60 (1 min)
     pub enabled: bool,
+    /// How entries in `expected_apps` are matched against the foreground app
+    #[serde(default)]
+    pub match_mode: MatchMode,
+    /// Optional group label (e.g. "work") for toggling schedules together
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Time ranges with their own allowlist, replacing `expected_apps` while
+    /// active (first match wins)
+    #[serde(default)]
+    pub windows: Vec<ScheduleWindow>,
+    /// Last local day the schedule applies; afterwards it disables itself
+    #[serde(default)]
+    pub active_until: Option<NaiveDate>,
+    /// Whether the one-time "schedule has ended" notice went out
+    #[serde(default)]
+    pub end_notified: bool,
+    /// Receives a JSON POST whenever a violation notification fires
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+/// Part of a schedule's day with its own expected apps, e.g. a browser that's
+/// fine in the morning but not the afternoon
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleWindow {
+    pub start_time: NaiveTime,
+    /// None = until end of local day
+    pub end_time: Option<NaiveTime>,
+    pub expected_apps: Vec<String>,
+}
+
+/// How a schedule's expected app entries match the foreground app (case-insensitive)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchMode {
+    /// The whole app name must equal the entry
+    Exact,
+    /// The entry appears anywhere in the app name (default)
+    #[default]
+    Substring,
+    /// `*` matches any run of characters and `?` a single character
+    Glob,
+    /// The entry is a regular expression searched in the app name
+    Regex,
+}
+
+impl MatchMode {
+    /// Name stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchMode::Exact => "Exact",
+            MatchMode::Substring => "Substring",
+            MatchMode::Glob => "Glob",
+            MatchMode::Regex => "Regex",
+        }
+    }
+
+    /// Parse a stored name; unknown names fall back to the default
+    pub fn from_stored(name: &str) -> Self {
+        match name {
+            "Exact" => MatchMode::Exact,
+            "Glob" => MatchMode::Glob,
+            "Regex" => MatchMode::Regex,
+            _ => MatchMode::Substring,
+        }
+    }
 }
 
 impl Default for Schedule {
@@ -39,16 +138,74 @@ impl Default for Schedule {
             id: None,
             name: String::new(),
             start_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
-            end_time: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(17, 0, 0),
             days: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
             expected_apps: Vec::new(),
             check_interval_secs: 300,
             grace_period_secs: 60,
             enabled: true,
+            match_mode: MatchMode::default(),
+            tag: None,
+            windows: Vec::new(),
+            active_until: None,
+            end_notified: false,
+            webhook_url: None,
         }
     }
 }
 
+/// A daily usage target: time in `apps` should reach `target_seconds` on each of `days`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Goal {
+    pub id: Option<i64>,
+    pub name: String,
+    pub apps: Vec<String>,              // App IDs whose time counts toward the goal
+    pub target_seconds: i64,
+    pub days: Vec<Weekday>,             // Days the goal applies to
+}
+
+/// Reminder to use an app that hasn't been used for a while, checked between
+/// `window_start` and `window_end` (local time)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NeglectReminder {
+    pub id: Option<i64>,
+    pub app_id: String,
+    pub remind_after_secs: i64,         // Gap since last use that triggers a reminder
+    pub window_start: NaiveTime,
+    pub window_end: NaiveTime,
+    pub enabled: bool,
+}
+
+/// Routes sessions of `process` (case-insensitive), optionally only those
+/// whose window title matches `title_pattern` (regex), to a billing project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRule {
+    pub id: Option<i64>,
+    pub project: String,
+    pub process: String,
+    pub title_pattern: Option<String>,
+}
+
+/// A week of project time: one row per project, one column per local day
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimesheetGrid {
+    /// The seven days of the week, in order
+    pub days: Vec<NaiveDate>,
+    /// Projects with any time that week, most first
+    pub rows: Vec<TimesheetRow>,
+    /// Column totals, one per day
+    pub day_totals: Vec<i64>,
+    pub total_seconds: i64,
+}
+
+/// One project's seconds per day of a `TimesheetGrid`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimesheetRow {
+    pub project: String,
+    pub seconds: Vec<i64>,
+    pub total_seconds: i64,
+}
+
 /// A log entry for compliance checks
 #[derive(Debug, Clone, Serialize)]
 pub struct ComplianceLog {
@@ -58,3 +215,105 @@ pub struct ComplianceLog {
     pub is_compliant: bool,
     pub current_app: Option<String>,
 }
+
+/// A compliance log entry together with its schedule's name
+#[derive(Debug, Clone, Serialize)]
+pub struct ComplianceLogDto {
+    #[serde(flatten)]
+    pub log: ComplianceLog,
+    pub schedule_name: String,
+}
+
+/// Live grace/notification status of a schedule
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleStatusDto {
+    pub schedule_id: i64,
+    pub in_grace: bool,
+    pub grace_remaining_secs: Option<u64>,
+    pub last_notification_secs_ago: Option<u64>,
+    pub consecutive_non_compliant: u32,
+}
+
+/// Why `SchedulerEngine::evaluate` would or wouldn't notify for a schedule right
+/// now, gate by gate, for diagnosing missed notifications
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EvalExplanation {
+    pub schedule_id: i64,
+    pub enabled: bool,
+    pub within_schedule: bool,
+    /// The check interval has elapsed since the last check
+    pub should_check: bool,
+    pub check_remaining_secs: Option<u64>,
+    pub is_compliant: bool,
+    /// A violation now would still be inside the grace period
+    pub in_grace: bool,
+    pub grace_remaining_secs: Option<u64>,
+    /// A notification went out too recently to send another
+    pub rate_limited: bool,
+    pub rate_limit_remaining_secs: Option<u64>,
+    /// All gates pass: evaluating now would notify
+    pub would_notify: bool,
+}
+
+/// Summary of how well a schedule was followed over a range
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScheduleStats {
+    pub schedule_id: i64,
+    pub average_compliance_rate: Option<f64>, // Mean daily rate; None without logs
+    pub notifications_sent: i64,
+    pub most_common_distraction: Option<String>,
+    pub violations_per_active_day: f64,       // Non-compliant logs per day with any logs
+}
+
+/// How well a schedule was followed on one day, from that day's sessions
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DayScheduleResult {
+    pub schedule_id: i64,
+    pub schedule_name: String,
+    /// Active seconds inside the schedule's windows on apps it allowed
+    pub compliant_seconds: i64,
+    /// All active seconds inside the schedule's windows
+    pub in_window_seconds: i64,
+    /// `compliant_seconds / in_window_seconds`; None without in-window time
+    pub compliance_rate: Option<f64>,
+}
+
+/// How often the collector delivered a usable reading over a range
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CollectorReliability {
+    pub ticks: i64,
+    pub failures: i64,
+    /// Share of ticks without a failure; None without recorded ticks
+    pub success_rate: Option<f64>,
+    /// Failure count per `CollectorFailure` reason, most first
+    pub failures_by_reason: Vec<(String, i64)>,
+}
+
+/// How relevant a schedule is right now, most relevant first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum ScheduleRelevance {
+    /// Active and the current app breaks it
+    Violated,
+    /// Active and being followed
+    Compliant,
+    /// Starts later today
+    UpcomingToday,
+    /// Disabled, or not active again today
+    Inactive,
+}
+
+/// A schedule with its current relevance, for the main screen
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedSchedule {
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    pub relevance: ScheduleRelevance,
+}
+
+/// A schedule that is active right now but not being followed
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleViolation {
+    pub schedule_id: i64,
+    pub schedule_name: String,
+    pub current_app: String,
+}
@@ -0,0 +1,191 @@
+use crate::engine::ScheduleAlert;
+use chrono::{DateTime, Utc};
+
+/// Consecutive failures before the user is told notifications look broken
+pub const ESCALATION_THRESHOLD: u32 = 3;
+
+/// Tracks failed notification attempts so repeated failures are reported once
+/// instead of being swallowed.
+#[derive(Debug, Default)]
+pub struct NotificationHealth {
+    /// Failures since the last successful notification
+    consecutive_failures: u32,
+    /// Failures since startup
+    total_failures: u32,
+    /// Whether the user has already been told
+    escalated: bool,
+}
+
+impl NotificationHealth {
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+    }
+
+    /// Record a failed notification. Returns true exactly once per run, when
+    /// failures first reach `ESCALATION_THRESHOLD` in a row.
+    pub fn record_failure(&mut self) -> bool {
+        self.consecutive_failures += 1;
+        self.total_failures += 1;
+        if self.escalated || self.consecutive_failures < ESCALATION_THRESHOLD {
+            return false;
+        }
+        self.escalated = true;
+        true
+    }
+
+    pub fn total_failures(&self) -> u32 {
+        self.total_failures
+    }
+}
+
+/// Whether notifications are globally muted at `now` (muted up to, not including, `muted_until`)
+pub fn is_muted(muted_until: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    muted_until.is_some_and(|until| now < until)
+}
+
+/// One-time notices (new apps, reminders, schedule ends) held back while
+/// notifications are muted. Repeating schedule alerts aren't held; a violation
+/// that persists is alerted again after the mute.
+#[derive(Debug, Default)]
+pub struct HeldNotifications {
+    held: Vec<NotificationContent>,
+}
+
+impl HeldNotifications {
+    /// The notices to show now: none while `muted` (they're held instead),
+    /// otherwise everything held so far followed by `notices`
+    pub fn release(&mut self, notices: Vec<NotificationContent>, muted: bool) -> Vec<NotificationContent> {
+        if muted {
+            self.held.extend(notices);
+            return Vec::new();
+        }
+        let mut due = std::mem::take(&mut self.held);
+        due.extend(notices);
+        due
+    }
+}
+
+/// Title and body of a notification to show
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationContent {
+    pub title: String,
+    pub body: String,
+}
+
+const SCHEDULE_ALERT_TITLE: &str = "Timewarden - Schedule Alert";
+
+/// Notifications for one tick's schedule alerts: one per alert, or with `grouped`
+/// a single notification listing every violated schedule
+pub fn schedule_alert_notifications(alerts: &[ScheduleAlert], grouped: bool) -> Vec<NotificationContent> {
+    if !grouped || alerts.len() < 2 {
+        return alerts
+            .iter()
+            .map(|alert| NotificationContent {
+                title: SCHEDULE_ALERT_TITLE.to_string(),
+                body: format!(
+                    "You're using {} during '{}'. Expected: {}",
+                    alert.current_app,
+                    alert.schedule.name,
+                    alert.schedule.expected_apps.join(", ")
+                ),
+            })
+            .collect();
+    }
+
+    let schedules: Vec<String> = alerts
+        .iter()
+        .map(|alert| format!("'{}'", alert.schedule.name))
+        .collect();
+    vec![NotificationContent {
+        title: SCHEDULE_ALERT_TITLE.to_string(),
+        body: format!(
+            "You're using {} during {} schedules: {}",
+            alerts[0].current_app,
+            alerts.len(),
+            schedules.join(", ")
+        ),
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Schedule;
+
+    fn alert(schedule: &str) -> ScheduleAlert {
+        ScheduleAlert {
+            schedule: Schedule {
+                name: schedule.to_string(),
+                expected_apps: vec!["code".to_string()],
+                ..Schedule::default()
+            },
+            current_app: "Steam.exe".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_grouped_alerts_coalesce_into_one() {
+        let alerts = vec![alert("Work"), alert("Deep focus"), alert("No games")];
+
+        let separate = schedule_alert_notifications(&alerts, false);
+        assert_eq!(separate.len(), 3);
+        assert_eq!(separate[0].body, "You're using Steam.exe during 'Work'. Expected: code");
+
+        let grouped = schedule_alert_notifications(&alerts, true);
+        assert_eq!(
+            grouped,
+            vec![NotificationContent {
+                title: SCHEDULE_ALERT_TITLE.to_string(),
+                body: "You're using Steam.exe during 3 schedules: 'Work', 'Deep focus', 'No games'".to_string(),
+            }]
+        );
+        assert_eq!(schedule_alert_notifications(&alerts[..1], true), separate[..1]);
+        assert!(schedule_alert_notifications(&[], true).is_empty());
+    }
+
+    #[test]
+    fn test_mute_window() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let until = now + chrono::Duration::minutes(30);
+        assert!(!is_muted(None, now));
+        assert!(is_muted(Some(until), now));
+        assert!(is_muted(Some(until), until - chrono::Duration::seconds(1)));
+        assert!(!is_muted(Some(until), until));
+    }
+
+    #[test]
+    fn test_notices_held_while_muted() {
+        let notice = |body: &str| NotificationContent {
+            title: "Timewarden - Reminder".to_string(),
+            body: body.to_string(),
+        };
+        let mut held = HeldNotifications::default();
+        assert!(held.release(vec![notice("first")], true).is_empty());
+        assert!(held.release(vec![notice("second")], true).is_empty());
+
+        // Delivered in order once unmuted, then not again
+        assert_eq!(
+            held.release(vec![notice("third")], false),
+            vec![notice("first"), notice("second"), notice("third")]
+        );
+        assert!(held.release(Vec::new(), false).is_empty());
+    }
+
+    #[test]
+    fn test_escalates_once_after_repeated_failures() {
+        let mut health = NotificationHealth::default();
+        assert!(!health.record_failure());
+        // A success resets the streak
+        health.record_success();
+        assert!(!health.record_failure());
+        assert!(!health.record_failure());
+        assert!(health.record_failure());
+        // Already escalated: stay quiet
+        assert!(!health.record_failure());
+        health.record_success();
+        for _ in 0..ESCALATION_THRESHOLD {
+            assert!(!health.record_failure());
+        }
+        assert_eq!(health.total_failures(), 8);
+    }
+}
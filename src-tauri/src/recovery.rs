@@ -0,0 +1,130 @@
+//! Keeps the polling loop alive when a tick panics (e.g. a misbehaving
+//! collector): the panic is contained to the tick, the in-progress session is
+//! saved as pending, and tracking resumes after a short delay.
+
+use crate::sessionizer::Sessionizer;
+use crate::storage::Database;
+use chrono::Utc;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// How long the polling loop pauses after a panicking tick
+pub const PANIC_RESTART_DELAY: Duration = Duration::from_secs(2);
+
+/// Run one tick on its own task so a panic can't take down the polling loop.
+/// Returns None if the tick panicked.
+pub async fn run_tick_catching_panics<Fut, T>(tick: Fut) -> Option<T>
+where
+    Fut: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    match tokio::spawn(tick).await {
+        Ok(output) => Some(output),
+        Err(e) => {
+            match e.try_into_panic() {
+                Ok(payload) => {
+                    let message = payload
+                        .downcast_ref::<&str>()
+                        .map(|s| s.to_string())
+                        .or_else(|| payload.downcast_ref::<String>().cloned())
+                        .unwrap_or_else(|| "unknown panic".to_string());
+                    eprintln!("[Poll] Tick panicked: {}", message);
+                }
+                Err(e) => eprintln!("[Poll] Tick did not finish: {}", e),
+            }
+            None
+        }
+    }
+}
+
+/// Save the in-progress session as pending, wait `delay`, then close it at the
+/// time of the panic. If the app dies during the delay the pending row is left
+/// for crash recovery.
+pub async fn recover_from_panic(
+    sessionizer: &Mutex<Sessionizer>,
+    database: &Mutex<Database>,
+    delay: Duration,
+    shutdown: &CancellationToken,
+) {
+    let panicked_at = Utc::now();
+
+    if let Some(session) = sessionizer.lock().await.take_in_progress() {
+        match database.lock().await.insert_pending_session(&session) {
+            Ok(id) => println!("[Poll] Saved in-progress session {} | {} as pending", id, session.app_id),
+            Err(e) => eprintln!("[Poll] Failed to save in-progress session: {}", e),
+        }
+    }
+
+    tokio::select! {
+        _ = shutdown.cancelled() => {}
+        _ = tokio::time::sleep(delay) => {}
+    }
+
+    if let Err(e) = database.lock().await.close_pending_sessions(panicked_at) {
+        eprintln!("[Poll] Failed to close pending sessions: {}", e);
+    }
+    println!("[Poll] Resuming tracking after panic");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::ForegroundCollector;
+    use crate::models::AppInfo;
+    use crate::sessionizer::{SessionState, SessionizerConfig};
+    use std::sync::Arc;
+
+    struct PanickingCollector;
+
+    impl ForegroundCollector for PanickingCollector {
+        fn get_foreground_app(&self) -> Option<AppInfo> {
+            panic!("collector exploded")
+        }
+
+        fn get_idle_seconds(&self) -> u64 {
+            0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_panicking_tick_saves_in_progress_session() {
+        let sessionizer = Mutex::new(Sessionizer::new(SessionizerConfig::default()));
+        let database = Mutex::new(Database::in_memory());
+        let start = Utc::now() - chrono::Duration::seconds(90);
+        sessionizer.lock().await.update_at(
+            Some(AppInfo {
+                process_name: "Code.exe".to_string(),
+                app_title: None,
+                bundle_id: None,
+                process_path: None,
+            }),
+            0,
+            false,
+            start,
+        );
+
+        let collector: Arc<dyn ForegroundCollector> = Arc::new(PanickingCollector);
+        let survived = run_tick_catching_panics(async move {
+            collector.get_foreground_app();
+        })
+        .await;
+        assert!(survived.is_none());
+
+        recover_from_panic(&sessionizer, &database, Duration::from_millis(10), &CancellationToken::new()).await;
+
+        assert!(matches!(sessionizer.lock().await.current_state(), SessionState::Inactive));
+        let db = database.lock().await;
+        assert!(db.get_pending_session().unwrap().is_none());
+        let sessions = db
+            .get_sessions_in_range(start - chrono::Duration::seconds(1), Utc::now())
+            .unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].app_id, "Code.exe");
+        assert!(sessions[0].duration_seconds.unwrap() >= 90);
+
+        // Later ticks run normally
+        assert_eq!(run_tick_catching_panics(async { 7 }).await, Some(7));
+    }
+}
@@ -1,76 +1,308 @@
-use crate::models::Schedule;
-use chrono::{Datelike, Local};
-use std::collections::HashMap;
+use crate::models::{
+    AppInfo, EvalExplanation, MatchMode, RankedSchedule, Schedule, ScheduleRelevance, ScheduleStatusDto, ScheduleViolation,
+};
+use crate::timezone::ReportTimezone;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
 
-/// Tracks the state of each schedule for rate limiting and grace periods
-#[derive(Debug, Clone)]
+/// Tracks the state of each schedule for rate limiting and grace periods.
+/// Timers are wall-clock so grace and notification state can survive a restart.
+#[derive(Debug, Clone, Default)]
 pub struct ScheduleState {
-    pub last_check: Option<Instant>,
-    pub last_notification: Option<Instant>,
-    pub grace_started: Option<Instant>,
+    pub last_check: Option<DateTime<Utc>>,
+    pub last_notification: Option<DateTime<Utc>>,
+    pub grace_started: Option<DateTime<Utc>>,
     pub consecutive_non_compliant: u32,
+    /// Compliance value and time of the last persisted compliance log
+    pub last_log: Option<(bool, DateTime<Utc>)>,
 }
 
-impl Default for ScheduleState {
-    fn default() -> Self {
-        Self {
-            last_check: None,
-            last_notification: None,
-            grace_started: None,
-            consecutive_non_compliant: 0,
-        }
+/// Whether a time of day falls in `start..=end`. No end runs until midnight;
+/// an end before the start wraps past midnight.
+fn time_in_window(start: NaiveTime, end: Option<NaiveTime>, time: NaiveTime) -> bool {
+    match end {
+        // Open-ended: e.g., 21:00 until midnight
+        None => time >= start,
+        // Normal case: e.g., 09:00 - 17:00
+        Some(end) if start <= end => time >= start && time <= end,
+        // Overnight case: e.g., 22:00 - 06:00
+        Some(end) => time >= start || time <= end,
     }
 }
 
+/// Time-of-day ranges (inclusive) a window covers on a scheduled day. Windows
+/// are gated by weekday, so an overnight window covers both ends of that day.
+fn window_ranges(start: NaiveTime, end: Option<NaiveTime>) -> Vec<(NaiveTime, NaiveTime)> {
+    let end_of_day = NaiveTime::from_hms_opt(23, 59, 59).unwrap();
+    match end {
+        None => vec![(start, end_of_day)],
+        Some(end) if start <= end => vec![(start, end)],
+        Some(end) => vec![(NaiveTime::MIN, end), (start, end_of_day)],
+    }
+}
+
+/// Whether two schedules are both active at some moment of the week
+fn windows_overlap(a: &Schedule, b: &Schedule) -> bool {
+    if !a.days.iter().any(|day| b.days.contains(day)) {
+        return false;
+    }
+    let b_ranges = window_ranges(b.start_time, b.end_time);
+    window_ranges(a.start_time, a.end_time)
+        .into_iter()
+        .any(|(a_start, a_end)| b_ranges.iter().any(|&(b_start, b_end)| a_start <= b_end && b_start <= a_end))
+}
+
+/// Local half-open intervals during which `schedule` applies on `day`, matching
+/// `is_within_schedule_at`. Empty if the day isn't scheduled or is past `active_until`.
+pub fn window_intervals_on(schedule: &Schedule, day: NaiveDate) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    if !schedule.days.contains(&day.weekday()) || schedule.active_until.is_some_and(|until| day > until) {
+        return Vec::new();
+    }
+    window_ranges(schedule.start_time, schedule.end_time)
+        .into_iter()
+        .map(|(start, end)| (day.and_time(start), day.and_time(end) + chrono::Duration::seconds(1)))
+        .collect()
+}
+
+/// Expected-app entries with this prefix match the executable path instead of the name
+pub const PATH_PREFIX: &str = "path:";
+
+/// Whether `app` matches one expected-app entry (case-insensitive).
+/// Invalid regex patterns match nothing.
+fn app_matches(mode: MatchMode, pattern: &str, app: &str) -> bool {
+    let regex = match mode {
+        MatchMode::Exact => return app.to_lowercase() == pattern.to_lowercase(),
+        MatchMode::Substring => return app.to_lowercase().contains(&pattern.to_lowercase()),
+        MatchMode::Glob => {
+            let body: String = pattern
+                .chars()
+                .map(|c| match c {
+                    '*' => ".*".to_string(),
+                    '?' => ".".to_string(),
+                    c => regex::escape(&c.to_string()),
+                })
+                .collect();
+            format!("^{}$", body)
+        }
+        MatchMode::Regex => pattern.to_string(),
+    };
+    regex::RegexBuilder::new(&regex)
+        .case_insensitive(true)
+        .build()
+        .is_ok_and(|re| re.is_match(app))
+}
+
+/// Whole seconds elapsed between `since` and `now` (0 if `since` is in the future)
+fn secs_since(since: DateTime<Utc>, now: DateTime<Utc>) -> u64 {
+    (now - since).num_seconds().max(0) as u64
+}
+
+/// Minimum seconds between two notifications for the same schedule
+const NOTIFY_RATE_LIMIT_SECS: u64 = 300;
+
+/// Seconds left of a `duration_secs` period that began at `since`, or None once it's over
+fn remaining_secs(since: DateTime<Utc>, duration_secs: u64, now: DateTime<Utc>) -> Option<u64> {
+    let elapsed = secs_since(since, now);
+    (elapsed < duration_secs).then(|| duration_secs - elapsed)
+}
+
+/// Configuration for the scheduler engine
+#[derive(Debug, Clone, Default)]
+pub struct SchedulerConfig {
+    /// Seconds between compliance logs while compliance is unchanged
+    /// (default: None = the schedule's check interval)
+    pub log_heartbeat_secs: Option<u32>,
+}
+
 /// Scheduler engine for evaluating compliance
 pub struct SchedulerEngine {
+    config: SchedulerConfig,
+    /// Timezone schedule windows are evaluated in
+    timezone: Mutex<ReportTimezone>,
     /// State for each schedule (keyed by schedule ID)
     states: Arc<Mutex<HashMap<i64, ScheduleState>>>,
+    /// Schedules whose persisted state changed since the last `take_dirty_states`
+    dirty: Mutex<HashSet<i64>>,
+}
+
+/// Decide whether a compliance reading should be logged.
+/// `last_logged` is the last logged value and seconds since it was written.
+/// Logs on every compliant/non-compliant transition, otherwise once per heartbeat.
+pub fn compliance_log_due(last_logged: Option<(bool, u64)>, is_compliant: bool, heartbeat_secs: u64) -> bool {
+    match last_logged {
+        None => true,
+        Some((was_compliant, _)) if was_compliant != is_compliant => true,
+        Some((_, elapsed)) => elapsed >= heartbeat_secs,
+    }
+}
+
+/// Whether a schedule's one-time "has ended" notice should go out on local `today`:
+/// its `active_until` day has passed and the notice hasn't been sent yet
+pub fn end_notice_due(schedule: &Schedule, today: NaiveDate) -> bool {
+    !schedule.end_notified && schedule.active_until.is_some_and(|until| today > until)
 }
 
 impl SchedulerEngine {
     pub fn new() -> Self {
+        Self::with_config(SchedulerConfig::default())
+    }
+
+    pub fn with_config(config: SchedulerConfig) -> Self {
         Self {
+            config,
+            timezone: Mutex::new(ReportTimezone::default()),
             states: Arc::new(Mutex::new(HashMap::new())),
+            dirty: Mutex::new(HashSet::new()),
         }
     }
 
+    /// Restore previously persisted schedule states (e.g. on startup)
+    pub fn restore_states(&self, restored: HashMap<i64, ScheduleState>) {
+        let mut states = self.states.lock().unwrap();
+        for (schedule_id, state) in restored {
+            states.insert(schedule_id, state);
+        }
+    }
+
+    /// Take the states that changed since the last call, for persisting
+    pub fn take_dirty_states(&self) -> Vec<(i64, ScheduleState)> {
+        let dirty = std::mem::take(&mut *self.dirty.lock().unwrap());
+        let states = self.states.lock().unwrap();
+        dirty
+            .into_iter()
+            .map(|id| (id, states.get(&id).cloned().unwrap_or_default()))
+            .collect()
+    }
+
+    fn mark_dirty(&self, schedule_id: i64) {
+        self.dirty.lock().unwrap().insert(schedule_id);
+    }
+
+    /// Timezone schedule windows are evaluated in
+    pub fn timezone(&self) -> ReportTimezone {
+        *self.timezone.lock().unwrap()
+    }
+
+    /// Set the timezone schedule windows are evaluated in
+    pub fn set_timezone(&self, tz: ReportTimezone) {
+        *self.timezone.lock().unwrap() = tz;
+    }
+
     /// Check if the current time falls within the schedule's time window
     pub fn is_within_schedule(&self, schedule: &Schedule) -> bool {
-        let now = Local::now();
+        self.is_within_schedule_at(schedule, self.timezone().now_local())
+    }
+
+    /// Check if a local wall-clock time falls within the schedule's time window
+    pub fn is_within_schedule_at(&self, schedule: &Schedule, now: NaiveDateTime) -> bool {
         let current_time = now.time();
         let current_day = now.weekday();
 
-        // Check if today is in the schedule's days
-        if !schedule.days.contains(&current_day) {
+        // Check if today is in the schedule's days, and the schedule hasn't ended
+        if !schedule.days.contains(&current_day) || schedule.active_until.is_some_and(|until| now.date() > until) {
             return false;
         }
 
-        // Check if current time is within the time window
-        if schedule.start_time <= schedule.end_time {
-            // Normal case: e.g., 09:00 - 17:00
-            current_time >= schedule.start_time && current_time <= schedule.end_time
-        } else {
-            // Overnight case: e.g., 22:00 - 06:00
-            current_time >= schedule.start_time || current_time <= schedule.end_time
+        time_in_window(schedule.start_time, schedule.end_time, current_time)
+    }
+
+    /// Index of the first entry in `schedule.windows` covering `now`'s time of
+    /// day, whose allowlist then replaces `expected_apps`. Only meaningful while
+    /// `now` is within the schedule.
+    pub fn active_window_at(&self, schedule: &Schedule, now: NaiveDateTime) -> Option<usize> {
+        schedule
+            .windows
+            .iter()
+            .position(|window| time_in_window(window.start_time, window.end_time, now.time()))
+    }
+
+    /// Next local time after `now` at which the schedule's window opens,
+    /// looking up to a week ahead. None if it has no days.
+    pub fn next_window_at(&self, schedule: &Schedule, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        now.date()
+            .iter_days()
+            .take(8)
+            .filter(|day| schedule.days.contains(&day.weekday()))
+            .map(|day| day.and_time(schedule.start_time))
+            .find(|start| *start > now)
+    }
+
+    /// Time left until the window containing `now` (local) closes, or None if
+    /// `now` is outside the schedule. Open-ended windows close at midnight;
+    /// overnight windows entered before midnight close the next day, or at
+    /// midnight if the schedule doesn't run the next day.
+    pub fn window_remaining(&self, schedule: &Schedule, now: NaiveDateTime) -> Option<chrono::Duration> {
+        if !self.is_within_schedule_at(schedule, now) {
+            return None;
         }
+        let today = now.date();
+        let end = match schedule.end_time {
+            None => today.succ_opt()?.and_time(chrono::NaiveTime::MIN),
+            Some(end_time) if schedule.start_time <= end_time => today.and_time(end_time),
+            Some(end_time) if now.time() >= schedule.start_time => {
+                let midnight = today.succ_opt()?.and_time(chrono::NaiveTime::MIN);
+                if self.is_within_schedule_at(schedule, midnight) {
+                    midnight.date().and_time(end_time)
+                } else {
+                    midnight
+                }
+            }
+            Some(end_time) => today.and_time(end_time),
+        };
+        Some(end - now)
     }
 
-    /// Check if the current app is compliant with the schedule
-    pub fn is_compliant(&self, schedule: &Schedule, current_app: &str) -> bool {
+    /// Enabled schedules whose window contains a local wall-clock time
+    pub fn active_schedules_at<'a>(&self, schedules: &'a [Schedule], now: NaiveDateTime) -> Vec<&'a Schedule> {
+        schedules
+            .iter()
+            .filter(|s| s.enabled && self.is_within_schedule_at(s, now))
+            .collect()
+    }
+
+    /// Check if the current app is compliant with the schedule while `window`
+    /// (from `active_window_at`) is active, or with `expected_apps` if None.
+    /// Entries prefixed with `!` are exclusions: the app must match an inclusion
+    /// (or there must be none) and match no exclusion.
+    /// Entries prefixed with `path:` never match here, see `is_app_compliant`.
+    pub fn is_compliant(&self, schedule: &Schedule, window: Option<usize>, current_app: &str) -> bool {
+        self.is_compliant_with_path(schedule, window, current_app, None)
+    }
+
+    /// Like `is_compliant`, also matching `path:` entries against the app's executable path
+    pub fn is_app_compliant(&self, schedule: &Schedule, window: Option<usize>, current_app: &AppInfo) -> bool {
+        self.is_compliant_with_path(schedule, window, &current_app.process_name, current_app.process_path.as_deref())
+    }
+
+    fn is_compliant_with_path(
+        &self,
+        schedule: &Schedule,
+        window: Option<usize>,
+        current_app: &str,
+        process_path: Option<&str>,
+    ) -> bool {
+        let expected_apps = window
+            .and_then(|i| schedule.windows.get(i))
+            .map_or(&schedule.expected_apps, |window| &window.expected_apps);
+
         // If no expected apps are specified, any app is compliant
-        if schedule.expected_apps.is_empty() {
+        if expected_apps.is_empty() {
             return true;
         }
 
-        // Check if current app matches any expected app (case-insensitive)
-        let current_lower = current_app.to_lowercase();
-        schedule
-            .expected_apps
+        let matches = |entry: &str| match entry.strip_prefix(PATH_PREFIX) {
+            Some(pattern) => process_path.is_some_and(|path| app_matches(schedule.match_mode, pattern, path)),
+            None => app_matches(schedule.match_mode, entry, current_app),
+        };
+
+        let (exclusions, inclusions): (Vec<&String>, Vec<&String>) = expected_apps
             .iter()
-            .any(|app| current_lower.contains(&app.to_lowercase()))
+            .partition(|app| app.starts_with('!'));
+
+        let included = inclusions.is_empty() || inclusions.iter().any(|app| matches(app));
+        let excluded = exclusions.iter().any(|app| matches(&app[1..]));
+        included && !excluded
     }
 
     /// Determine if enough time has passed since the last check
@@ -78,7 +310,7 @@ impl SchedulerEngine {
         let states = self.states.lock().unwrap();
         if let Some(state) = states.get(&schedule_id) {
             if let Some(last_check) = state.last_check {
-                return last_check.elapsed().as_secs() >= check_interval_secs as u64;
+                return secs_since(last_check, Utc::now()) >= check_interval_secs as u64;
             }
         }
         true // No previous check, should check
@@ -88,25 +320,25 @@ impl SchedulerEngine {
     pub fn mark_checked(&self, schedule_id: i64) {
         let mut states = self.states.lock().unwrap();
         let state = states.entry(schedule_id).or_default();
-        state.last_check = Some(Instant::now());
+        state.last_check = Some(Utc::now());
     }
 
     /// Check if we should send a notification (respecting grace period and rate limiting)
     pub fn should_notify(&self, schedule_id: i64, grace_period_secs: u32) -> bool {
+        let now = Utc::now();
         let mut states = self.states.lock().unwrap();
         let state = states.entry(schedule_id).or_default();
 
         // Check grace period
         if let Some(grace_started) = state.grace_started {
-            if grace_started.elapsed().as_secs() < grace_period_secs as u64 {
+            if secs_since(grace_started, now) < grace_period_secs as u64 {
                 return false; // Still in grace period
             }
         }
 
-        // Check rate limiting (don't notify more than once per check interval)
+        // Check rate limiting (don't notify more than once per 5 minutes)
         if let Some(last_notification) = state.last_notification {
-            if last_notification.elapsed().as_secs() < 300 {
-                // 5 minute rate limit
+            if secs_since(last_notification, now) < NOTIFY_RATE_LIMIT_SECS {
                 return false;
             }
         }
@@ -119,7 +351,9 @@ impl SchedulerEngine {
         let mut states = self.states.lock().unwrap();
         let state = states.entry(schedule_id).or_default();
         if state.grace_started.is_none() {
-            state.grace_started = Some(Instant::now());
+            state.grace_started = Some(Utc::now());
+            drop(states);
+            self.mark_dirty(schedule_id);
         }
     }
 
@@ -127,8 +361,12 @@ impl SchedulerEngine {
     pub fn reset_grace(&self, schedule_id: i64) {
         let mut states = self.states.lock().unwrap();
         if let Some(state) = states.get_mut(&schedule_id) {
-            state.grace_started = None;
-            state.consecutive_non_compliant = 0;
+            if state.grace_started.is_some() || state.consecutive_non_compliant > 0 {
+                state.grace_started = None;
+                state.consecutive_non_compliant = 0;
+                drop(states);
+                self.mark_dirty(schedule_id);
+            }
         }
     }
 
@@ -136,8 +374,224 @@ impl SchedulerEngine {
     pub fn mark_notified(&self, schedule_id: i64) {
         let mut states = self.states.lock().unwrap();
         let state = states.entry(schedule_id).or_default();
-        state.last_notification = Some(Instant::now());
+        state.last_notification = Some(Utc::now());
         state.consecutive_non_compliant += 1;
+        drop(states);
+        self.mark_dirty(schedule_id);
+    }
+
+    /// Check whether a compliance reading should be persisted, recording it if so
+    pub fn should_log(&self, schedule: &Schedule, is_compliant: bool) -> bool {
+        let heartbeat_secs = self
+            .config
+            .log_heartbeat_secs
+            .unwrap_or(schedule.check_interval_secs) as u64;
+
+        let mut states = self.states.lock().unwrap();
+        let state = states.entry(schedule.id.unwrap_or(0)).or_default();
+        let last_logged = state
+            .last_log
+            .map(|(compliant, at)| (compliant, secs_since(at, Utc::now())));
+
+        if compliance_log_due(last_logged, is_compliant, heartbeat_secs) {
+            state.last_log = Some((is_compliant, Utc::now()));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the current grace/notification status for a schedule
+    pub fn status(&self, schedule: &Schedule) -> ScheduleStatusDto {
+        self.status_at(schedule, Utc::now())
+    }
+
+    /// Get the grace/notification status for a schedule as of `now`
+    pub fn status_at(&self, schedule: &Schedule, now: DateTime<Utc>) -> ScheduleStatusDto {
+        let schedule_id = schedule.id.unwrap_or(0);
+        let states = self.states.lock().unwrap();
+        let state = states.get(&schedule_id).cloned().unwrap_or_default();
+
+        let grace_remaining_secs = state
+            .grace_started
+            .and_then(|started| remaining_secs(started, schedule.grace_period_secs as u64, now));
+
+        ScheduleStatusDto {
+            schedule_id,
+            in_grace: grace_remaining_secs.is_some(),
+            grace_remaining_secs,
+            last_notification_secs_ago: state
+                .last_notification
+                .map(|at| secs_since(at, now)),
+            consecutive_non_compliant: state.consecutive_non_compliant,
+        }
+    }
+
+    /// Enabled, currently active schedules that `current_app` violates.
+    /// Read-only: grace and notification state are left untouched.
+    pub fn current_violations(&self, schedules: &[Schedule], current_app: &AppInfo) -> Vec<ScheduleViolation> {
+        self.violations_at(schedules, current_app, self.timezone().now_local())
+    }
+
+    /// Schedules violated by `current_app` at a local wall-clock time
+    pub fn violations_at(
+        &self,
+        schedules: &[Schedule],
+        current_app: &AppInfo,
+        now: NaiveDateTime,
+    ) -> Vec<ScheduleViolation> {
+        schedules
+            .iter()
+            .filter(|s| s.enabled && self.is_within_schedule_at(s, now))
+            .filter(|s| !self.is_app_compliant(s, self.active_window_at(s, now), current_app))
+            .map(|s| ScheduleViolation {
+                schedule_id: s.id.unwrap_or(0),
+                schedule_name: s.name.clone(),
+                current_app: current_app.process_name.clone(),
+            })
+            .collect()
+    }
+
+    /// Schedules ordered for the main screen: violated, compliant, upcoming today
+    /// (soonest first), then inactive. Ties keep their input order.
+    pub fn rank_schedules(&self, schedules: &[Schedule], current_app: Option<&AppInfo>) -> Vec<RankedSchedule> {
+        self.rank_schedules_at(schedules, current_app, self.timezone().now_local())
+    }
+
+    /// `rank_schedules` at a local wall-clock time
+    pub fn rank_schedules_at(
+        &self,
+        schedules: &[Schedule],
+        current_app: Option<&AppInfo>,
+        now: NaiveDateTime,
+    ) -> Vec<RankedSchedule> {
+        let active = self.active_schedules_at(schedules, now);
+
+        let mut ranked: Vec<_> = schedules
+            .iter()
+            .map(|schedule| {
+                let next_start = self
+                    .next_window_at(schedule, now)
+                    .filter(|start| start.date() == now.date());
+                let relevance = if active.iter().any(|a| std::ptr::eq(*a, schedule)) {
+                    match current_app {
+                        Some(app) if !self.is_app_compliant(schedule, self.active_window_at(schedule, now), app) => ScheduleRelevance::Violated,
+                        _ => ScheduleRelevance::Compliant,
+                    }
+                } else if schedule.enabled && next_start.is_some() {
+                    ScheduleRelevance::UpcomingToday
+                } else {
+                    ScheduleRelevance::Inactive
+                };
+                (relevance, next_start, schedule)
+            })
+            .collect();
+        ranked.sort_by_key(|(relevance, next_start, _)| (*relevance, *next_start));
+
+        ranked
+            .into_iter()
+            .map(|(relevance, _, schedule)| RankedSchedule {
+                schedule: schedule.clone(),
+                relevance,
+            })
+            .collect()
+    }
+
+    /// Ids of enabled schedule pairs whose windows overlap on some day while
+    /// their expectations contradict: an app one lists is excluded by the other,
+    /// or both have allowlists with no app in common. Compares the schedules'
+    /// own `expected_apps`, not per-window lists.
+    pub fn find_conflicts(&self, schedules: &[Schedule]) -> Vec<(i64, i64)> {
+        let enabled: Vec<&Schedule> = schedules.iter().filter(|s| s.enabled).collect();
+        let mut conflicts = Vec::new();
+        for (i, a) in enabled.iter().enumerate() {
+            for b in &enabled[i + 1..] {
+                if windows_overlap(a, b) && self.expectations_contradict(a, b) {
+                    conflicts.push((a.id.unwrap_or(0), b.id.unwrap_or(0)));
+                }
+            }
+        }
+        conflicts
+    }
+
+    fn expectations_contradict(&self, a: &Schedule, b: &Schedule) -> bool {
+        let inclusions = |s: &'_ Schedule| -> Vec<String> {
+            s.expected_apps
+                .iter()
+                .filter(|app| !app.starts_with('!') && !app.starts_with(PATH_PREFIX))
+                .cloned()
+                .collect()
+        };
+        let (a_apps, b_apps) = (inclusions(a), inclusions(b));
+
+        let excluded_by = |s: &Schedule, app: &str| {
+            s.expected_apps
+                .iter()
+                .filter_map(|entry| entry.strip_prefix('!'))
+                .any(|pattern| app_matches(s.match_mode, pattern, app))
+        };
+        if a_apps.iter().any(|app| excluded_by(b, app)) || b_apps.iter().any(|app| excluded_by(a, app)) {
+            return true;
+        }
+
+        // Both restrict to specific apps and none of either list satisfies the other
+        !a_apps.is_empty()
+            && !b_apps.is_empty()
+            && !a_apps.iter().any(|app| self.is_compliant(b, None, app))
+            && !b_apps.iter().any(|app| self.is_compliant(a, None, app))
+    }
+
+    /// Walk the gates of `evaluate` for `current_app` at `now` without changing
+    /// any state. Every gate is reported, even those `evaluate` would not reach.
+    pub fn explain(&self, schedule: &Schedule, current_app: &AppInfo, now: DateTime<Utc>) -> EvalExplanation {
+        let schedule_id = schedule.id.unwrap_or(0);
+        let state = self
+            .states
+            .lock()
+            .unwrap()
+            .get(&schedule_id)
+            .cloned()
+            .unwrap_or_default();
+
+        let local_now = self.timezone().to_local(now);
+        let within_schedule = self.is_within_schedule_at(schedule, local_now);
+        let check_remaining_secs = state
+            .last_check
+            .and_then(|at| remaining_secs(at, schedule.check_interval_secs as u64, now));
+        let window = self.active_window_at(schedule, local_now);
+        let is_compliant = self.is_app_compliant(schedule, window, current_app);
+        // A first violation starts the grace period now
+        let grace_remaining_secs = remaining_secs(
+            state.grace_started.unwrap_or(now),
+            schedule.grace_period_secs as u64,
+            now,
+        );
+        let rate_limit_remaining_secs = state
+            .last_notification
+            .and_then(|at| remaining_secs(at, NOTIFY_RATE_LIMIT_SECS, now));
+
+        let explanation = EvalExplanation {
+            schedule_id,
+            enabled: schedule.enabled,
+            within_schedule,
+            should_check: check_remaining_secs.is_none(),
+            check_remaining_secs,
+            is_compliant,
+            in_grace: grace_remaining_secs.is_some(),
+            grace_remaining_secs,
+            rate_limited: rate_limit_remaining_secs.is_some(),
+            rate_limit_remaining_secs,
+            would_notify: false,
+        };
+        EvalExplanation {
+            would_notify: explanation.enabled
+                && explanation.within_schedule
+                && explanation.should_check
+                && !explanation.is_compliant
+                && !explanation.in_grace
+                && !explanation.rate_limited,
+            ..explanation
+        }
     }
 
     /// Evaluate a schedule and return if notification should be triggered
@@ -145,7 +599,7 @@ impl SchedulerEngine {
     pub fn evaluate(
         &self,
         schedule: &Schedule,
-        current_app: &str,
+        current_app: &AppInfo,
     ) -> (bool, bool) {
         let schedule_id = schedule.id.unwrap_or(0);
 
@@ -154,7 +608,8 @@ impl SchedulerEngine {
             return (false, true);
         }
 
-        if !self.is_within_schedule(schedule) {
+        let now = self.timezone().now_local();
+        if !self.is_within_schedule_at(schedule, now) {
             return (false, true);
         }
 
@@ -164,7 +619,8 @@ impl SchedulerEngine {
 
         self.mark_checked(schedule_id);
 
-        let is_compliant = self.is_compliant(schedule, current_app);
+        let window = self.active_window_at(schedule, now);
+        let is_compliant = self.is_app_compliant(schedule, window, current_app);
 
         if is_compliant {
             self.reset_grace(schedule_id);
@@ -188,3 +644,482 @@ impl Default for SchedulerEngine {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ScheduleWindow;
+
+    fn schedule_with_apps(apps: &[&str]) -> Schedule {
+        Schedule {
+            id: Some(1),
+            expected_apps: apps.iter().map(|a| a.to_string()).collect(),
+            ..Schedule::default()
+        }
+    }
+
+    fn app(name: &str, path: Option<&str>) -> AppInfo {
+        AppInfo {
+            process_name: name.to_string(),
+            app_title: None,
+            bundle_id: None,
+            process_path: path.map(|p| p.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_is_compliant_with_exclusions() {
+        let engine = SchedulerEngine::new();
+        let schedule = schedule_with_apps(&["chrome", "firefox", "!chrome_proxy"]);
+
+        assert!(engine.is_compliant(&schedule, None, "chrome.exe"));
+        assert!(engine.is_compliant(&schedule, None, "firefox.exe"));
+        assert!(!engine.is_compliant(&schedule, None, "chrome_proxy.exe"));
+        assert!(!engine.is_compliant(&schedule, None, "Slack.exe"));
+    }
+
+    #[test]
+    fn test_is_compliant_exclusions_only() {
+        let engine = SchedulerEngine::new();
+        let schedule = schedule_with_apps(&["!steam", "!discord"]);
+
+        assert!(engine.is_compliant(&schedule, None, "Code.exe"));
+        assert!(!engine.is_compliant(&schedule, None, "Steam.exe"));
+        assert!(!engine.is_compliant(&schedule, None, "Discord.exe"));
+    }
+
+    #[test]
+    fn test_is_compliant_plain_entries_unchanged() {
+        let engine = SchedulerEngine::new();
+        assert!(engine.is_compliant(&schedule_with_apps(&["Code"]), None, "Code.exe"));
+        assert!(!engine.is_compliant(&schedule_with_apps(&["Code"]), None, "Slack.exe"));
+        assert!(engine.is_compliant(&schedule_with_apps(&[]), None, "Slack.exe"));
+    }
+
+    #[test]
+    fn test_is_compliant_match_modes() {
+        let engine = SchedulerEngine::new();
+        let with_mode = |apps: &[&str], match_mode| Schedule {
+            match_mode,
+            ..schedule_with_apps(apps)
+        };
+
+        // Substring (default) accepts partial names; Exact does not
+        assert!(engine.is_compliant(&with_mode(&["note"], MatchMode::Substring), None, "notepad.exe"));
+        assert!(engine.is_compliant(&with_mode(&["note"], MatchMode::Substring), None, "OneNote.exe"));
+        assert!(!engine.is_compliant(&with_mode(&["note"], MatchMode::Exact), None, "notepad.exe"));
+        assert!(engine.is_compliant(&with_mode(&["notepad.exe"], MatchMode::Exact), None, "Notepad.exe"));
+
+        let glob = with_mode(&["note*.exe"], MatchMode::Glob);
+        assert!(engine.is_compliant(&glob, None, "Notepad.exe"));
+        assert!(!engine.is_compliant(&glob, None, "OneNote.exe"));
+
+        let regex = with_mode(&["^(code|devenv)\\.exe$", "!["], MatchMode::Regex);
+        assert!(engine.is_compliant(&regex, None, "Code.exe"));
+        assert!(!engine.is_compliant(&regex, None, "vscode.exe.bak"));
+    }
+
+    #[test]
+    fn test_path_entries_distinguish_same_basename() {
+        let engine = SchedulerEngine::new();
+        let schedule = schedule_with_apps(&["path:C:\\Tools\\Jupyter\\", "!path:\\games\\"]);
+        let notebook = app("python.exe", Some("C:\\Tools\\Jupyter\\python.exe"));
+        let game = app("python.exe", Some("D:\\Games\\Modded\\python.exe"));
+
+        assert!(engine.is_app_compliant(&schedule, None, &notebook));
+        assert!(!engine.is_app_compliant(&schedule, None, &game));
+        // Without a path, path entries can't match
+        assert!(!engine.is_app_compliant(&schedule, None, &app("python.exe", None)));
+        assert!(!engine.is_compliant(&schedule, None, "python.exe"));
+    }
+
+    #[test]
+    fn test_end_notice_fires_once() {
+        let until = chrono::NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+        let mut schedule = Schedule {
+            name: "Exam Prep".to_string(),
+            active_until: Some(until),
+            ..schedule_with_apps(&["anki"])
+        };
+
+        let mut fired = 0;
+        for day in until.pred_opt().unwrap().iter_days().take(5) {
+            if end_notice_due(&schedule, day) {
+                fired += 1;
+                schedule.end_notified = true;
+            }
+        }
+        assert_eq!(fired, 1);
+        assert!(!end_notice_due(&schedule_with_apps(&[]), until.succ_opt().unwrap()));
+
+        // The schedule stops applying after its last day
+        let engine = SchedulerEngine::new();
+        let last_day = chrono::NaiveDate::from_ymd_opt(2024, 6, 28).unwrap().and_hms_opt(10, 0, 0).unwrap();
+        schedule.active_until = Some(last_day.date());
+        assert!(engine.is_within_schedule_at(&schedule, last_day));
+        assert!(!engine.is_within_schedule_at(&schedule, last_day + chrono::Duration::days(3)));
+    }
+
+    #[test]
+    fn test_compliance_log_due_on_transition() {
+        assert!(compliance_log_due(None, true, 300));
+        assert!(compliance_log_due(Some((true, 5)), false, 300));
+        assert!(compliance_log_due(Some((false, 5)), true, 300));
+    }
+
+    #[test]
+    fn test_compliance_log_throttled_until_heartbeat() {
+        assert!(!compliance_log_due(Some((false, 5)), false, 300));
+        assert!(!compliance_log_due(Some((true, 299)), true, 300));
+        assert!(compliance_log_due(Some((false, 300)), false, 300));
+    }
+
+    #[test]
+    fn test_status_grace_remaining() {
+        let engine = SchedulerEngine::new();
+        let schedule = Schedule {
+            id: Some(7),
+            grace_period_secs: 60,
+            ..Schedule::default()
+        };
+        let t0 = Utc::now();
+        engine.states.lock().unwrap().insert(
+            7,
+            ScheduleState {
+                grace_started: Some(t0),
+                ..ScheduleState::default()
+            },
+        );
+
+        let status = engine.status_at(&schedule, t0 + chrono::Duration::seconds(20));
+        assert!(status.in_grace);
+        assert_eq!(status.grace_remaining_secs, Some(40));
+        assert_eq!(status.last_notification_secs_ago, None);
+
+        let status = engine.status_at(&schedule, t0 + chrono::Duration::seconds(60));
+        assert!(!status.in_grace);
+        assert_eq!(status.grace_remaining_secs, None);
+    }
+
+    #[test]
+    fn test_explain_reports_each_gate() {
+        let engine = SchedulerEngine::new();
+        engine.set_timezone(ReportTimezone::parse("UTC").unwrap());
+        let schedule = Schedule {
+            id: Some(4),
+            start_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(17, 0, 0),
+            days: vec![chrono::Weekday::Mon],
+            expected_apps: vec!["code".to_string()],
+            check_interval_secs: 60,
+            grace_period_secs: 120,
+            ..Schedule::default()
+        };
+        // Monday 10:00 UTC
+        let now = DateTime::from_timestamp(1_704_103_200, 0).unwrap();
+        let ago = |secs: i64| now - chrono::Duration::seconds(secs);
+        engine.states.lock().unwrap().insert(
+            4,
+            ScheduleState {
+                last_check: Some(ago(20)),
+                grace_started: Some(ago(200)),
+                last_notification: Some(ago(100)),
+                ..ScheduleState::default()
+            },
+        );
+
+        let explanation = engine.explain(&schedule, &app("Steam.exe", None), now);
+        assert_eq!(
+            explanation,
+            EvalExplanation {
+                schedule_id: 4,
+                enabled: true,
+                within_schedule: true,
+                should_check: false,
+                check_remaining_secs: Some(40),
+                is_compliant: false,
+                in_grace: false,
+                grace_remaining_secs: None,
+                rate_limited: true,
+                rate_limit_remaining_secs: Some(200),
+                would_notify: false,
+            }
+        );
+        // Read-only
+        assert_eq!(engine.states.lock().unwrap()[&4].last_check, Some(ago(20)));
+
+        // Once the interval and rate limit pass, a notification is due
+        let later = now + chrono::Duration::seconds(240);
+        let explanation = engine.explain(&schedule, &app("Steam.exe", None), later);
+        assert!(explanation.should_check && !explanation.rate_limited && explanation.would_notify);
+
+        // An untouched schedule starts its grace period first
+        engine.states.lock().unwrap().clear();
+        let explanation = engine.explain(&schedule, &app("Steam.exe", None), now);
+        assert!(explanation.in_grace && !explanation.would_notify);
+        assert_eq!(explanation.grace_remaining_secs, Some(120));
+        assert!(engine.explain(&schedule, &app("Code.exe", None), now).is_compliant);
+    }
+
+    #[test]
+    fn test_status_after_notification() {
+        let engine = SchedulerEngine::new();
+        let schedule = Schedule {
+            id: Some(3),
+            ..Schedule::default()
+        };
+        engine.mark_notified(3);
+
+        let status = engine.status_at(&schedule, Utc::now() + chrono::Duration::seconds(5));
+        assert_eq!(status.consecutive_non_compliant, 1);
+        assert!(status.last_notification_secs_ago.unwrap() >= 5);
+
+        let unknown = engine.status(&Schedule { id: Some(99), ..Schedule::default() });
+        assert!(!unknown.in_grace);
+        assert_eq!(unknown.consecutive_non_compliant, 0);
+    }
+
+    #[test]
+    fn test_dirty_states_tracked_on_change() {
+        let engine = SchedulerEngine::new();
+        engine.start_grace(1);
+        engine.start_grace(1);
+        engine.mark_notified(2);
+
+        let mut dirty: Vec<i64> = engine.take_dirty_states().into_iter().map(|(id, _)| id).collect();
+        dirty.sort();
+        assert_eq!(dirty, vec![1, 2]);
+        assert!(engine.take_dirty_states().is_empty());
+
+        // Resetting a schedule that was never in grace changes nothing
+        engine.reset_grace(3);
+        assert!(engine.take_dirty_states().is_empty());
+    }
+
+    #[test]
+    fn test_should_log_records_last_log() {
+        let engine = SchedulerEngine::new();
+        let schedule = Schedule {
+            id: Some(1),
+            ..Schedule::default()
+        };
+
+        assert!(engine.should_log(&schedule, false));
+        assert!(!engine.should_log(&schedule, false));
+        assert!(engine.should_log(&schedule, true));
+        assert!(!engine.should_log(&schedule, true));
+    }
+
+    #[test]
+    fn test_violations_snapshot_leaves_state_untouched() {
+        let engine = SchedulerEngine::new();
+        let mut focus = schedule_with_apps(&["code"]);
+        focus.name = "Focus".to_string();
+        let disabled = Schedule {
+            id: Some(2),
+            enabled: false,
+            ..schedule_with_apps(&["code"])
+        };
+        // Monday 2024-01-15, 10:00 local
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 1, 15)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap();
+
+        let violations = engine.violations_at(&[focus.clone(), disabled], &app("Steam.exe", None), now);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].schedule_id, 1);
+        assert_eq!(violations[0].schedule_name, "Focus");
+        assert_eq!(violations[0].current_app, "Steam.exe");
+
+        assert!(engine.violations_at(&[focus.clone()], &app("Code.exe", None), now).is_empty());
+        assert!(!engine.status(&focus).in_grace);
+        assert!(engine.take_dirty_states().is_empty());
+    }
+
+    #[test]
+    fn test_open_ended_window_runs_to_end_of_day() {
+        let engine = SchedulerEngine::new();
+        let schedule = Schedule {
+            start_time: chrono::NaiveTime::from_hms_opt(21, 0, 0).unwrap(),
+            end_time: None,
+            ..schedule_with_apps(&["kindle"])
+        };
+        let monday = chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+
+        assert!(engine.is_within_schedule_at(&schedule, monday.and_hms_opt(23, 59, 30).unwrap()));
+        assert!(engine.is_within_schedule_at(&schedule, monday.and_hms_opt(21, 0, 0).unwrap()));
+        assert!(!engine.is_within_schedule_at(&schedule, monday.and_hms_opt(20, 59, 59).unwrap()));
+        // Doesn't spill into the next morning
+        let tuesday = monday.succ_opt().unwrap();
+        assert!(!engine.is_within_schedule_at(&schedule, tuesday.and_hms_opt(0, 0, 30).unwrap()));
+    }
+
+    #[test]
+    fn test_rank_schedules() {
+        let engine = SchedulerEngine::new();
+        let at = |h: u32, m: u32| chrono::NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let schedule = |id: i64, start: chrono::NaiveTime, end: chrono::NaiveTime, apps: &[&str]| Schedule {
+            id: Some(id),
+            name: format!("schedule {}", id),
+            start_time: start,
+            end_time: Some(end),
+            ..schedule_with_apps(apps)
+        };
+        let schedules = vec![
+            schedule(1, at(18, 0), at(20, 0), &["kindle"]),
+            Schedule {
+                enabled: false,
+                ..schedule(2, at(9, 0), at(17, 0), &["steam"])
+            },
+            schedule(3, at(9, 0), at(12, 0), &["code"]),
+            schedule(4, at(7, 0), at(8, 0), &["anki"]),
+            schedule(5, at(13, 0), at(14, 0), &["slack"]),
+            schedule(6, at(10, 0), at(11, 0), &["steam"]),
+        ];
+        // Monday 10:30 with Steam in the foreground
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap().and_time(at(10, 30));
+
+        let ranked: Vec<_> = engine
+            .rank_schedules_at(&schedules, Some(&app("steam.exe", None)), now)
+            .into_iter()
+            .map(|r| (r.schedule.id.unwrap(), r.relevance))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                (3, ScheduleRelevance::Violated),
+                (6, ScheduleRelevance::Compliant),
+                (5, ScheduleRelevance::UpcomingToday),
+                (1, ScheduleRelevance::UpcomingToday),
+                (2, ScheduleRelevance::Inactive),
+                (4, ScheduleRelevance::Inactive),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_next_window_skips_to_next_scheduled_day() {
+        let engine = SchedulerEngine::new();
+        let schedule = schedule_with_apps(&["code"]);
+        // Friday evening -> Monday morning
+        let friday = chrono::NaiveDate::from_ymd_opt(2024, 1, 19).unwrap();
+        let next = engine.next_window_at(&schedule, friday.and_hms_opt(18, 0, 0).unwrap());
+        assert_eq!(next, Some(chrono::NaiveDate::from_ymd_opt(2024, 1, 22).unwrap().and_hms_opt(9, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_window_remaining_overnight() {
+        let engine = SchedulerEngine::new();
+        let schedule = Schedule {
+            start_time: chrono::NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end_time: chrono::NaiveTime::from_hms_opt(6, 0, 0),
+            days: vec![chrono::Weekday::Mon, chrono::Weekday::Tue],
+            ..schedule_with_apps(&["kindle"])
+        };
+        let monday = chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+
+        // Before midnight: runs through to 06:00 the next morning
+        let remaining = engine.window_remaining(&schedule, monday.and_hms_opt(23, 30, 0).unwrap());
+        assert_eq!(remaining, Some(chrono::Duration::minutes(390)));
+        // Past midnight: 06:00 the same morning
+        let remaining = engine.window_remaining(&schedule, tuesday.and_hms_opt(5, 22, 0).unwrap());
+        assert_eq!(remaining, Some(chrono::Duration::minutes(38)));
+        // Outside the window
+        assert_eq!(engine.window_remaining(&schedule, tuesday.and_hms_opt(12, 0, 0).unwrap()), None);
+
+        let daytime = schedule_with_apps(&["code"]);
+        let remaining = engine.window_remaining(&daytime, monday.and_hms_opt(16, 22, 0).unwrap());
+        assert_eq!(remaining, Some(chrono::Duration::minutes(38)));
+    }
+
+    #[test]
+    fn test_window_remaining_overnight_into_unscheduled_day() {
+        let engine = SchedulerEngine::new();
+        let monday = chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let schedule = Schedule {
+            start_time: chrono::NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end_time: chrono::NaiveTime::from_hms_opt(2, 0, 0),
+            days: vec![chrono::Weekday::Mon],
+            ..schedule_with_apps(&["kindle"])
+        };
+        let late = monday.and_hms_opt(23, 0, 0).unwrap();
+
+        // Tuesday isn't scheduled, so the window closes at midnight
+        assert_eq!(engine.window_remaining(&schedule, late), Some(chrono::Duration::hours(1)));
+
+        // Nor is anything past the last active day
+        let ending = Schedule {
+            days: vec![chrono::Weekday::Mon, chrono::Weekday::Tue],
+            active_until: Some(monday),
+            ..schedule
+        };
+        assert_eq!(engine.window_remaining(&ending, late), Some(chrono::Duration::hours(1)));
+    }
+
+    #[test]
+    fn test_windows_have_their_own_allowlists() {
+        let engine = SchedulerEngine::new();
+        let at = |h, m| chrono::NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let schedule = Schedule {
+            windows: vec![
+                ScheduleWindow {
+                    start_time: at(9, 0),
+                    end_time: Some(at(12, 0)),
+                    expected_apps: vec!["chrome".to_string(), "code".to_string()],
+                },
+                ScheduleWindow {
+                    start_time: at(12, 0),
+                    end_time: Some(at(17, 0)),
+                    expected_apps: vec!["code".to_string()],
+                },
+            ],
+            ..schedule_with_apps(&["slack"])
+        };
+        let monday = chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let morning = monday.and_hms_opt(10, 0, 0).unwrap();
+        let afternoon = monday.and_hms_opt(14, 0, 0).unwrap();
+        let chrome = app("chrome.exe", None);
+
+        // The first matching window wins on the boundary
+        assert_eq!(engine.active_window_at(&schedule, morning), Some(0));
+        assert_eq!(engine.active_window_at(&schedule, monday.and_hms_opt(12, 0, 0).unwrap()), Some(0));
+        assert_eq!(engine.active_window_at(&schedule, afternoon), Some(1));
+
+        assert!(engine.is_app_compliant(&schedule, Some(0), &chrome));
+        assert!(!engine.is_app_compliant(&schedule, Some(1), &chrome));
+        // Without a window the schedule's own list applies
+        assert!(!engine.is_app_compliant(&schedule, None, &chrome));
+        assert!(engine.is_compliant(&schedule, None, "Slack.exe"));
+
+        assert!(engine.violations_at(std::slice::from_ref(&schedule), &chrome, morning).is_empty());
+        assert_eq!(engine.violations_at(std::slice::from_ref(&schedule), &chrome, afternoon).len(), 1);
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let engine = SchedulerEngine::new();
+        let at = |h| chrono::NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        let schedule = |id: i64, start, end, apps: &[&str]| Schedule {
+            id: Some(id),
+            start_time: at(start),
+            end_time: Some(at(end)),
+            ..schedule_with_apps(apps)
+        };
+        let schedules = vec![
+            schedule(1, 9, 17, &["chrome", "code"]),
+            // Blocks an app schedule 1 allows, during its hours
+            schedule(2, 13, 15, &["!chrome"]),
+            // Same contradiction but after hours
+            schedule(3, 18, 20, &["!chrome"]),
+            // Overlaps and agrees on code
+            schedule(4, 10, 11, &["code"]),
+            // Overlaps with no app in common with schedule 4
+            schedule(5, 10, 12, &["kindle"]),
+            Schedule { enabled: false, ..schedule(6, 9, 17, &["!code"]) },
+        ];
+
+        assert_eq!(engine.find_conflicts(&schedules), vec![(1, 2), (1, 5), (4, 5)]);
+    }
+}
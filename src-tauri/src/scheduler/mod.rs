@@ -1,4 +1,5 @@
 pub mod engine;
 pub mod evaluator;
+pub mod neglect;
 
-pub use engine::SchedulerEngine;
+pub use engine::{SchedulerConfig, SchedulerEngine};
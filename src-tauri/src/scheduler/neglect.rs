@@ -0,0 +1,67 @@
+//! When to remind the user about an app they've been neglecting
+
+use crate::models::NeglectReminder;
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+
+/// Whether a local time falls in the reminder's window. Windows ending before
+/// they start run overnight.
+fn in_window(reminder: &NeglectReminder, time: NaiveTime) -> bool {
+    if reminder.window_start <= reminder.window_end {
+        time >= reminder.window_start && time <= reminder.window_end
+    } else {
+        time >= reminder.window_start || time <= reminder.window_end
+    }
+}
+
+/// Whether a reminder should fire: it's enabled, `local_time` is within its window,
+/// the app hasn't been used for `remind_after_secs` (or ever), and it hasn't
+/// already fired within that interval.
+pub fn reminder_due(
+    reminder: &NeglectReminder,
+    last_used: Option<DateTime<Utc>>,
+    last_reminded: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    local_time: NaiveTime,
+) -> bool {
+    if !reminder.enabled || !in_window(reminder, local_time) {
+        return false;
+    }
+    let threshold = Duration::seconds(reminder.remind_after_secs);
+    let neglected = last_used.is_none_or(|at| now - at >= threshold);
+    let reminded_recently = last_reminded.is_some_and(|at| now - at < threshold);
+    neglected && !reminded_recently
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_fires_only_after_neglect_threshold() {
+        let reminder = NeglectReminder {
+            id: Some(1),
+            app_id: "Obsidian.exe".to_string(),
+            remind_after_secs: 4 * 3_600,
+            window_start: NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+            window_end: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            enabled: true,
+        };
+        let evening = NaiveTime::from_hms_opt(19, 0, 0).unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 3, 4, 19, 0, 0).unwrap();
+
+        // Used 3h ago: not yet neglected
+        assert!(!reminder_due(&reminder, Some(now - Duration::hours(3)), None, now, evening));
+        // Used exactly 4h ago, or never: fire
+        assert!(reminder_due(&reminder, Some(now - Duration::hours(4)), None, now, evening));
+        assert!(reminder_due(&reminder, None, None, now, evening));
+        // Outside the window
+        let morning = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        assert!(!reminder_due(&reminder, None, None, now, morning));
+        // Already reminded an hour ago
+        assert!(!reminder_due(&reminder, None, Some(now - Duration::hours(1)), now, evening));
+        // Disabled
+        let disabled = NeglectReminder { enabled: false, ..reminder.clone() };
+        assert!(!reminder_due(&disabled, None, None, now, evening));
+    }
+}
@@ -0,0 +1,76 @@
+use crate::models::AppInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Where a process's `app_id` comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AppIdSource {
+    /// The executable name (default)
+    #[default]
+    Process,
+    /// The app hint at the end of the window title, e.g. "Outlook" in
+    /// "Inbox - Outlook", for PWAs and other apps hosted by a shared executable
+    Title,
+}
+
+/// App hint of a window title: its last " - " separated part, if not blank
+fn title_app_hint(title: &str) -> Option<&str> {
+    let hint = title.rsplit(" - ").next()?.trim();
+    (!hint.is_empty()).then_some(hint)
+}
+
+/// Apply the configured source to the reading's `app_id` (`process_name`).
+/// Processes are looked up case-insensitively; unlisted processes, and titles
+/// without a hint, keep the process name.
+pub fn resolve_app_id(mut app: AppInfo, sources: &HashMap<String, AppIdSource>) -> AppInfo {
+    let source = sources
+        .iter()
+        .find(|(process, _)| process.eq_ignore_ascii_case(&app.process_name))
+        .map_or(AppIdSource::Process, |(_, source)| *source);
+    if source == AppIdSource::Title {
+        if let Some(hint) = app.app_title.as_deref().and_then(title_app_hint) {
+            app.process_name = hint.to_string();
+        }
+    }
+    app
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(name: &str, title: Option<&str>) -> AppInfo {
+        AppInfo {
+            process_name: name.to_string(),
+            app_title: title.map(|t| t.to_string()),
+            bundle_id: None,
+            process_path: Some(format!("C:\\Apps\\{name}")),
+        }
+    }
+
+    #[test]
+    fn test_title_source_wins_for_configured_processes() {
+        let sources = HashMap::from([
+            ("msedge.exe".to_string(), AppIdSource::Title),
+            ("Code.exe".to_string(), AppIdSource::Process),
+        ]);
+
+        let pwa = resolve_app_id(app("MSEDGE.EXE", Some("Inbox - me@example.com - Outlook")), &sources);
+        assert_eq!(pwa.process_name, "Outlook");
+        assert_eq!(pwa.app_title.as_deref(), Some("Inbox - me@example.com - Outlook"));
+        assert_eq!(pwa.process_path.as_deref(), Some("C:\\Apps\\MSEDGE.EXE"));
+
+        let editor = resolve_app_id(app("Code.exe", Some("main.rs - timewarden - Visual Studio Code")), &sources);
+        assert_eq!(editor.process_name, "Code.exe");
+        let unlisted = resolve_app_id(app("Slack.exe", Some("general - Slack")), &sources);
+        assert_eq!(unlisted.process_name, "Slack.exe");
+    }
+
+    #[test]
+    fn test_title_without_hint_keeps_process_name() {
+        let sources = HashMap::from([("msedge.exe".to_string(), AppIdSource::Title)]);
+        assert_eq!(resolve_app_id(app("msedge.exe", None), &sources).process_name, "msedge.exe");
+        assert_eq!(resolve_app_id(app("msedge.exe", Some("  ")), &sources).process_name, "msedge.exe");
+        assert_eq!(resolve_app_id(app("msedge.exe", Some("Outlook")), &sources).process_name, "Outlook");
+    }
+}
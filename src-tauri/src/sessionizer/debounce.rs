@@ -0,0 +1,112 @@
+use crate::models::AppInfo;
+
+/// Filters out brief foreground changes (notification popups, tooltips).
+/// A newly detected app must be seen for `required_ticks` consecutive readings
+/// before it replaces the previously reported app.
+pub struct ForegroundDebouncer {
+    required_ticks: u32,
+    /// App currently reported to the sessionizer
+    current: Option<AppInfo>,
+    /// App waiting to be confirmed, and how many consecutive ticks it has been seen
+    candidate: Option<(Option<AppInfo>, u32)>,
+}
+
+/// Ticks a new app must persist when no setting overrides it
+pub const DEFAULT_REQUIRED_TICKS: u32 = 2;
+
+fn same_app(a: &Option<AppInfo>, b: &Option<AppInfo>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.process_name == b.process_name && a.process_path == b.process_path,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+impl ForegroundDebouncer {
+    pub fn new(required_ticks: u32) -> Self {
+        Self {
+            required_ticks,
+            current: None,
+            candidate: None,
+        }
+    }
+
+    /// Change how many consecutive ticks a new app needs before it is reported
+    pub fn set_required_ticks(&mut self, required_ticks: u32) {
+        self.required_ticks = required_ticks;
+        self.candidate = None;
+    }
+
+    /// Feed a raw collector reading, returning the app to report to the sessionizer
+    pub fn filter(&mut self, app: Option<AppInfo>) -> Option<AppInfo> {
+        if self.required_ticks <= 1 || same_app(&app, &self.current) {
+            self.candidate = None;
+            self.current = app;
+            return self.current.clone();
+        }
+
+        let seen = match &self.candidate {
+            Some((candidate, count)) if same_app(candidate, &app) => count + 1,
+            _ => 1,
+        };
+
+        if seen >= self.required_ticks {
+            self.candidate = None;
+            self.current = app;
+        } else {
+            self.candidate = Some((app, seen));
+        }
+        self.current.clone()
+    }
+}
+
+impl Default for ForegroundDebouncer {
+    fn default() -> Self {
+        Self::new(DEFAULT_REQUIRED_TICKS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(name: &str) -> Option<AppInfo> {
+        Some(AppInfo {
+            process_name: name.to_string(),
+            app_title: None,
+            bundle_id: None,
+            process_path: None,
+        })
+    }
+
+    fn name(app: Option<AppInfo>) -> Option<String> {
+        app.map(|a| a.process_name)
+    }
+
+    #[test]
+    fn test_single_tick_blip_is_ignored() {
+        let mut debouncer = ForegroundDebouncer::new(2);
+        debouncer.filter(app("Code.exe"));
+        debouncer.filter(app("Code.exe"));
+
+        assert_eq!(name(debouncer.filter(app("ShellExperienceHost.exe"))), Some("Code.exe".into()));
+        assert_eq!(name(debouncer.filter(app("Code.exe"))), Some("Code.exe".into()));
+    }
+
+    #[test]
+    fn test_sustained_switch_is_reported() {
+        let mut debouncer = ForegroundDebouncer::new(2);
+        debouncer.filter(app("Code.exe"));
+        debouncer.filter(app("Code.exe"));
+
+        assert_eq!(name(debouncer.filter(app("firefox.exe"))), Some("Code.exe".into()));
+        assert_eq!(name(debouncer.filter(app("firefox.exe"))), Some("firefox.exe".into()));
+    }
+
+    #[test]
+    fn test_single_required_tick_passes_through() {
+        let mut debouncer = ForegroundDebouncer::new(1);
+        debouncer.filter(app("Code.exe"));
+        assert_eq!(name(debouncer.filter(app("firefox.exe"))), Some("firefox.exe".into()));
+    }
+}
@@ -1,3 +1,8 @@
+pub mod app_id;
+pub mod debounce;
+pub mod privacy;
 pub mod state;
 
-pub use state::{Sessionizer, SessionizerConfig, SessionState};
+pub use app_id::AppIdSource;
+pub use debounce::ForegroundDebouncer;
+pub use state::{SessionKey, Sessionizer, SessionizerConfig, SessionState};
@@ -0,0 +1,49 @@
+use crate::collectors::process_matches;
+use crate::models::AppInfo;
+
+/// Drop the window title unless the process matches the title capture allowlist.
+/// `None` captures every title; an empty list captures none.
+/// Patterns match case-insensitively as substrings of the process name.
+pub fn redact_title(mut app: AppInfo, allowlist: Option<&[String]>) -> AppInfo {
+    if let Some(patterns) = allowlist {
+        if !process_matches(&app.process_name, patterns) {
+            app.app_title = None;
+        }
+    }
+    app
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(name: &str, title: &str) -> AppInfo {
+        AppInfo {
+            process_name: name.to_string(),
+            app_title: Some(title.to_string()),
+            bundle_id: None,
+            process_path: None,
+        }
+    }
+
+    #[test]
+    fn test_non_allowlisted_titles_are_dropped() {
+        let allowlist = vec!["code".to_string(), "WindowsTerminal".to_string()];
+
+        let editor = redact_title(app("Code.exe", "main.rs - timewarden"), Some(&allowlist));
+        assert_eq!(editor.app_title.as_deref(), Some("main.rs - timewarden"));
+
+        let vault = redact_title(app("KeePassXC.exe", "Banking - KeePassXC"), Some(&allowlist));
+        assert_eq!(vault.app_title, None);
+        assert_eq!(vault.process_name, "KeePassXC.exe");
+    }
+
+    #[test]
+    fn test_unset_captures_all_and_empty_captures_none() {
+        let captured = redact_title(app("KeePassXC.exe", "Banking"), None);
+        assert_eq!(captured.app_title.as_deref(), Some("Banking"));
+
+        let redacted = redact_title(app("Code.exe", "main.rs"), Some(&[]));
+        assert_eq!(redacted.app_title, None);
+    }
+}
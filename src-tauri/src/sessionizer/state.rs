@@ -1,20 +1,98 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Utc, Weekday};
+use std::collections::HashMap;
 use crate::models::{AppInfo, Session};
+use crate::timezone::ReportTimezone;
+use serde::{Deserialize, Serialize};
+
+/// What makes consecutive readings of one process the same session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionKey {
+    /// The process alone: switching windows of an app continues its session (default)
+    #[default]
+    ProcessOnly,
+    /// Process and full window title: any title change starts a new session
+    ProcessAndTitle,
+    /// Process and the document or project in the title (see `title_document`),
+    /// so moving between files of one project continues the session
+    ProcessAndDocument,
+}
+
+/// Document or project named in a window title: the part just before the
+/// trailing app name ("api" in "main.rs - api - Code"), without unsaved-change
+/// markers. A title without separators is its own document.
+fn title_document(title: &str) -> &str {
+    let mut parts = title.rsplit(" - ");
+    let last = parts.next().unwrap_or(title);
+    parts
+        .next()
+        .unwrap_or(last)
+        .trim_matches(|c: char| c == '●' || c == '*' || c.is_whitespace())
+}
 
 /// Configuration for the sessionizer
+#[derive(Debug, Clone)]
 pub struct SessionizerConfig {
-    /// Idle threshold in seconds (default: 300 = 5 minutes)
+    /// Hard idle threshold in seconds: past it the user is idle (default: 300 = 5 minutes)
     pub idle_threshold_seconds: u64,
+    /// Soft idle threshold in seconds: past it (but below the hard threshold) the
+    /// active app is held without counting as engaged time (default: 0 = disabled)
+    pub soft_idle_seconds: u64,
+    /// Margin around the hard idle threshold: going idle takes `threshold + margin`
+    /// seconds without input, and coming back needs idle to drop below
+    /// `threshold - margin`, so readings hovering at the threshold don't thrash
+    /// (default: 0 = no hysteresis)
+    pub idle_hysteresis_seconds: u64,
+    /// Per-weekday overrides of `idle_threshold_seconds` (local weekday)
+    pub idle_threshold_by_weekday: HashMap<Weekday, u64>,
+    /// Completed sessions shorter than this are dropped instead of persisted
+    /// (default: 0 = keep everything). Dropped time is discarded, so app and
+    /// idle totals shrink by the length of every filtered session.
+    pub min_persist_seconds: i64,
+    /// Treat the user as active while a call is running, even without input
+    /// (default: false)
+    pub call_keeps_active: bool,
+    /// What counts as the same session within one app (default: process only)
+    pub session_key: SessionKey,
+    /// Another app in front for less than this many seconds before the active app
+    /// returns is counted as an interruption of the session instead of splitting it
+    /// (default: 0 = disabled)
+    pub interruption_max_seconds: u64,
+    /// Idle stretches longer than this are stored as several idle sessions of at
+    /// most this length, so a weekend away isn't one huge row (default: 0 = no cap)
+    pub max_idle_session_seconds: u64,
+    /// Consecutive readings without a foreground app (while not idle) that keep
+    /// the current session alive before it ends where they began (default: 0 =
+    /// end at the first one)
+    pub missing_reading_tolerance: u32,
 }
 
 impl Default for SessionizerConfig {
     fn default() -> Self {
         Self {
             idle_threshold_seconds: 300,
+            soft_idle_seconds: 0,
+            idle_hysteresis_seconds: 0,
+            idle_threshold_by_weekday: HashMap::new(),
+            min_persist_seconds: 0,
+            call_keeps_active: false,
+            session_key: SessionKey::default(),
+            interruption_max_seconds: 0,
+            max_idle_session_seconds: 0,
+            missing_reading_tolerance: 0,
         }
     }
 }
 
+impl SessionizerConfig {
+    /// Idle threshold in effect on a local weekday
+    pub fn idle_threshold_on(&self, weekday: Weekday) -> u64 {
+        self.idle_threshold_by_weekday
+            .get(&weekday)
+            .copied()
+            .unwrap_or(self.idle_threshold_seconds)
+    }
+}
+
 /// Current state of the sessionizer
 #[derive(Debug, Clone)]
 pub enum SessionState {
@@ -24,7 +102,39 @@ pub enum SessionState {
     Active {
         app_id: String,
         app_name: Option<String>,
+        /// Full executable path, when the collector reports it
+        process_path: Option<String>,
         start_time: DateTime<Utc>,
+        /// Soft-idle seconds within the session, excluded from its duration
+        soft_idle_seconds: i64,
+        /// Brief switches to another app and back, see `interruption_max_seconds`
+        interruptions: u32,
+    },
+    /// Briefly without input (soft idle): the active app is held, but the time
+    /// only counts once input resumes before the hard threshold
+    SoftIdle {
+        app_id: String,
+        app_name: Option<String>,
+        process_path: Option<String>,
+        start_time: DateTime<Utc>,
+        soft_idle_seconds: i64,
+        interruptions: u32,
+        /// When the current soft-idle stretch began
+        since: DateTime<Utc>,
+    },
+    /// Another app took focus: the active session is held until the app returns
+    /// (an interruption) or the other app keeps focus (a real switch)
+    Interrupted {
+        app_id: String,
+        app_name: Option<String>,
+        process_path: Option<String>,
+        start_time: DateTime<Utc>,
+        soft_idle_seconds: i64,
+        interruptions: u32,
+        /// When the other app took focus
+        since: DateTime<Utc>,
+        /// The app that took focus
+        blip: AppInfo,
     },
     /// User is idle
     Idle {
@@ -32,36 +142,342 @@ pub enum SessionState {
     },
 }
 
+/// When input resumed, given an idle reading of `idle_seconds` taken at `now`,
+/// never before the idle period began
+fn input_resumed_at(idle_start: DateTime<Utc>, idle_seconds: u64, now: DateTime<Utc>) -> DateTime<Utc> {
+    let idle_for = (now - idle_start).num_seconds().max(0);
+    let back = i64::try_from(idle_seconds).unwrap_or(i64::MAX).min(idle_for);
+    now - chrono::Duration::seconds(back)
+}
+
+/// Weight of the newest sample in a session's smoothed input rate
+const INPUT_RATE_SMOOTHING: f64 = 0.2;
+
+/// Exponential moving average step: `previous` moved toward `sample` by
+/// `alpha` (0..=1), or the sample itself when there is no previous value
+pub fn smooth_input_rate(previous: Option<f64>, sample: f64, alpha: f64) -> f64 {
+    match previous {
+        Some(previous) => previous + alpha.clamp(0.0, 1.0) * (sample - previous),
+        None => sample,
+    }
+}
+
 /// The Sessionizer manages session state and handles transitions
 pub struct Sessionizer {
     config: SessionizerConfig,
+    /// Timezone used to find the local weekday for idle thresholds
+    timezone: ReportTimezone,
     state: SessionState,
     /// Completed sessions waiting to be persisted
     pending_sessions: Vec<Session>,
+    /// Start and length of the current run of readings without an app, while
+    /// it is within `missing_reading_tolerance`
+    missing_run: Option<(DateTime<Utc>, u32)>,
+    /// Smoothed input rate of the app session starting at the given time
+    input_rate: Option<(DateTime<Utc>, f64)>,
 }
 
 impl Sessionizer {
     pub fn new(config: SessionizerConfig) -> Self {
         Self {
             config,
+            timezone: ReportTimezone::default(),
             state: SessionState::Inactive,
             pending_sessions: Vec::new(),
+            missing_run: None,
+            input_rate: None,
         }
     }
 
+    /// Set the timezone whose weekday selects the idle threshold
+    pub fn set_timezone(&mut self, timezone: ReportTimezone) {
+        self.timezone = timezone;
+    }
+
+    /// Replace the per-weekday idle threshold overrides
+    pub fn set_idle_threshold_by_weekday(&mut self, thresholds: HashMap<Weekday, u64>) {
+        self.config.idle_threshold_by_weekday = thresholds;
+    }
+
+    /// Enable or disable treating calls as activity
+    pub fn set_call_keeps_active(&mut self, enabled: bool) {
+        self.config.call_keeps_active = enabled;
+    }
+
+    /// Set what counts as the same session within one app
+    pub fn set_session_key(&mut self, key: SessionKey) {
+        self.config.session_key = key;
+    }
+
     /// Process a new foreground app reading
     /// Returns true if a session was completed
-    pub fn update(&mut self, app: Option<AppInfo>, idle_seconds: u64) -> bool {
-        let now = Utc::now();
-        let is_idle = idle_seconds >= self.config.idle_threshold_seconds;
+    pub fn update(&mut self, app: Option<AppInfo>, idle_seconds: u64, in_call: bool) -> bool {
+        self.update_at(app, idle_seconds, in_call, Utc::now())
+    }
+
+    /// Fold an input rate sample into the active session's smoothed rate.
+    /// Missing samples, and samples while soft idle, interrupted, idle or
+    /// inactive, are ignored.
+    pub fn record_input_rate(&mut self, sample: Option<f64>) {
+        let SessionState::Active { start_time, .. } = self.state else {
+            return;
+        };
+        if let Some(sample) = sample {
+            let rate = smooth_input_rate(self.input_rate_of(start_time), sample, INPUT_RATE_SMOOTHING);
+            self.input_rate = Some((start_time, rate));
+        }
+    }
+
+    /// Smoothed input rate of the app session that started at `start`
+    fn input_rate_of(&self, start: DateTime<Utc>) -> Option<f64> {
+        self.input_rate.filter(|(since, _)| *since == start).map(|(_, rate)| rate)
+    }
+
+    /// Queue a completed session unless it is shorter than `min_persist_seconds`.
+    /// Returns true if it was queued.
+    fn complete(&mut self, mut session: Session) -> bool {
+        if session.duration_seconds.unwrap_or(0) < self.config.min_persist_seconds {
+            return false;
+        }
+        if !session.is_idle {
+            session.input_rate = self.input_rate_of(session.start_time);
+        }
+        self.pending_sessions.push(session);
+        true
+    }
+
+    /// Complete back-to-back idle sessions of `max_idle_session_seconds` from
+    /// `start` for as long as a full one fits before `until`. Returns whether any
+    /// completed and where the remaining idle time starts.
+    fn complete_idle_chunks(&mut self, mut start: DateTime<Utc>, until: DateTime<Utc>) -> (bool, DateTime<Utc>) {
+        let max = i64::try_from(self.config.max_idle_session_seconds).unwrap_or(i64::MAX);
+        let mut completed = false;
+        if max == 0 {
+            return (completed, start);
+        }
+        let chunk = chrono::Duration::seconds(max);
+        while until - start > chunk {
+            completed |= self.complete(Session::idle(start, Some(start + chunk)));
+            start += chunk;
+        }
+        (completed, start)
+    }
+
+    /// Whether a reading shows the same app as a session (and the same title,
+    /// when splitting on titles)
+    fn is_same_app(
+        &self,
+        app_id: &str,
+        app_name: &Option<String>,
+        process_path: &Option<String>,
+        info: &AppInfo,
+    ) -> bool {
+        app_id == info.process_name
+            && process_path == &info.process_path
+            && match self.config.session_key {
+                SessionKey::ProcessOnly => true,
+                SessionKey::ProcessAndTitle => app_name == &info.app_title,
+                SessionKey::ProcessAndDocument => {
+                    app_name.as_deref().map(title_document) == info.app_title.as_deref().map(title_document)
+                }
+            }
+    }
+
+    /// Process a foreground app reading taken at `now`
+    /// Returns true if a session was completed
+    pub fn update_at(
+        &mut self,
+        app: Option<AppInfo>,
+        idle_seconds: u64,
+        in_call: bool,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let threshold = self.config.idle_threshold_on(self.timezone.to_local(now).weekday());
+        let call_active = in_call && self.config.call_keeps_active;
+        let margin = self.config.idle_hysteresis_seconds;
+        let crossed = match self.state {
+            SessionState::Idle { .. } => idle_seconds >= threshold.saturating_sub(margin),
+            _ => idle_seconds >= threshold.saturating_add(margin),
+        };
+        let is_idle = crossed && !call_active;
+        let is_soft_idle = !is_idle
+            && !call_active
+            && self.config.soft_idle_seconds > 0
+            && idle_seconds >= self.config.soft_idle_seconds;
+
+        // A short run of readings without an app keeps the session alive; once it
+        // outlasts the tolerance the session ends where the run began
+        let mut now = now;
+        let tracking_app = matches!(
+            self.state,
+            SessionState::Active { .. } | SessionState::SoftIdle { .. } | SessionState::Interrupted { .. }
+        );
+        if app.is_none() && !is_idle && tracking_app && self.config.missing_reading_tolerance > 0 {
+            let (since, count) = self.missing_run.map_or((now, 1), |(since, count)| (since, count + 1));
+            if count <= self.config.missing_reading_tolerance {
+                self.missing_run = Some((since, count));
+                return false;
+            }
+            now = since;
+        }
+        self.missing_run = None;
+
+        let interruption_max = i64::try_from(self.config.interruption_max_seconds).unwrap_or(i64::MAX);
 
         match (&self.state, &app, is_idle) {
+            // Active session, soft idle -> hold the app
+            (SessionState::Active { app_id, app_name, process_path, start_time, soft_idle_seconds, interruptions }, _, false)
+                if is_soft_idle =>
+            {
+                self.state = SessionState::SoftIdle {
+                    app_id: app_id.clone(),
+                    app_name: app_name.clone(),
+                    process_path: process_path.clone(),
+                    start_time: *start_time,
+                    soft_idle_seconds: *soft_idle_seconds,
+                    interruptions: *interruptions,
+                    since: now,
+                };
+                false
+            }
+
+            // Soft idle, still soft idle -> keep holding
+            (SessionState::SoftIdle { .. }, _, false) if is_soft_idle => false,
+
+            // Soft idle, input resumed -> resume the held session (minus the soft idle
+            // stretch), then handle the reading as an active session would
+            (
+                SessionState::SoftIdle { app_id, app_name, process_path, start_time, soft_idle_seconds, interruptions, since },
+                _,
+                false,
+            ) => {
+                self.state = SessionState::Active {
+                    app_id: app_id.clone(),
+                    app_name: app_name.clone(),
+                    process_path: process_path.clone(),
+                    start_time: *start_time,
+                    soft_idle_seconds: soft_idle_seconds + (now - *since).num_seconds(),
+                    interruptions: *interruptions,
+                };
+                self.update_at(app, idle_seconds, in_call, now)
+            }
+
+            // Soft idle, hard idle reached -> end the session where soft idle began;
+            // the whole stretch becomes idle time
+            (
+                SessionState::SoftIdle { app_id, app_name, process_path, start_time, soft_idle_seconds, interruptions, since },
+                _,
+                true,
+            ) => {
+                let since = *since;
+                let session = Session {
+                    id: None,
+                    app_id: app_id.clone(),
+                    app_name: app_name.clone(),
+                    process_path: process_path.clone(),
+                    start_time: *start_time,
+                    end_time: Some(since),
+                    duration_seconds: Some((since - *start_time).num_seconds() - soft_idle_seconds),
+                    is_idle: false,
+                    manual: false,
+                    interruptions: *interruptions,
+                    input_rate: None,
+                };
+                let completed = self.complete(session);
+                self.state = SessionState::Idle { start_time: since };
+                completed
+            }
+
+            // Interrupted, focus back on the held app -> count the blip and carry on
+            (
+                SessionState::Interrupted {
+                    app_id,
+                    app_name,
+                    process_path,
+                    start_time,
+                    soft_idle_seconds,
+                    interruptions,
+                    since,
+                    ..
+                },
+                Some(info),
+                false,
+            ) if !is_soft_idle
+                && (now - *since).num_seconds() < interruption_max
+                && self.is_same_app(app_id, app_name, process_path, info) =>
+            {
+                self.state = SessionState::Active {
+                    app_id: app_id.clone(),
+                    app_name: app_name.clone(),
+                    process_path: process_path.clone(),
+                    start_time: *start_time,
+                    soft_idle_seconds: *soft_idle_seconds,
+                    interruptions: interruptions + 1,
+                };
+                false
+            }
+
+            // Interrupted, the other app still briefly in front -> keep holding
+            (SessionState::Interrupted { since, blip, .. }, Some(info), false)
+                if !is_soft_idle
+                    && (now - *since).num_seconds() < interruption_max
+                    && self.is_same_app(&blip.process_name, &blip.app_title, &blip.process_path, info) =>
+            {
+                false
+            }
+
+            // Interrupted, but the other app kept focus (or something else happened) ->
+            // it was a real switch: end the held session where the blip began, start the
+            // other app there, then handle the reading as that session would
+            (
+                SessionState::Interrupted {
+                    app_id,
+                    app_name,
+                    process_path,
+                    start_time,
+                    soft_idle_seconds,
+                    interruptions,
+                    since,
+                    blip,
+                },
+                _,
+                _,
+            ) => {
+                let since = *since;
+                let session = Session {
+                    id: None,
+                    app_id: app_id.clone(),
+                    app_name: app_name.clone(),
+                    process_path: process_path.clone(),
+                    start_time: *start_time,
+                    end_time: Some(since),
+                    duration_seconds: Some((since - *start_time).num_seconds() - soft_idle_seconds),
+                    is_idle: false,
+                    manual: false,
+                    interruptions: *interruptions,
+                    input_rate: None,
+                };
+                self.state = SessionState::Active {
+                    app_id: blip.process_name.clone(),
+                    app_name: blip.app_title.clone(),
+                    process_path: blip.process_path.clone(),
+                    start_time: since,
+                    soft_idle_seconds: 0,
+                    interruptions: 0,
+                };
+                let completed = self.complete(session);
+                self.update_at(app, idle_seconds, in_call, now) || completed
+            }
+
             // Currently inactive, app detected, not idle -> start new session
             (SessionState::Inactive, Some(info), false) => {
                 self.state = SessionState::Active {
                     app_id: info.process_name.clone(),
                     app_name: info.app_title.clone(),
+                    process_path: info.process_path.clone(),
                     start_time: now,
+                    soft_idle_seconds: 0,
+                    interruptions: 0,
                 };
                 false
             }
@@ -72,91 +488,126 @@ impl Sessionizer {
                 false
             }
 
-            // Active session, same app, not idle -> continue
-            (SessionState::Active { app_id, .. }, Some(info), false) if app_id == &info.process_name => {
+            // Active session, same app (and title, when splitting on titles), not idle -> continue
+            (SessionState::Active { app_id, app_name, process_path, .. }, Some(info), false)
+                if self.is_same_app(app_id, app_name, process_path, info) =>
+            {
+                false
+            }
+
+            // Active session, another app with interruption tracking on -> hold the
+            // session until it's clear whether this is a blip
+            (
+                SessionState::Active { app_id, app_name, process_path, start_time, soft_idle_seconds, interruptions },
+                Some(info),
+                false,
+            ) if interruption_max > 0 => {
+                self.state = SessionState::Interrupted {
+                    app_id: app_id.clone(),
+                    app_name: app_name.clone(),
+                    process_path: process_path.clone(),
+                    start_time: *start_time,
+                    soft_idle_seconds: *soft_idle_seconds,
+                    interruptions: *interruptions,
+                    since: now,
+                    blip: info.clone(),
+                };
                 false
             }
 
             // Active session, different app or no app, not idle -> end session, start new
-            (SessionState::Active { app_id, app_name, start_time }, new_app, false) => {
+            (
+                SessionState::Active { app_id, app_name, process_path, start_time, soft_idle_seconds, interruptions },
+                new_app,
+                false,
+            ) => {
                 // End current session
                 let session = Session {
                     id: None,
                     app_id: app_id.clone(),
                     app_name: app_name.clone(),
+                    process_path: process_path.clone(),
                     start_time: *start_time,
                     end_time: Some(now),
-                    duration_seconds: Some((now - *start_time).num_seconds()),
+                    duration_seconds: Some((now - *start_time).num_seconds() - soft_idle_seconds),
                     is_idle: false,
+                    manual: false,
+                    interruptions: *interruptions,
+                    input_rate: None,
                 };
-                self.pending_sessions.push(session);
+                let completed = self.complete(session);
 
                 // Start new session if app available
                 if let Some(info) = new_app {
                     self.state = SessionState::Active {
                         app_id: info.process_name.clone(),
                         app_name: info.app_title.clone(),
+                        process_path: info.process_path.clone(),
                         start_time: now,
+                        soft_idle_seconds: 0,
+                        interruptions: 0,
                     };
                 } else {
                     self.state = SessionState::Inactive;
                 }
-                true
+                completed
             }
 
             // Active session, now idle -> end session, start idle
-            (SessionState::Active { app_id, app_name, start_time }, _, true) => {
+            (
+                SessionState::Active { app_id, app_name, process_path, start_time, soft_idle_seconds, interruptions },
+                _,
+                true,
+            ) => {
                 let session = Session {
                     id: None,
                     app_id: app_id.clone(),
                     app_name: app_name.clone(),
+                    process_path: process_path.clone(),
                     start_time: *start_time,
                     end_time: Some(now),
-                    duration_seconds: Some((now - *start_time).num_seconds()),
+                    duration_seconds: Some((now - *start_time).num_seconds() - soft_idle_seconds),
                     is_idle: false,
+                    manual: false,
+                    interruptions: *interruptions,
+                    input_rate: None,
                 };
-                self.pending_sessions.push(session);
+                let completed = self.complete(session);
                 self.state = SessionState::Idle { start_time: now };
-                true
+                completed
             }
 
-            // Idle, still idle -> continue
-            (SessionState::Idle { .. }, _, true) => false,
+            // Idle, still idle -> continue, closing off capped chunks
+            (SessionState::Idle { start_time }, _, true) => {
+                let (completed, start_time) = self.complete_idle_chunks(*start_time, now);
+                self.state = SessionState::Idle { start_time };
+                completed
+            }
 
-            // Idle, no longer idle, app detected -> end idle, start new session
+            // Idle, no longer idle, app detected -> end idle, start new session.
+            // Input resumed `idle_seconds` before this poll, so the boundary is back-dated.
             (SessionState::Idle { start_time }, Some(info), false) => {
-                let session = Session {
-                    id: None,
-                    app_id: "Idle".to_string(),
-                    app_name: Some("Idle".to_string()),
-                    start_time: *start_time,
-                    end_time: Some(now),
-                    duration_seconds: Some((now - *start_time).num_seconds()),
-                    is_idle: true,
-                };
-                self.pending_sessions.push(session);
+                let resumed = input_resumed_at(*start_time, idle_seconds, now);
+                let (chunked, start_time) = self.complete_idle_chunks(*start_time, resumed);
+                let completed = self.complete(Session::idle(start_time, Some(resumed))) || chunked;
                 self.state = SessionState::Active {
                     app_id: info.process_name.clone(),
                     app_name: info.app_title.clone(),
-                    start_time: now,
+                    process_path: info.process_path.clone(),
+                    start_time: resumed,
+                    soft_idle_seconds: 0,
+                    interruptions: 0,
                 };
-                true
+                completed
             }
 
-            // Idle, no longer idle, no app -> end idle, become inactive
+            // Idle, no longer idle, no app -> end idle (back-dated), become inactive
             (SessionState::Idle { start_time }, None, false) => {
-                let session = Session {
-                    id: None,
-                    app_id: "Idle".to_string(),
-                    app_name: Some("Idle".to_string()),
-                    start_time: *start_time,
-                    end_time: Some(now),
-                    duration_seconds: Some((now - *start_time).num_seconds()),
-                    is_idle: true,
-                };
-                self.pending_sessions.push(session);
+                let resumed = input_resumed_at(*start_time, idle_seconds, now);
+                let (chunked, start_time) = self.complete_idle_chunks(*start_time, resumed);
+                let completed = self.complete(Session::idle(start_time, Some(resumed))) || chunked;
                 self.state = SessionState::Inactive;
-                true
+                completed
             }
 
             // No app, not idle, inactive -> stay inactive
@@ -169,8 +620,527 @@ impl Sessionizer {
         std::mem::take(&mut self.pending_sessions)
     }
 
+    /// Completed sessions not yet persisted, left in place
+    pub fn peek_pending_sessions(&self) -> Vec<Session> {
+        self.pending_sessions.clone()
+    }
+
+    /// The in-progress app session as if it ended at `now`, counting only time
+    /// already credited to it (not a soft-idle stretch or interruption still
+    /// being held). None while idle or inactive.
+    pub fn in_progress_at(&self, now: DateTime<Utc>) -> Option<Session> {
+        let (app_id, app_name, process_path, start_time, soft_idle_seconds, interruptions, end) = match &self.state {
+            SessionState::Active { app_id, app_name, process_path, start_time, soft_idle_seconds, interruptions } => {
+                (app_id, app_name, process_path, start_time, soft_idle_seconds, interruptions, now)
+            }
+            SessionState::SoftIdle { app_id, app_name, process_path, start_time, soft_idle_seconds, interruptions, since }
+            | SessionState::Interrupted { app_id, app_name, process_path, start_time, soft_idle_seconds, interruptions, since, .. } => {
+                (app_id, app_name, process_path, start_time, soft_idle_seconds, interruptions, *since)
+            }
+            SessionState::Inactive | SessionState::Idle { .. } => return None,
+        };
+        let end = end.max(*start_time);
+        Some(Session {
+            id: None,
+            app_id: app_id.clone(),
+            app_name: app_name.clone(),
+            process_path: process_path.clone(),
+            start_time: *start_time,
+            end_time: Some(end),
+            duration_seconds: Some(((end - *start_time).num_seconds() - soft_idle_seconds).max(0)),
+            is_idle: false,
+            manual: false,
+            interruptions: *interruptions,
+            input_rate: self.input_rate_of(*start_time),
+        })
+    }
+
+    /// Abandon the in-progress session and return it unfinished (no end time).
+    /// Used to save what was being tracked when the polling loop has to restart.
+    pub fn take_in_progress(&mut self) -> Option<Session> {
+        let session = match &self.state {
+            SessionState::Inactive => None,
+            SessionState::Active { app_id, app_name, process_path, start_time, interruptions, .. }
+            | SessionState::SoftIdle { app_id, app_name, process_path, start_time, interruptions, .. }
+            | SessionState::Interrupted { app_id, app_name, process_path, start_time, interruptions, .. } => {
+                Some(Session {
+                    id: None,
+                    app_id: app_id.clone(),
+                    app_name: app_name.clone(),
+                    process_path: process_path.clone(),
+                    start_time: *start_time,
+                    end_time: None,
+                    duration_seconds: None,
+                    is_idle: false,
+                    manual: false,
+                    interruptions: *interruptions,
+                    input_rate: self.input_rate_of(*start_time),
+                })
+            }
+            SessionState::Idle { start_time } => Some(Session::idle(*start_time, None)),
+        };
+        self.state = SessionState::Inactive;
+        session
+    }
+
     /// Get current state for debugging
     pub fn current_state(&self) -> &SessionState {
         &self.state
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn app(name: &str) -> Option<AppInfo> {
+        Some(AppInfo {
+            process_name: name.to_string(),
+            app_title: None,
+            bundle_id: None,
+            process_path: None,
+        })
+    }
+
+    fn titled(name: &str, title: &str) -> Option<AppInfo> {
+        Some(AppInfo {
+            app_title: Some(title.to_string()),
+            ..app(name)?
+        })
+    }
+
+    /// Feed an editor switching between projects, then another app
+    fn run_title_sequence(config: SessionizerConfig) -> Vec<Session> {
+        let mut sessionizer = Sessionizer::new(config);
+        sessionizer.update_at(titled("Code.exe", "api - Code"), 0, false, at(0));
+        sessionizer.update_at(titled("Code.exe", "api - Code"), 0, false, at(30));
+        sessionizer.update_at(titled("Code.exe", "web - Code"), 0, false, at(60));
+        sessionizer.update_at(titled("firefox.exe", "Docs"), 0, false, at(100));
+        sessionizer.take_pending_sessions()
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000, 0).unwrap() + Duration::seconds(secs)
+    }
+
+    #[test]
+    fn test_min_persist_drops_short_sessions() {
+        let mut sessionizer = Sessionizer::new(SessionizerConfig {
+            min_persist_seconds: 3,
+            ..SessionizerConfig::default()
+        });
+
+        sessionizer.update_at(app("Code.exe"), 0, false, at(0));
+        // 2s in the notification host: below the threshold, dropped
+        sessionizer.update_at(app("ShellExperienceHost.exe"), 0, false, at(10));
+        assert!(!sessionizer.update_at(app("Code.exe"), 0, false, at(12)));
+        // Exactly at the threshold: kept
+        assert!(sessionizer.update_at(app("firefox.exe"), 0, false, at(15)));
+
+        let sessions = sessionizer.take_pending_sessions();
+        let apps: Vec<_> = sessions.iter().map(|s| s.app_id.as_str()).collect();
+        assert_eq!(apps, vec!["Code.exe", "Code.exe"]);
+        assert_eq!(sessions[1].duration_seconds, Some(3));
+    }
+
+    #[test]
+    fn test_default_keeps_every_session() {
+        let mut sessionizer = Sessionizer::new(SessionizerConfig::default());
+        sessionizer.update_at(app("Code.exe"), 0, false, at(0));
+        assert!(sessionizer.update_at(app("firefox.exe"), 0, false, at(0)));
+        assert_eq!(sessionizer.take_pending_sessions().len(), 1);
+    }
+
+    #[test]
+    fn test_call_overrides_idle_when_enabled() {
+        let mut sessionizer = Sessionizer::new(SessionizerConfig {
+            call_keeps_active: true,
+            ..SessionizerConfig::default()
+        });
+
+        sessionizer.update_at(app("Code.exe"), 0, false, at(0));
+        // Reading notes during a call: no input for 10 minutes
+        assert!(!sessionizer.update_at(app("Code.exe"), 600, true, at(600)));
+        assert!(matches!(sessionizer.current_state(), SessionState::Active { .. }));
+
+        // Call ends, still no input -> idle
+        assert!(sessionizer.update_at(app("Code.exe"), 660, false, at(660)));
+        assert!(matches!(sessionizer.current_state(), SessionState::Idle { .. }));
+    }
+
+    #[test]
+    fn test_call_ignored_when_disabled() {
+        let mut sessionizer = Sessionizer::new(SessionizerConfig::default());
+        sessionizer.update_at(app("Code.exe"), 0, false, at(0));
+        assert!(sessionizer.update_at(app("Code.exe"), 600, true, at(600)));
+        assert!(matches!(sessionizer.current_state(), SessionState::Idle { .. }));
+    }
+
+    #[test]
+    fn test_title_change_splits_when_keyed_by_title() {
+        let sessions = run_title_sequence(SessionizerConfig {
+            session_key: SessionKey::ProcessAndTitle,
+            ..SessionizerConfig::default()
+        });
+
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.iter().all(|s| s.app_id == "Code.exe"));
+        assert_eq!(sessions[0].app_name.as_deref(), Some("api - Code"));
+        assert_eq!(sessions[0].duration_seconds, Some(60));
+        assert_eq!(sessions[1].app_name.as_deref(), Some("web - Code"));
+        assert_eq!(sessions[1].duration_seconds, Some(40));
+    }
+
+    #[test]
+    fn test_title_change_ignored_by_default() {
+        let sessions = run_title_sequence(SessionizerConfig::default());
+
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].app_id, "Code.exe");
+        assert_eq!(sessions[0].app_name.as_deref(), Some("api - Code"));
+        assert_eq!(sessions[0].duration_seconds, Some(100));
+    }
+
+    /// Feed an editor moving between files of one project, then to another project
+    fn run_document_sequence(session_key: SessionKey) -> Vec<(Option<String>, Option<i64>)> {
+        let mut sessionizer = Sessionizer::new(SessionizerConfig {
+            session_key,
+            ..SessionizerConfig::default()
+        });
+        sessionizer.update_at(titled("Code.exe", "main.rs - api - Visual Studio Code"), 0, false, at(0));
+        sessionizer.update_at(titled("Code.exe", "● lib.rs - api - Visual Studio Code"), 0, false, at(30));
+        sessionizer.update_at(titled("Code.exe", "index.ts - web - Visual Studio Code"), 0, false, at(60));
+        sessionizer.update_at(titled("firefox.exe", "Docs"), 0, false, at(100));
+        sessionizer
+            .take_pending_sessions()
+            .into_iter()
+            .map(|s| (s.app_name, s.duration_seconds))
+            .collect()
+    }
+
+    #[test]
+    fn test_session_key_strategies() {
+        let name = |title: &str| Some(format!("{} - Visual Studio Code", title));
+
+        assert_eq!(run_document_sequence(SessionKey::ProcessOnly), vec![(name("main.rs - api"), Some(100))]);
+        assert_eq!(
+            run_document_sequence(SessionKey::ProcessAndTitle),
+            vec![
+                (name("main.rs - api"), Some(30)),
+                (name("● lib.rs - api"), Some(30)),
+                (name("index.ts - web"), Some(40)),
+            ]
+        );
+        assert_eq!(
+            run_document_sequence(SessionKey::ProcessAndDocument),
+            vec![(name("main.rs - api"), Some(60)), (name("index.ts - web"), Some(40))]
+        );
+    }
+
+    #[test]
+    fn test_idle_threshold_varies_by_weekday() {
+        let config = SessionizerConfig {
+            idle_threshold_by_weekday: HashMap::from([(Weekday::Sat, 1_800)]),
+            ..SessionizerConfig::default()
+        };
+        assert_eq!(config.idle_threshold_on(Weekday::Mon), 300);
+        assert_eq!(config.idle_threshold_on(Weekday::Sat), 1_800);
+
+        // 10 minutes without input: idle on a Monday, still active on a Saturday
+        let monday = Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap();
+        let saturday = Utc.with_ymd_and_hms(2024, 1, 20, 10, 0, 0).unwrap();
+        for (day, expect_idle) in [(monday, true), (saturday, false)] {
+            let mut sessionizer = Sessionizer::new(config.clone());
+            sessionizer.set_timezone(ReportTimezone::parse("UTC").unwrap());
+            sessionizer.update_at(app("Code.exe"), 0, false, day);
+            sessionizer.update_at(app("Code.exe"), 600, false, day + Duration::seconds(600));
+            assert_eq!(
+                matches!(sessionizer.current_state(), SessionState::Idle { .. }),
+                expect_idle
+            );
+        }
+    }
+
+    fn soft_idle_config() -> SessionizerConfig {
+        SessionizerConfig {
+            soft_idle_seconds: 60,
+            idle_threshold_seconds: 300,
+            ..SessionizerConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_soft_idle_holds_app_without_counting_it() {
+        let mut sessionizer = Sessionizer::new(soft_idle_config());
+        sessionizer.update_at(app("AcroRd32.exe"), 0, false, at(0));
+        // Reading without input: soft idle after a minute, the app is held
+        assert!(!sessionizer.update_at(app("AcroRd32.exe"), 60, false, at(100)));
+        assert!(matches!(sessionizer.current_state(), SessionState::SoftIdle { .. }));
+        assert!(!sessionizer.update_at(app("AcroRd32.exe"), 200, false, at(240)));
+
+        // Input resumes before the hard threshold: same session, soft stretch excluded
+        assert!(!sessionizer.update_at(app("AcroRd32.exe"), 0, false, at(250)));
+        assert!(matches!(sessionizer.current_state(), SessionState::Active { .. }));
+        assert!(sessionizer.update_at(app("Code.exe"), 0, false, at(300)));
+
+        let sessions = sessionizer.take_pending_sessions();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].app_id, "AcroRd32.exe");
+        assert_eq!(sessions[0].end_time, Some(at(300)));
+        assert_eq!(sessions[0].duration_seconds, Some(300 - 150));
+    }
+
+    #[test]
+    fn test_soft_idle_becomes_idle_at_hard_threshold() {
+        let mut sessionizer = Sessionizer::new(soft_idle_config());
+        sessionizer.update_at(app("AcroRd32.exe"), 0, false, at(0));
+        sessionizer.update_at(app("AcroRd32.exe"), 60, false, at(100));
+
+        // Hard threshold: the session ends where soft idle began
+        assert!(sessionizer.update_at(app("AcroRd32.exe"), 300, false, at(340)));
+        match sessionizer.current_state() {
+            SessionState::Idle { start_time } => assert_eq!(*start_time, at(100)),
+            state => panic!("expected idle, got {:?}", state),
+        }
+        assert!(sessionizer.update_at(app("Code.exe"), 0, false, at(400)));
+
+        let sessions: Vec<_> = sessionizer
+            .take_pending_sessions()
+            .into_iter()
+            .map(|s| (s.app_id, s.is_idle, s.duration_seconds))
+            .collect();
+        assert_eq!(
+            sessions,
+            vec![
+                ("AcroRd32.exe".to_string(), false, Some(100)),
+                ("Idle".to_string(), true, Some(300)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_soft_idle_disabled_by_default() {
+        let mut sessionizer = Sessionizer::new(SessionizerConfig::default());
+        sessionizer.update_at(app("Code.exe"), 0, false, at(0));
+        sessionizer.update_at(app("Code.exe"), 200, false, at(200));
+        assert!(matches!(sessionizer.current_state(), SessionState::Active { .. }));
+    }
+
+    /// Idle readings flicking around the 300s threshold, one per 10s
+    fn run_idle_oscillation(config: SessionizerConfig) -> usize {
+        let mut sessionizer = Sessionizer::new(config);
+        sessionizer.update_at(app("AcroRd32.exe"), 0, false, at(0));
+        let mut transitions = 0;
+        let mut was_idle = false;
+        for (i, idle) in [290, 305, 295, 310, 285, 302, 298, 315, 292, 306].iter().enumerate() {
+            sessionizer.update_at(app("AcroRd32.exe"), *idle, false, at(10 * (i as i64 + 1)));
+            let is_idle = matches!(sessionizer.current_state(), SessionState::Idle { .. });
+            transitions += (is_idle != was_idle) as usize;
+            was_idle = is_idle;
+        }
+        transitions
+    }
+
+    #[test]
+    fn test_idle_hysteresis_smooths_threshold_flicker() {
+        assert_eq!(run_idle_oscillation(SessionizerConfig::default()), 9);
+        let with_hysteresis = run_idle_oscillation(SessionizerConfig {
+            idle_hysteresis_seconds: 20,
+            ..SessionizerConfig::default()
+        });
+        assert_eq!(with_hysteresis, 0);
+    }
+
+    #[test]
+    fn test_idle_hysteresis_needs_clear_return() {
+        let mut sessionizer = Sessionizer::new(SessionizerConfig {
+            idle_hysteresis_seconds: 20,
+            ..SessionizerConfig::default()
+        });
+        sessionizer.update_at(app("Code.exe"), 0, false, at(0));
+        assert!(!sessionizer.update_at(app("Code.exe"), 310, false, at(310)));
+        assert!(sessionizer.update_at(app("Code.exe"), 320, false, at(320)));
+        assert!(matches!(sessionizer.current_state(), SessionState::Idle { .. }));
+
+        // Just under the threshold isn't clearly back
+        assert!(!sessionizer.update_at(app("Code.exe"), 285, false, at(330)));
+        assert!(matches!(sessionizer.current_state(), SessionState::Idle { .. }));
+        assert!(sessionizer.update_at(app("Code.exe"), 0, false, at(340)));
+        assert!(matches!(sessionizer.current_state(), SessionState::Active { .. }));
+    }
+
+    #[test]
+    fn test_idle_end_backdated_to_input() {
+        let mut sessionizer = Sessionizer::new(SessionizerConfig::default());
+        sessionizer.update_at(app("Code.exe"), 300, false, at(0));
+        // Input resumed 4s before this poll
+        assert!(sessionizer.update_at(app("Code.exe"), 4, false, at(10)));
+        let idle = sessionizer.take_pending_sessions().remove(0);
+        assert!(idle.is_idle);
+        assert_eq!(idle.end_time, Some(at(6)));
+        assert_eq!(idle.duration_seconds, Some(6));
+
+        sessionizer.update_at(app("firefox.exe"), 0, false, at(30));
+        let active = sessionizer.take_pending_sessions().remove(0);
+        assert_eq!((active.start_time, active.duration_seconds), (at(6), Some(24)));
+
+        // A stale reading can't move the boundary before the idle period began
+        sessionizer.update_at(None, 300, false, at(40));
+        sessionizer.take_pending_sessions();
+        sessionizer.update_at(None, 50, false, at(45));
+        let idle = sessionizer.take_pending_sessions().remove(0);
+        assert_eq!((idle.start_time, idle.end_time), (at(40), Some(at(40))));
+    }
+
+    #[test]
+    fn test_peek_pending_does_not_clear() {
+        let mut sessionizer = Sessionizer::new(SessionizerConfig::default());
+        sessionizer.update_at(app("Code.exe"), 0, false, at(0));
+        sessionizer.update_at(app("firefox.exe"), 0, false, at(30));
+
+        assert_eq!(sessionizer.peek_pending_sessions().len(), 1);
+        assert_eq!(sessionizer.peek_pending_sessions()[0].app_id, "Code.exe");
+        assert_eq!(sessionizer.take_pending_sessions().len(), 1);
+        assert!(sessionizer.peek_pending_sessions().is_empty());
+    }
+
+    fn interruption_config() -> SessionizerConfig {
+        SessionizerConfig {
+            interruption_max_seconds: 10,
+            ..SessionizerConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_brief_switches_count_as_interruptions() {
+        let mut sessionizer = Sessionizer::new(interruption_config());
+        sessionizer.update_at(app("Code.exe"), 0, false, at(0));
+        // Two quick looks at chat, one of them spanning two readings
+        assert!(!sessionizer.update_at(app("Slack.exe"), 0, false, at(60)));
+        assert!(!sessionizer.update_at(app("Code.exe"), 0, false, at(63)));
+        assert!(!sessionizer.update_at(app("Slack.exe"), 0, false, at(120)));
+        assert!(!sessionizer.update_at(app("Slack.exe"), 0, false, at(125)));
+        assert!(!sessionizer.update_at(app("Code.exe"), 0, false, at(128)));
+        // Switching away holds the session until it's clear it wasn't a blip
+        assert!(!sessionizer.update_at(app("firefox.exe"), 0, false, at(200)));
+        assert!(sessionizer.update_at(app("Code.exe"), 0, false, at(300)));
+
+        let sessions = sessionizer.take_pending_sessions();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].app_id, "Code.exe");
+        assert_eq!(sessions[0].duration_seconds, Some(200));
+        assert_eq!(sessions[0].interruptions, 2);
+
+        let current = sessionizer.take_in_progress().unwrap();
+        assert_eq!(current.app_id, "firefox.exe");
+        assert_eq!(current.start_time, at(200));
+        assert_eq!(current.interruptions, 0);
+    }
+
+    #[test]
+    fn test_input_rate_smoothing() {
+        assert_eq!(smooth_input_rate(None, 120.0, 0.2), 120.0);
+        assert_eq!(smooth_input_rate(Some(100.0), 200.0, 0.2), 120.0);
+        assert_eq!(smooth_input_rate(Some(100.0), 200.0, 5.0), 200.0);
+
+        // Each session gets its own rate, from samples taken while it was active
+        let mut sessionizer = Sessionizer::new(SessionizerConfig::default());
+        sessionizer.record_input_rate(Some(500.0));
+        sessionizer.update_at(app("Code.exe"), 0, false, at(0));
+        sessionizer.record_input_rate(Some(100.0));
+        sessionizer.update_at(app("Code.exe"), 0, false, at(5));
+        sessionizer.record_input_rate(Some(200.0));
+        sessionizer.record_input_rate(None);
+        sessionizer.update_at(app("Slack.exe"), 0, false, at(10));
+        assert_eq!(sessionizer.take_pending_sessions()[0].input_rate, Some(120.0));
+        assert_eq!(sessionizer.in_progress_at(at(15)).unwrap().input_rate, None);
+    }
+
+    #[test]
+    fn test_long_switch_splits_at_its_start() {
+        let mut sessionizer = Sessionizer::new(interruption_config());
+        sessionizer.update_at(app("Code.exe"), 0, false, at(0));
+        sessionizer.update_at(app("Slack.exe"), 0, false, at(60));
+        assert!(sessionizer.update_at(app("Slack.exe"), 0, false, at(75)));
+
+        let sessions = sessionizer.take_pending_sessions();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].app_id, "Code.exe");
+        assert_eq!(sessions[0].duration_seconds, Some(60));
+        assert_eq!(sessions[0].interruptions, 0);
+
+        let current = sessionizer.take_in_progress().unwrap();
+        assert_eq!(current.app_id, "Slack.exe");
+        assert_eq!(current.start_time, at(60));
+    }
+
+    fn missing_reading_config() -> SessionizerConfig {
+        SessionizerConfig {
+            missing_reading_tolerance: 2,
+            ..SessionizerConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_missing_reading_blip_absorbed() {
+        let mut sessionizer = Sessionizer::new(missing_reading_config());
+        sessionizer.update_at(app("Code.exe"), 0, false, at(0));
+        assert!(!sessionizer.update_at(None, 0, false, at(5)));
+        assert!(!sessionizer.update_at(app("Code.exe"), 0, false, at(10)));
+        // The run starts over after an app comes back
+        assert!(!sessionizer.update_at(None, 0, false, at(15)));
+        assert!(!sessionizer.update_at(None, 0, false, at(20)));
+        assert!(!sessionizer.update_at(app("Code.exe"), 0, false, at(25)));
+
+        assert!(sessionizer.take_pending_sessions().is_empty());
+        let current = sessionizer.take_in_progress().unwrap();
+        assert_eq!(current.app_id, "Code.exe");
+        assert_eq!(current.start_time, at(0));
+    }
+
+    #[test]
+    fn test_sustained_missing_readings_end_session() {
+        let mut sessionizer = Sessionizer::new(missing_reading_config());
+        sessionizer.update_at(app("Code.exe"), 0, false, at(0));
+        assert!(!sessionizer.update_at(None, 0, false, at(5)));
+        assert!(!sessionizer.update_at(None, 0, false, at(10)));
+        assert!(sessionizer.update_at(None, 0, false, at(15)));
+
+        let sessions = sessionizer.take_pending_sessions();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].end_time, Some(at(5)));
+        assert_eq!(sessions[0].duration_seconds, Some(5));
+        assert!(matches!(sessionizer.current_state(), SessionState::Inactive));
+    }
+
+    #[test]
+    fn test_long_idle_split_into_capped_chunks() {
+        let mut sessionizer = Sessionizer::new(SessionizerConfig {
+            max_idle_session_seconds: 8 * 3600,
+            ..SessionizerConfig::default()
+        });
+        let hours = |h: i64| h * 3600;
+
+        sessionizer.update_at(app("Code.exe"), 0, false, at(0));
+        assert!(sessionizer.update_at(app("Code.exe"), 300, false, at(300)));
+        sessionizer.take_pending_sessions();
+
+        // Polled through the first night: a chunk closes once 8 hours are up
+        assert!(!sessionizer.update_at(app("Code.exe"), 3_600, false, at(hours(1))));
+        assert!(sessionizer.update_at(app("Code.exe"), 30_000, false, at(300 + hours(8) + 60)));
+        // The machine slept through the rest of the weekend, input resumes 2 days later
+        let back = 300 + hours(48) + 1_800;
+        assert!(sessionizer.update_at(app("Code.exe"), 0, false, at(back)));
+
+        let idle: Vec<_> = sessionizer
+            .take_pending_sessions()
+            .into_iter()
+            .inspect(|s| assert!(s.is_idle))
+            .map(|s| (s.start_time, s.duration_seconds))
+            .collect();
+        let mut expected: Vec<_> = (0..6).map(|i| (at(300 + i * hours(8)), Some(hours(8)))).collect();
+        expected.push((at(300 + hours(48)), Some(1_800)));
+        assert_eq!(idle, expected);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::storage::db::Database;
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+
+/// Known (previously seen) app tracking
+impl Database {
+    /// Remember that an app was seen in the foreground.
+    /// Returns true if it had never been seen before.
+    pub fn record_app_seen(&self, app_id: &str, at: DateTime<Utc>) -> Result<bool, rusqlite::Error> {
+        let inserted = self.connection().execute(
+            "INSERT OR IGNORE INTO known_apps (app_id, first_seen) VALUES (?1, ?2)",
+            params![app_id, at.timestamp()],
+        )?;
+        Ok(inserted == 1)
+    }
+
+    /// Apps first seen at or after `since`, oldest first
+    pub fn new_apps_since(&self, since: DateTime<Utc>) -> Result<Vec<(String, DateTime<Utc>)>, rusqlite::Error> {
+        let mut stmt = self.connection().prepare(
+            "SELECT app_id, first_seen FROM known_apps WHERE first_seen >= ?1 ORDER BY first_seen ASC, app_id ASC",
+        )?;
+        let apps = stmt.query_map(params![since.timestamp()], |row| {
+            let first_seen: i64 = row.get(1)?;
+            Ok((
+                row.get::<_, String>(0)?,
+                DateTime::from_timestamp(first_seen, 0).unwrap_or_else(Utc::now),
+            ))
+        })?;
+        apps.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_new_app_detected_once() {
+        let db = Database::in_memory();
+        let monday = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+        let tuesday = Utc.with_ymd_and_hms(2024, 1, 2, 9, 0, 0).unwrap();
+
+        assert!(db.record_app_seen("Code.exe", monday).unwrap());
+        assert!(!db.record_app_seen("Code.exe", tuesday).unwrap());
+        assert!(db.record_app_seen("miner.exe", tuesday).unwrap());
+        assert!(!db.record_app_seen("miner.exe", tuesday).unwrap());
+
+        let new_apps = db.new_apps_since(tuesday).unwrap();
+        assert_eq!(new_apps, vec![("miner.exe".to_string(), tuesday)]);
+        assert_eq!(db.new_apps_since(monday).unwrap().len(), 2);
+    }
+}
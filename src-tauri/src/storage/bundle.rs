@@ -0,0 +1,257 @@
+use crate::models::{Goal, NeglectReminder, ProjectRule, Schedule};
+use crate::storage::db::{Database, DbError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Format version written to exported bundles (2: goals, project rules and
+/// neglect reminders; version 1 bundles import with none)
+pub const CONFIG_BUNDLE_VERSION: u32 = 2;
+
+/// Backup of user configuration (no session data)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub version: u32,
+    pub schedules: Vec<Schedule>,
+    #[serde(default)]
+    pub settings: BTreeMap<String, String>,
+    #[serde(default)]
+    pub goals: Vec<Goal>,
+    /// In matching order
+    #[serde(default)]
+    pub project_rules: Vec<ProjectRule>,
+    #[serde(default)]
+    pub neglect_reminders: Vec<NeglectReminder>,
+}
+
+/// Reject schedules that couldn't be evaluated sensibly
+fn validate_schedule(schedule: &Schedule) -> Result<(), DbError> {
+    if schedule.name.trim().is_empty() {
+        return Err(DbError::InvalidInput("schedule name must not be empty".to_string()));
+    }
+    if schedule.days.is_empty() {
+        return Err(DbError::InvalidInput(format!("schedule {:?} has no days", schedule.name)));
+    }
+    if schedule.check_interval_secs == 0 {
+        return Err(DbError::InvalidInput(format!(
+            "schedule {:?} has a zero check interval",
+            schedule.name
+        )));
+    }
+    Ok(())
+}
+
+impl Database {
+    /// Serialize schedules, settings, goals, project rules and neglect reminders
+    /// into a versioned JSON bundle
+    pub fn export_config_bundle(&self) -> Result<String, DbError> {
+        let schedules = self
+            .get_all_schedules()?
+            .into_iter()
+            .map(|schedule| Schedule { id: None, ..schedule })
+            .collect();
+        let bundle = ConfigBundle {
+            version: CONFIG_BUNDLE_VERSION,
+            schedules,
+            settings: self.get_all_settings()?.into_iter().collect(),
+            goals: self
+                .get_all_goals()?
+                .into_iter()
+                .map(|goal| Goal { id: None, ..goal })
+                .collect(),
+            project_rules: self
+                .get_all_project_rules()?
+                .into_iter()
+                .map(|rule| ProjectRule { id: None, ..rule })
+                .collect(),
+            neglect_reminders: self
+                .get_all_neglect_reminders()?
+                .into_iter()
+                .map(|reminder| NeglectReminder { id: None, ..reminder })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&bundle).map_err(|e| DbError::InvalidInput(e.to_string()))
+    }
+
+    /// Apply a bundle produced by `export_config_bundle`. Schedules and goals are
+    /// matched by name, neglect reminders by app and settings by key; matches are
+    /// replaced, everything else is inserted. Project rules not already present are
+    /// appended after the existing ones. Nothing is written unless the whole
+    /// bundle is valid.
+    pub fn import_config_bundle(&self, json: &str) -> Result<(), DbError> {
+        let bundle: ConfigBundle =
+            serde_json::from_str(json).map_err(|e| DbError::InvalidInput(format!("invalid bundle: {}", e)))?;
+        if bundle.version > CONFIG_BUNDLE_VERSION {
+            return Err(DbError::InvalidInput(format!(
+                "bundle version {} is newer than supported version {}",
+                bundle.version, CONFIG_BUNDLE_VERSION
+            )));
+        }
+        for schedule in &bundle.schedules {
+            validate_schedule(schedule)?;
+        }
+
+        let existing: BTreeMap<String, i64> = self
+            .get_all_schedules()?
+            .into_iter()
+            .filter_map(|s| Some((s.name, s.id?)))
+            .collect();
+
+        let tx = self.connection().unchecked_transaction()?;
+        for schedule in bundle.schedules {
+            match existing.get(&schedule.name) {
+                Some(&id) => self.update_schedule(&Schedule { id: Some(id), ..schedule })?,
+                None => {
+                    self.insert_schedule(&schedule)?;
+                }
+            }
+        }
+        for (key, value) in &bundle.settings {
+            self.set_setting(key, value)?;
+        }
+
+        let existing_goals = self.get_all_goals()?;
+        for goal in bundle.goals {
+            for existing in existing_goals.iter().filter(|existing| existing.name == goal.name) {
+                self.delete_goal(existing.id.unwrap_or(0))?;
+            }
+            self.insert_goal(&goal)?;
+        }
+
+        let existing_rules = self.get_all_project_rules()?;
+        for rule in bundle.project_rules {
+            let present = existing_rules.iter().any(|existing| {
+                existing.project == rule.project
+                    && existing.process == rule.process
+                    && existing.title_pattern == rule.title_pattern
+            });
+            if !present {
+                self.insert_project_rule(&rule)?;
+            }
+        }
+
+        let existing_reminders = self.get_all_neglect_reminders()?;
+        for reminder in bundle.neglect_reminders {
+            for existing in existing_reminders.iter().filter(|existing| existing.app_id == reminder.app_id) {
+                self.delete_neglect_reminder(existing.id.unwrap_or(0))?;
+            }
+            self.insert_neglect_reminder(&reminder)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveTime, Weekday};
+
+    #[test]
+    fn test_config_bundle_round_trip() {
+        let source = Database::in_memory();
+        source
+            .insert_schedule(&Schedule {
+                name: "Deep work".to_string(),
+                expected_apps: vec!["code".to_string(), "!slack".to_string()],
+                ..Schedule::default()
+            })
+            .unwrap();
+        source
+            .insert_schedule(&Schedule {
+                name: "Weekend reading".to_string(),
+                start_time: NaiveTime::from_hms_opt(10, 0, 0).unwrap(),
+                end_time: NaiveTime::from_hms_opt(12, 30, 0),
+                days: vec![Weekday::Sat, Weekday::Sun],
+                enabled: false,
+                ..Schedule::default()
+            })
+            .unwrap();
+        source.set_setting("report_timezone", "Europe/Berlin").unwrap();
+        source
+            .insert_goal(&Goal {
+                id: None,
+                name: "Write".to_string(),
+                apps: vec!["Obsidian.exe".to_string()],
+                target_seconds: 3_600,
+                days: vec![Weekday::Mon, Weekday::Wed],
+            })
+            .unwrap();
+        source
+            .insert_project_rule(&ProjectRule {
+                id: None,
+                project: "Client A".to_string(),
+                process: "Code.exe".to_string(),
+                title_pattern: Some("client-a".to_string()),
+            })
+            .unwrap();
+        source
+            .insert_neglect_reminder(&NeglectReminder {
+                id: None,
+                app_id: "Anki.exe".to_string(),
+                remind_after_secs: 86_400,
+                window_start: NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+                window_end: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+                enabled: true,
+            })
+            .unwrap();
+        let json = source.export_config_bundle().unwrap();
+
+        let target = Database::in_memory();
+        target
+            .insert_schedule(&Schedule {
+                name: "Deep work".to_string(),
+                ..Schedule::default()
+            })
+            .unwrap();
+        target.import_config_bundle(&json).unwrap();
+        // Importing again upserts instead of duplicating
+        target.import_config_bundle(&json).unwrap();
+
+        let strip = |db: &Database| -> Vec<String> {
+            db.get_all_schedules()
+                .unwrap()
+                .into_iter()
+                .map(|s| format!("{:?}", Schedule { id: None, ..s }))
+                .collect()
+        };
+        assert_eq!(strip(&target), strip(&source));
+        assert_eq!(target.get_all_settings().unwrap(), source.get_all_settings().unwrap());
+
+        let goals = target.get_all_goals().unwrap();
+        assert_eq!(goals.len(), 1);
+        assert_eq!((goals[0].name.as_str(), goals[0].target_seconds), ("Write", 3_600));
+        let rules = target.get_all_project_rules().unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].title_pattern.as_deref(), Some("client-a"));
+        let reminders = target.get_all_neglect_reminders().unwrap();
+        assert_eq!(reminders.len(), 1);
+        assert_eq!((reminders[0].app_id.as_str(), reminders[0].remind_after_secs), ("Anki.exe", 86_400));
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_schedule() {
+        let db = Database::in_memory();
+        let bundle = ConfigBundle {
+            version: CONFIG_BUNDLE_VERSION,
+            schedules: vec![
+                Schedule {
+                    name: "Valid".to_string(),
+                    ..Schedule::default()
+                },
+                Schedule {
+                    name: "No days".to_string(),
+                    days: Vec::new(),
+                    ..Schedule::default()
+                },
+            ],
+            settings: BTreeMap::new(),
+            goals: Vec::new(),
+            project_rules: Vec::new(),
+            neglect_reminders: Vec::new(),
+        };
+
+        let result = db.import_config_bundle(&serde_json::to_string(&bundle).unwrap());
+        assert!(matches!(result, Err(DbError::InvalidInput(_))));
+        assert!(db.get_all_schedules().unwrap().is_empty());
+    }
+}
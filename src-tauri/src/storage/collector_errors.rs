@@ -0,0 +1,107 @@
+use crate::collectors::CollectorFailure;
+use crate::models::CollectorReliability;
+use crate::storage::db::Database;
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+
+/// Ticks are counted in buckets of this many seconds
+const TICK_BUCKET_SECS: i64 = 3_600;
+
+/// Tick counts and failures older than this are pruned
+const RETENTION_SECS: i64 = 30 * 24 * 3_600;
+
+/// Start of the tick bucket holding `time`
+fn bucket_start(time: DateTime<Utc>) -> i64 {
+    time.timestamp().div_euclid(TICK_BUCKET_SECS) * TICK_BUCKET_SECS
+}
+
+impl Database {
+    /// Count one polling tick and, if the collector failed during it, record why.
+    /// This writes on every tick, unless the engine's `record_collector_errors`
+    /// is off. Whenever a new hour's bucket starts, rows older than
+    /// `RETENTION_SECS` are pruned, so the tables stay bounded.
+    pub fn record_collector_tick(
+        &self,
+        at: DateTime<Utc>,
+        failure: Option<CollectorFailure>,
+    ) -> Result<(), rusqlite::Error> {
+        let ticks: i64 = self.connection().query_row(
+            "INSERT INTO collector_ticks (bucket_start, ticks) VALUES (?1, 1)
+             ON CONFLICT(bucket_start) DO UPDATE SET ticks = ticks + 1
+             RETURNING ticks",
+            params![bucket_start(at)],
+            |row| row.get(0),
+        )?;
+        if ticks == 1 {
+            let cutoff = bucket_start(at) - RETENTION_SECS;
+            self.connection().execute("DELETE FROM collector_ticks WHERE bucket_start < ?1", params![cutoff])?;
+            self.connection().execute("DELETE FROM collector_errors WHERE timestamp < ?1", params![cutoff])?;
+        }
+        if let Some(failure) = failure {
+            self.connection().execute(
+                "INSERT INTO collector_errors (timestamp, reason) VALUES (?1, ?2)",
+                params![at.timestamp(), failure.as_str()],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Share of recorded ticks between `start` and `end` where the collector
+    /// didn't fail, with failures per reason (most first). Ticks are counted per
+    /// hour, so the range is widened to whole hours.
+    pub fn collector_reliability(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<CollectorReliability, rusqlite::Error> {
+        let from = bucket_start(start);
+        let to = bucket_start(end) + TICK_BUCKET_SECS;
+
+        let ticks: i64 = self.connection().query_row(
+            "SELECT COALESCE(SUM(ticks), 0) FROM collector_ticks WHERE bucket_start >= ?1 AND bucket_start < ?2",
+            params![from, to],
+            |row| row.get(0),
+        )?;
+        let mut stmt = self.connection().prepare(
+            "SELECT reason, COUNT(*) FROM collector_errors
+             WHERE timestamp >= ?1 AND timestamp < ?2
+             GROUP BY reason
+             ORDER BY COUNT(*) DESC, reason",
+        )?;
+        let failures_by_reason = stmt
+            .query_map(params![from, to], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(String, i64)>, _>>()?;
+
+        let failures = failures_by_reason.iter().map(|(_, count)| count).sum();
+        Ok(CollectorReliability {
+            ticks,
+            failures,
+            success_rate: (ticks > 0).then(|| (ticks - failures).max(0) as f64 / ticks as f64),
+            failures_by_reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn test_old_ticks_are_pruned() {
+        let db = Database::in_memory();
+        let start = Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap();
+        db.record_collector_tick(start, Some(CollectorFailure::NoReading)).unwrap();
+        db.record_collector_tick(start + Duration::seconds(1), None).unwrap();
+        assert_eq!(db.collector_reliability(start, start).unwrap().ticks, 2);
+
+        // Still retained at the end of the window, gone once a later hour starts
+        let kept = start + Duration::seconds(RETENTION_SECS);
+        db.record_collector_tick(kept, None).unwrap();
+        assert_eq!(db.collector_reliability(start, start).unwrap().failures, 1);
+        db.record_collector_tick(kept + Duration::hours(1), None).unwrap();
+        let pruned = db.collector_reliability(start, start).unwrap();
+        assert_eq!((pruned.ticks, pruned.failures), (0, 0));
+        assert_eq!(db.collector_reliability(kept, kept + Duration::hours(1)).unwrap().ticks, 2);
+    }
+}
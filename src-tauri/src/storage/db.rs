@@ -1,7 +1,11 @@
-use rusqlite::Connection;
-use std::path::PathBuf;
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, ErrorCode};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use thiserror::Error;
 
+use crate::timezone::{DayAttribution, ReportTimezone};
+
 #[derive(Error, Debug)]
 pub enum DbError {
     #[error("SQLite error: {0}")]
@@ -10,11 +14,246 @@ pub enum DbError {
     NoAppDataDir,
     #[error("Failed to create database directory: {0}")]
     CreateDir(std::io::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("Database could not be recovered: {0}")]
+    Unrecoverable(String),
+    #[error("Another instance is already using the database (lock held on {0})")]
+    AlreadyRunning(PathBuf),
+}
+
+/// How many times opening the database is attempted before giving up
+const OPEN_ATTEMPTS: u32 = 3;
+/// Delay between open attempts
+const OPEN_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Whether an error may go away after reopening the connection
+/// (locked/busy file, corrupted page cache, transient I/O failure)
+pub fn is_recoverable(error: &rusqlite::Error) -> bool {
+    matches!(
+        error.sqlite_error_code(),
+        Some(
+            ErrorCode::DatabaseBusy
+                | ErrorCode::DatabaseLocked
+                | ErrorCode::DatabaseCorrupt
+                | ErrorCode::NotADatabase
+                | ErrorCode::CannotOpen
+                | ErrorCode::SystemIoFailure
+        )
+    )
+}
+
+/// Call `open` until it succeeds, retrying recoverable errors up to `attempts` times
+fn open_with_retry<F>(mut open: F, attempts: u32, delay: Duration) -> Result<Connection, rusqlite::Error>
+where
+    F: FnMut() -> Result<Connection, rusqlite::Error>,
+{
+    let mut attempt = 1;
+    loop {
+        match open() {
+            Ok(conn) => return Ok(conn),
+            Err(e) if attempt < attempts && is_recoverable(&e) => {
+                eprintln!("[DB] Open attempt {} failed, retrying: {}", attempt, e);
+                attempt += 1;
+                std::thread::sleep(delay);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Schema migrations applied in order on top of the base schema.
+/// `PRAGMA user_version` records how many have been applied.
+const MIGRATIONS: &[&str] = &[
+    // 1: flag manually entered sessions
+    "ALTER TABLE sessions ADD COLUMN manual BOOLEAN NOT NULL DEFAULT FALSE;",
+    // 2: key/value user settings
+    "CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+    // 3: apps seen in the foreground at least once, seeded from existing sessions
+    "CREATE TABLE known_apps (app_id TEXT PRIMARY KEY, first_seen INTEGER NOT NULL);
+     INSERT INTO known_apps (app_id, first_seen)
+         SELECT app_id, MIN(start_time) FROM sessions WHERE is_idle = FALSE GROUP BY app_id;",
+    // 4: allow open-ended schedules (NULL end_time). Existing rows keep their explicit
+    // end times; the table is rebuilt because SQLite can't drop NOT NULL in place.
+    "CREATE TABLE schedules_new (
+         id INTEGER PRIMARY KEY AUTOINCREMENT,
+         name TEXT NOT NULL,
+         start_time TEXT NOT NULL,
+         end_time TEXT,
+         days TEXT NOT NULL,
+         expected_apps TEXT NOT NULL,
+         check_interval_secs INTEGER DEFAULT 300,
+         grace_period_secs INTEGER DEFAULT 60,
+         enabled BOOLEAN DEFAULT TRUE
+     );
+     INSERT INTO schedules_new
+         SELECT id, name, start_time, end_time, days, expected_apps, check_interval_secs, grace_period_secs, enabled
+         FROM schedules;
+     DROP TABLE schedules;
+     ALTER TABLE schedules_new RENAME TO schedules;",
+    // 5: how a schedule's expected apps are matched; existing schedules keep substring matching
+    "ALTER TABLE schedules ADD COLUMN match_mode TEXT NOT NULL DEFAULT 'Substring';",
+    // 6: daily usage goals
+    "CREATE TABLE goals (
+         id INTEGER PRIMARY KEY AUTOINCREMENT,
+         name TEXT NOT NULL,
+         apps TEXT NOT NULL,
+         target_seconds INTEGER NOT NULL,
+         days TEXT NOT NULL
+     );",
+    // 7: full executable path, to tell apart apps sharing a basename
+    "ALTER TABLE sessions ADD COLUMN process_path TEXT;",
+    // 8: why an idle session was idle (e.g. no-input, locked); NULL for older sessions
+    "ALTER TABLE sessions ADD COLUMN idle_reason TEXT;",
+    // 9: schedule notifications that were sent, for per-schedule stats
+    "CREATE TABLE notification_logs (
+         id INTEGER PRIMARY KEY AUTOINCREMENT,
+         schedule_id INTEGER NOT NULL,
+         timestamp INTEGER NOT NULL,
+         current_app TEXT,
+         FOREIGN KEY (schedule_id) REFERENCES schedules(id)
+     );",
+    // 10: precomputed per-app totals by local day, plus which days are cached
+    // (a cached day may have no rows)
+    "CREATE TABLE daily_totals_cache (
+         day TEXT NOT NULL,
+         app_id TEXT NOT NULL,
+         total_seconds INTEGER NOT NULL,
+         PRIMARY KEY (day, app_id)
+     );
+     CREATE TABLE daily_totals_cache_days (day TEXT PRIMARY KEY);",
+    // 11: reminders to use neglected apps
+    "CREATE TABLE neglect_reminders (
+         id INTEGER PRIMARY KEY AUTOINCREMENT,
+         app_id TEXT NOT NULL,
+         remind_after_secs INTEGER NOT NULL,
+         window_start TEXT NOT NULL,
+         window_end TEXT NOT NULL,
+         enabled BOOLEAN NOT NULL DEFAULT TRUE
+     );",
+    // 12: optional group label for toggling schedules together
+    "ALTER TABLE schedules ADD COLUMN tag TEXT;",
+    // 13: time ranges with their own expected apps, as JSON
+    "ALTER TABLE schedules ADD COLUMN windows TEXT;",
+    // 14: rules mapping apps and titles to projects
+    "CREATE TABLE project_rules (
+         id INTEGER PRIMARY KEY AUTOINCREMENT,
+         project TEXT NOT NULL,
+         process TEXT NOT NULL,
+         title_pattern TEXT
+     );",
+    // 15: schedules that end on a date, with a one-time end notice
+    "ALTER TABLE schedules ADD COLUMN active_until TEXT;
+     ALTER TABLE schedules ADD COLUMN end_notified BOOLEAN NOT NULL DEFAULT FALSE;",
+    // 16: brief focus switches counted on the session instead of split out
+    "ALTER TABLE sessions ADD COLUMN interruptions INTEGER NOT NULL DEFAULT 0;",
+    // 17: per-schedule violation webhooks
+    "ALTER TABLE schedules ADD COLUMN webhook_url TEXT;",
+    // 18: millisecond part of session start and end times; the seconds stay in
+    // start_time/end_time so range queries are unchanged
+    "ALTER TABLE sessions ADD COLUMN start_time_ms INTEGER NOT NULL DEFAULT 0;
+     ALTER TABLE sessions ADD COLUMN end_time_ms INTEGER NOT NULL DEFAULT 0;",
+    // 19: smoothed input events per minute over each session
+    "ALTER TABLE sessions ADD COLUMN input_rate REAL;",
+    // 20: polling ticks per hour, and the ticks where the collector failed
+    "CREATE TABLE collector_ticks (
+         bucket_start INTEGER PRIMARY KEY,
+         ticks INTEGER NOT NULL
+     );
+     CREATE TABLE collector_errors (
+         id INTEGER PRIMARY KEY AUTOINCREMENT,
+         timestamp INTEGER NOT NULL,
+         reason TEXT NOT NULL
+     );
+     CREATE INDEX idx_collector_errors_timestamp ON collector_errors(timestamp);",
+];
+
+/// Schema version of a fully migrated database
+pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// File name of the database inside its data directory
+pub const DB_FILE_NAME: &str = "timewarden.db";
+
+/// File in the app data dir naming the directory the data was moved to
+pub const DATA_DIR_REDIRECT_FILE: &str = "data_dir_redirect";
+
+/// Directory named by the redirect file in `dir`, if there is one
+fn redirected_data_dir(dir: &Path) -> Option<PathBuf> {
+    let target = std::fs::read_to_string(dir.join(DATA_DIR_REDIRECT_FILE)).ok()?;
+    let target = target.trim();
+    (!target.is_empty()).then(|| PathBuf::from(target))
+}
+
+/// Make later launches open the database in `to` instead of `app_data_dir`
+pub fn redirect_data_dir(app_data_dir: &Path, to: &Path) -> Result<(), DbError> {
+    std::fs::create_dir_all(app_data_dir).map_err(DbError::CreateDir)?;
+    std::fs::write(app_data_dir.join(DATA_DIR_REDIRECT_FILE), to.to_string_lossy().as_bytes())?;
+    Ok(())
+}
+
+/// Where the database lives, and whether a fallback directory had to be used
+#[derive(Debug, Clone)]
+pub struct DbLocation {
+    pub path: PathBuf,
+    pub is_fallback: bool,
+}
+
+/// Resolve the database path, falling back to the temp dir and then the current
+/// dir when the app data dir is unavailable or can't be created. A redirect file
+/// in the app data dir (see `redirect_data_dir`) takes its place.
+pub fn resolve_db_path(app_data_dir: Option<PathBuf>) -> Result<DbLocation, DbError> {
+    let mut fallbacks = vec![std::env::temp_dir().join("timewarden")];
+    if let Ok(cwd) = std::env::current_dir() {
+        fallbacks.push(cwd);
+    }
+    resolve_db_path_in(app_data_dir, &fallbacks)
+}
+
+fn resolve_db_path_in(app_data_dir: Option<PathBuf>, fallbacks: &[PathBuf]) -> Result<DbLocation, DbError> {
+    let app_data_dir = app_data_dir.map(|dir| redirected_data_dir(&dir).unwrap_or(dir));
+    if let Some(dir) = app_data_dir {
+        if std::fs::create_dir_all(&dir).is_ok() {
+            return Ok(DbLocation {
+                path: dir.join(DB_FILE_NAME),
+                is_fallback: false,
+            });
+        }
+    }
+
+    fallbacks
+        .iter()
+        .find(|dir| std::fs::create_dir_all(dir).is_ok())
+        .map(|dir| DbLocation {
+            path: dir.join(DB_FILE_NAME),
+            is_fallback: true,
+        })
+        .ok_or(DbError::NoAppDataDir)
+}
+
+/// Open the database file at `path` and verify it with an integrity check
+fn open_verified(path: &Path) -> Result<Connection, DbError> {
+    let conn = open_with_retry(|| Connection::open(path), OPEN_ATTEMPTS, OPEN_RETRY_DELAY)
+        .map_err(|e| DbError::Unrecoverable(e.to_string()))?;
+    let integrity: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| DbError::Unrecoverable(e.to_string()))?;
+    if integrity != "ok" {
+        return Err(DbError::Unrecoverable(format!("integrity check failed: {}", integrity)));
+    }
+    Ok(conn)
 }
 
 /// Database manager for Timewarden
 pub struct Database {
     conn: Connection,
+    /// File the connection was opened from (None for in-memory databases)
+    path: Option<PathBuf>,
+    /// Timezone used for day boundaries in reports
+    report_tz: ReportTimezone,
+    /// How sessions crossing midnight are assigned to days
+    day_attribution: DayAttribution,
 }
 
 impl Database {
@@ -25,12 +264,35 @@ impl Database {
             std::fs::create_dir_all(parent).map_err(DbError::CreateDir)?;
         }
 
-        let conn = Connection::open(&db_path)?;
-        let db = Self { conn };
+        let conn = open_with_retry(|| Connection::open(&db_path), OPEN_ATTEMPTS, OPEN_RETRY_DELAY)?;
+        let db = Self {
+            conn,
+            path: Some(db_path),
+            report_tz: ReportTimezone::default(),
+            day_attribution: DayAttribution::default(),
+        };
         db.init_schema()?;
         Ok(db)
     }
 
+    /// Open a throwaway in-memory database (e.g. for replaying a tape)
+    pub fn open_in_memory() -> Result<Self, DbError> {
+        let db = Self {
+            conn: Connection::open_in_memory()?,
+            path: None,
+            report_tz: ReportTimezone::default(),
+            day_attribution: DayAttribution::default(),
+        };
+        db.init_schema()?;
+        Ok(db)
+    }
+
+    /// Open a throwaway in-memory database
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        Self::open_in_memory().expect("Failed to open in-memory database")
+    }
+
     /// Initialize database schema
     fn init_schema(&self) -> Result<(), DbError> {
         self.conn.execute_batch(
@@ -73,11 +335,165 @@ impl Database {
                 current_app TEXT,
                 FOREIGN KEY (schedule_id) REFERENCES schedules(id)
             );
+
+            -- Scheduler engine state (grace/notification timers) per schedule
+            CREATE TABLE IF NOT EXISTS schedule_state (
+                schedule_id INTEGER PRIMARY KEY,
+                grace_started INTEGER,
+                last_notification INTEGER,
+                consecutive_non_compliant INTEGER NOT NULL DEFAULT 0,
+                FOREIGN KEY (schedule_id) REFERENCES schedules(id)
+            );
             "#,
         )?;
+        self.run_migrations()
+    }
+
+    /// Apply any migrations newer than the database's schema version
+    fn run_migrations(&self) -> Result<(), DbError> {
+        let version = self.schema_version()?;
+        if version >= MIGRATIONS.len() {
+            return Ok(());
+        }
+
+        // Table rebuilds drop parent tables, which foreign keys would block.
+        // The pragma is a no-op inside a transaction, so toggle it around the batch.
+        self.conn.execute_batch("PRAGMA foreign_keys = OFF;")?;
+        let result = MIGRATIONS
+            .iter()
+            .enumerate()
+            .skip(version)
+            .try_for_each(|(index, migration)| {
+                self.conn.execute_batch(&format!(
+                    "BEGIN; {} PRAGMA user_version = {}; COMMIT;",
+                    migration,
+                    index + 1
+                ))
+            });
+        self.conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        Ok(result?)
+    }
+
+    /// Reopen the connection and verify the file with an integrity check
+    pub fn reconnect(&mut self) -> Result<(), DbError> {
+        let Some(path) = self.path.clone() else {
+            return Err(DbError::Unrecoverable("in-memory database cannot be reopened".to_string()));
+        };
+
+        self.conn = open_verified(&path)?;
+        println!("[DB] Reconnected to {:?}", path);
+        Ok(())
+    }
+
+    /// Move the database file to `new_path` and continue on it there. The data is
+    /// copied with `backup_to` and the copy integrity-checked, then `record` runs
+    /// (e.g. `redirect_data_dir`, so later launches find the new file) before the
+    /// old file is removed. A failure at any step, `record` included, removes the
+    /// copy and leaves the current database in use. Refuses to overwrite an
+    /// existing file.
+    pub fn move_data_to(
+        &mut self,
+        new_path: &Path,
+        record: impl FnOnce() -> Result<(), DbError>,
+    ) -> Result<(), DbError> {
+        let Some(old_path) = self.path.clone() else {
+            return Err(DbError::InvalidInput("in-memory database cannot be moved".to_string()));
+        };
+        if new_path.exists() {
+            return Err(DbError::InvalidInput(format!("{} already exists", new_path.display())));
+        }
+        if let Some(parent) = new_path.parent() {
+            std::fs::create_dir_all(parent).map_err(DbError::CreateDir)?;
+        }
+
+        self.backup_to(new_path)?;
+        let switched = open_verified(new_path).and_then(|conn| {
+            conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+            record()?;
+            Ok(conn)
+        });
+        let conn = match switched {
+            Ok(conn) => conn,
+            Err(e) => {
+                let _ = std::fs::remove_file(new_path);
+                return Err(e);
+            }
+        };
+        self.conn = conn;
+        self.path = Some(new_path.to_path_buf());
+
+        if let Err(e) = std::fs::remove_file(&old_path) {
+            eprintln!("[DB] Moved to {:?} but failed to remove {:?}: {}", new_path, old_path, e);
+        }
+        println!("[DB] Moved database to {:?}", new_path);
         Ok(())
     }
 
+    /// Run `op`, reconnecting and retrying once if it fails with a recoverable error
+    pub fn with_reconnect<T, F>(&mut self, mut op: F) -> Result<T, DbError>
+    where
+        F: FnMut(&Database) -> Result<T, rusqlite::Error>,
+    {
+        match op(self) {
+            Err(e) if is_recoverable(&e) => {
+                eprintln!("[DB Error] {}, attempting to reconnect", e);
+                self.reconnect()?;
+                op(self).map_err(|e| DbError::Unrecoverable(e.to_string()))
+            }
+            result => Ok(result?),
+        }
+    }
+
+    /// Number of migrations applied to this database
+    pub fn schema_version(&self) -> Result<usize, DbError> {
+        let version: i64 = self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        Ok(version as usize)
+    }
+
+    /// Copy the database to `path` with SQLite's online backup API. The copy is
+    /// written next to `path` first and renamed into place, so `path` is never
+    /// left half-written.
+    pub fn backup_to(&self, path: &Path) -> Result<(), DbError> {
+        let partial = path.with_extension("partial");
+        let result = (|| {
+            let mut dest = Connection::open(&partial)?;
+            // All pages in one step: the connection stays borrowed for the whole
+            // copy, so pausing between steps wouldn't let the polling loop write
+            Backup::new(&self.conn, &mut dest)?.run_to_completion(
+                std::os::raw::c_int::MAX,
+                Duration::ZERO,
+                None,
+            )?;
+            drop(dest);
+            std::fs::rename(&partial, path)?;
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = std::fs::remove_file(&partial);
+        }
+        result
+    }
+
+    /// Timezone used for day boundaries in reports
+    pub fn report_timezone(&self) -> ReportTimezone {
+        self.report_tz
+    }
+
+    /// Set the timezone used for day boundaries in reports
+    pub fn set_report_timezone(&mut self, tz: ReportTimezone) {
+        self.report_tz = tz;
+    }
+
+    /// How sessions crossing midnight are assigned to days
+    pub fn day_attribution(&self) -> DayAttribution {
+        self.day_attribution
+    }
+
+    /// Set how sessions crossing midnight are assigned to days
+    pub fn set_day_attribution(&mut self, mode: DayAttribution) {
+        self.day_attribution = mode;
+    }
+
     /// Get a reference to the connection for queries
     pub fn connection(&self) -> &Connection {
         &self.conn
@@ -87,7 +503,6 @@ impl Database {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
 
     #[test]
     fn test_database_creation() {
@@ -112,8 +527,184 @@ mod tests {
         assert!(tables.contains(&"sessions".to_string()));
         assert!(tables.contains(&"schedules".to_string()));
         assert!(tables.contains(&"compliance_logs".to_string()));
+        assert_eq!(db.schema_version().unwrap(), MIGRATIONS.len());
         
         // Clean up
         let _ = std::fs::remove_file(&db_path);
     }
+
+    fn busy_error() -> rusqlite::Error {
+        rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY), None)
+    }
+
+    #[test]
+    fn test_open_retries_transient_failure() {
+        let mut calls = 0;
+        let conn = open_with_retry(
+            || {
+                calls += 1;
+                if calls < 3 {
+                    Err(busy_error())
+                } else {
+                    Connection::open_in_memory()
+                }
+            },
+            3,
+            Duration::ZERO,
+        );
+        assert!(conn.is_ok());
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_open_gives_up_after_attempts() {
+        let mut calls = 0;
+        let result = open_with_retry(
+            || {
+                calls += 1;
+                Err(busy_error())
+            },
+            2,
+            Duration::ZERO,
+        );
+        assert!(result.is_err());
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_with_reconnect_retries_once() {
+        let db_path = std::env::temp_dir().join("timewarden_reconnect_test.db");
+        let _ = std::fs::remove_file(&db_path);
+        let mut db = Database::new(db_path.clone()).unwrap();
+
+        let mut calls = 0;
+        let result = db.with_reconnect(|db| {
+            calls += 1;
+            if calls == 1 {
+                Err(busy_error())
+            } else {
+                db.connection().query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get::<_, i64>(0))
+            }
+        });
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(calls, 2);
+
+        // Non-recoverable errors are returned without reconnecting
+        let result: Result<(), DbError> = db.with_reconnect(|_| Err(rusqlite::Error::InvalidQuery));
+        assert!(matches!(result, Err(DbError::Sqlite(_))));
+
+        drop(db);
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_resolve_db_path_falls_back() {
+        let temp_dir = std::env::temp_dir();
+        // A regular file can't be used as a directory, simulating an unusable location
+        let blocked = temp_dir.join("timewarden_blocked_dir");
+        std::fs::write(&blocked, b"").unwrap();
+        let fallback = temp_dir.join("timewarden_fallback_dir");
+
+        let location = resolve_db_path_in(Some(blocked.clone()), &[blocked.join("nested"), fallback.clone()]).unwrap();
+        assert!(location.is_fallback);
+        assert_eq!(location.path, fallback.join(DB_FILE_NAME));
+
+        let location = resolve_db_path_in(None, std::slice::from_ref(&fallback)).unwrap();
+        assert!(location.is_fallback);
+
+        let location = resolve_db_path_in(Some(fallback.clone()), &[]).unwrap();
+        assert!(!location.is_fallback);
+
+        assert!(matches!(
+            resolve_db_path_in(None, std::slice::from_ref(&blocked)),
+            Err(DbError::NoAppDataDir)
+        ));
+
+        let _ = std::fs::remove_file(&blocked);
+        let _ = std::fs::remove_dir_all(&fallback);
+    }
+
+    #[test]
+    fn test_backup_copies_all_rows() {
+        use crate::models::Session;
+        use chrono::{TimeZone, Utc};
+
+        let db = Database::in_memory();
+        for i in 0..5 {
+            let start = Utc.timestamp_opt(1_700_000_000 + i * 60, 0).unwrap();
+            db.insert_session(&Session {
+                id: None,
+                app_id: format!("app{}.exe", i),
+                app_name: None,
+                start_time: start,
+                end_time: Some(start + chrono::Duration::seconds(60)),
+                duration_seconds: Some(60),
+                is_idle: false,
+                manual: false,
+                process_path: None,
+                interruptions: 0,
+                input_rate: None,
+            })
+            .unwrap();
+        }
+        db.set_setting("report_timezone", "utc").unwrap();
+
+        let dest = std::env::temp_dir().join(format!("timewarden_backup_test_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&dest);
+        db.backup_to(&dest).unwrap();
+        assert!(!dest.with_extension("partial").exists());
+
+        let copy = Database::new(dest.clone()).unwrap();
+        let count = |db: &Database, table: &str| -> i64 {
+            db.connection()
+                .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+                .unwrap()
+        };
+        for table in ["sessions", "settings"] {
+            assert_eq!(count(&copy, table), count(&db, table), "{}", table);
+        }
+        assert_eq!(count(&copy, "sessions"), 5);
+        assert_eq!(copy.schema_version().unwrap(), db.schema_version().unwrap());
+
+        drop(copy);
+        let _ = std::fs::remove_file(&dest);
+    }
+
+    #[test]
+    fn test_move_data_to_keeps_data() {
+        let dir = std::env::temp_dir().join(format!("timewarden_move_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let old_path = dir.join("old").join(DB_FILE_NAME);
+        let new_path = dir.join("new").join(DB_FILE_NAME);
+
+        let mut db = Database::new(old_path.clone()).unwrap();
+        db.set_setting("report_timezone", "utc").unwrap();
+        db.move_data_to(&new_path, || redirect_data_dir(&dir, new_path.parent().unwrap()))
+            .unwrap();
+        assert!(!old_path.exists());
+        assert_eq!(resolve_db_path_in(Some(dir.clone()), &[]).unwrap().path, new_path);
+        // Writes after the move land in the new file
+        db.set_setting("day_attribution", "Split").unwrap();
+        drop(db);
+
+        let moved = Database::new(new_path.clone()).unwrap();
+        assert_eq!(moved.get_setting("report_timezone").unwrap().as_deref(), Some("utc"));
+        assert_eq!(moved.get_setting("day_attribution").unwrap().as_deref(), Some("Split"));
+
+        let mut other = Database::new(old_path.clone()).unwrap();
+        assert!(matches!(other.move_data_to(&new_path, || Ok(())), Err(DbError::InvalidInput(_))));
+        assert!(old_path.exists());
+
+        // Failing to record the new location rolls the move back
+        let elsewhere = dir.join("elsewhere").join(DB_FILE_NAME);
+        let failed = other.move_data_to(&elsewhere, || Err(DbError::InvalidInput("no redirect".to_string())));
+        assert!(matches!(failed, Err(DbError::InvalidInput(_))));
+        assert!(old_path.exists());
+        assert!(!elsewhere.exists());
+        other.set_setting("report_timezone", "local").unwrap();
+        assert_eq!(other.get_setting("report_timezone").unwrap().as_deref(), Some("local"));
+
+        drop((moved, other));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
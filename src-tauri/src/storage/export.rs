@@ -0,0 +1,602 @@
+use crate::models::{Session, IDLE_APP_ID};
+use crate::storage::db::{Database, DbError};
+use crate::storage::sessions::{session_from_row, SESSION_COLUMNS};
+use rusqlite::params;
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Write;
+
+const SESSIONS_CSV_HEADER: &str = "id,app_id,app_name,start_time,end_time,duration_seconds,is_idle";
+const APP_TOTALS_CSV_HEADER: &str = "app_id,total_seconds";
+/// Pivot column collecting every app outside the top N
+pub const PIVOT_OTHER_COLUMN: &str = "Other";
+/// Label of the timesheet row holding the day totals
+pub const TIMESHEET_TOTAL_ROW: &str = "Total";
+
+/// Timesheet row label of `project`, told apart from the totals row if it
+/// shares its name
+fn timesheet_project_label(project: &str) -> String {
+    if project.eq_ignore_ascii_case(TIMESHEET_TOTAL_ROW) {
+        format!("{} (project)", project)
+    } else {
+        project.to_string()
+    }
+}
+
+/// How durations are rounded to a billing increment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum RoundingMode {
+    /// Any part of an increment bills the whole increment (default)
+    #[default]
+    Up,
+    /// Closest increment, halves rounding up
+    Nearest,
+}
+
+/// Round exported durations to `increment_minutes` (e.g. 6 or 15).
+/// Only exports are rounded; stored sessions keep their exact durations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct DurationRounding {
+    pub increment_minutes: u32,
+    #[serde(default)]
+    pub mode: RoundingMode,
+}
+
+/// Round `secs` to the increment. A zero increment leaves it unchanged.
+pub fn round_duration(secs: i64, rounding: DurationRounding) -> i64 {
+    let increment = i64::from(rounding.increment_minutes) * 60;
+    if increment == 0 {
+        return secs;
+    }
+    let increments = match rounding.mode {
+        RoundingMode::Up => (secs + increment - 1).div_euclid(increment),
+        RoundingMode::Nearest => (secs + increment / 2).div_euclid(increment),
+    };
+    increments * increment
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escape text for an iCalendar property value
+fn ics_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Escape text for SVG element content and attribute values
+fn xml_text(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Timeline strip size: one pixel per minute of a regular day
+const TIMELINE_WIDTH: f64 = 1440.0;
+const TIMELINE_HEIGHT: u32 = 48;
+const TIMELINE_IDLE_COLOR: &str = "#d0d0d0";
+const TIMELINE_PALETTE: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+];
+
+/// Stable palette color for an app, so it looks the same on every day's strip
+fn timeline_color(app_id: &str) -> &'static str {
+    // FNV-1a: std's hasher isn't guaranteed stable across releases
+    let hash = app_id
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, b| (hash ^ u64::from(b)).wrapping_mul(0x100_0000_01b3));
+    TIMELINE_PALETTE[(hash % TIMELINE_PALETTE.len() as u64) as usize]
+}
+
+fn ics_timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Compact duration label, e.g. "1h12m", "5m", "40s"
+pub fn format_duration_short(secs: i64) -> String {
+    let hours = secs / 3600;
+    let minutes = (secs % 3600) / 60;
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", secs),
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h{}m", h, m),
+    }
+}
+
+fn format_timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Write one `SESSIONS_CSV_HEADER` row, optionally rounding the duration
+fn write_session_row<W: Write>(w: &mut W, session: &Session, rounding: Option<DurationRounding>) -> std::io::Result<()> {
+    let duration = session
+        .duration_seconds
+        .map(|d| rounding.map_or(d, |rounding| round_duration(d, rounding)));
+    writeln!(
+        w,
+        "{},{},{},{},{},{},{}",
+        session.id.unwrap_or_default(),
+        csv_field(&session.app_id),
+        csv_field(session.app_name.as_deref().unwrap_or("")),
+        format_timestamp(session.start_time),
+        session.end_time.map(format_timestamp).unwrap_or_default(),
+        duration.map(|d| d.to_string()).unwrap_or_default(),
+        session.is_idle
+    )
+}
+
+/// Export operations
+impl Database {
+    /// Stream sessions within a time range to `w` as CSV, one row at a time,
+    /// optionally rounding each session's duration.
+    /// Returns the number of rows written (excluding the header).
+    pub fn export_sessions_csv_to_writer<W: Write>(
+        &self,
+        w: &mut W,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        rounding: Option<DurationRounding>,
+    ) -> Result<usize, DbError> {
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT {}
+             FROM sessions
+             WHERE start_time >= ?1 AND start_time <= ?2
+             ORDER BY start_time ASC, start_time_ms ASC",
+            SESSION_COLUMNS
+        ))?;
+
+        writeln!(w, "{}", SESSIONS_CSV_HEADER)?;
+
+        let mut rows = stmt.query(params![start.timestamp(), end.timestamp()])?;
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            write_session_row(w, &session_from_row(row)?, rounding)?;
+            count += 1;
+        }
+
+        w.flush()?;
+        Ok(count)
+    }
+
+    /// Sessions within a time range as CSV, keeping only those assigned to
+    /// `project` by the project rules and/or whose app contains `app_pattern`
+    /// (case-insensitive). Missing or empty filters keep everything.
+    pub fn export_sessions_csv_filtered(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        project: Option<String>,
+        app_pattern: Option<String>,
+    ) -> Result<String, DbError> {
+        let project = project.filter(|p| !p.is_empty());
+        let app_pattern = app_pattern.filter(|p| !p.is_empty()).map(|p| p.to_lowercase());
+        let matcher = self.project_matcher()?;
+
+        let mut csv = Vec::new();
+        writeln!(csv, "{}", SESSIONS_CSV_HEADER)?;
+        for session in self.get_sessions_in_range(start, end)? {
+            let in_project = project.as_deref().is_none_or(|p| matcher.project_of(&session) == p);
+            let app_matches = app_pattern
+                .as_deref()
+                .is_none_or(|pattern| session.app_id.to_lowercase().contains(pattern));
+            if in_project && app_matches {
+                write_session_row(&mut csv, &session, None)?;
+            }
+        }
+        Ok(String::from_utf8_lossy(&csv).into_owned())
+    }
+
+    /// Write each app's active total within a time range to `w` as CSV, most
+    /// used first, optionally rounding each total. Returns the number of rows.
+    pub fn export_app_totals_csv_to_writer<W: Write>(
+        &self,
+        w: &mut W,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        rounding: Option<DurationRounding>,
+    ) -> Result<usize, DbError> {
+        let totals = self.get_app_totals(start, end, false)?;
+
+        writeln!(w, "{}", APP_TOTALS_CSV_HEADER)?;
+        for (app_id, total) in &totals {
+            let total = rounding.map_or(*total, |rounding| round_duration(*total, rounding));
+            writeln!(w, "{},{}", csv_field(app_id), total)?;
+        }
+
+        w.flush()?;
+        Ok(totals.len())
+    }
+
+    /// Per-app daily totals as a CSV matrix: one row per local day from `start` to
+    /// `end`, one column per app among the `top_n_apps` most used over the range,
+    /// and an `Other` column summing the rest (only if there is a rest)
+    pub fn export_pivot_csv(&self, start: NaiveDate, end: NaiveDate, top_n_apps: usize) -> Result<String, DbError> {
+        let days = self.get_daily_totals(start, end)?;
+
+        let mut overall: HashMap<&str, i64> = HashMap::new();
+        for (_, totals) in &days {
+            for (app_id, total) in totals {
+                *overall.entry(app_id.as_str()).or_default() += total;
+            }
+        }
+        let mut apps: Vec<_> = overall.into_iter().collect();
+        apps.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let has_other = apps.len() > top_n_apps;
+        let columns: Vec<&str> = apps.into_iter().take(top_n_apps).map(|(app_id, _)| app_id).collect();
+
+        let mut csv = String::from("day");
+        for app_id in &columns {
+            csv.push(',');
+            csv.push_str(&csv_field(app_id));
+        }
+        if has_other {
+            csv.push(',');
+            csv.push_str(PIVOT_OTHER_COLUMN);
+        }
+        csv.push('\n');
+
+        for (day, totals) in &days {
+            let mut cells = vec![0; columns.len() + has_other as usize];
+            for (app_id, total) in totals {
+                let column = columns.iter().position(|column| column == app_id).unwrap_or(columns.len());
+                if let Some(cell) = cells.get_mut(column) {
+                    *cell += total;
+                }
+            }
+            csv.push_str(&day.to_string());
+            for cell in cells {
+                csv.push_str(&format!(",{}", cell));
+            }
+            csv.push('\n');
+        }
+        Ok(csv)
+    }
+
+    /// `weekly_timesheet` as CSV: one row per project with a column per day and a
+    /// `total` column, then a `Total` row of day totals. A project itself named
+    /// `Total` is labelled `Total (project)`.
+    pub fn export_timesheet_csv(
+        &self,
+        week_start: NaiveDate,
+        rounding: Option<DurationRounding>,
+    ) -> Result<String, DbError> {
+        let grid = self.weekly_timesheet(week_start, rounding)?;
+
+        let mut csv = String::from("project");
+        for day in &grid.days {
+            csv.push_str(&format!(",{}", day));
+        }
+        csv.push_str(",total\n");
+
+        let totals_row = (TIMESHEET_TOTAL_ROW.to_string(), &grid.day_totals, grid.total_seconds);
+        let rows = grid
+            .rows
+            .iter()
+            .map(|row| (timesheet_project_label(&row.project), &row.seconds, row.total_seconds));
+        for (label, cells, total) in rows.chain(std::iter::once(totals_row)) {
+            csv.push_str(&csv_field(&label));
+            for cell in cells {
+                csv.push_str(&format!(",{}", cell));
+            }
+            csv.push_str(&format!(",{}\n", total));
+        }
+        Ok(csv)
+    }
+}
+
+impl Database {
+    /// Export a local day's sessions as an iCalendar document, one VEVENT per session.
+    /// Times are written in UTC so calendar apps show them in the viewer's local time.
+    /// Idle sessions are included as transparent (free) events when `include_idle` is set.
+    pub fn export_day_ics(&self, day: NaiveDate, include_idle: bool) -> Result<String, rusqlite::Error> {
+        let stamp = ics_timestamp(Utc::now());
+
+        let mut ics = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Timewarden//Activity Export//EN\r\n");
+        for session in self.get_day_sessions(day)? {
+            let Some(session_end) = session.end_time else { continue };
+            if session.is_idle && !include_idle {
+                continue;
+            }
+
+            let duration = session
+                .duration_seconds
+                .unwrap_or_else(|| (session_end - session.start_time).num_seconds());
+            let label = if session.is_idle { IDLE_APP_ID } else { session.app_id.as_str() };
+
+            ics.push_str("BEGIN:VEVENT\r\n");
+            ics.push_str(&format!("UID:session-{}@timewarden\r\n", session.id.unwrap_or(0)));
+            ics.push_str(&format!("DTSTAMP:{}\r\n", stamp));
+            ics.push_str(&format!("DTSTART:{}\r\n", ics_timestamp(session.start_time)));
+            ics.push_str(&format!("DTEND:{}\r\n", ics_timestamp(session_end)));
+            ics.push_str(&format!(
+                "SUMMARY:{}\r\n",
+                ics_text(&format!("{} ({})", label, format_duration_short(duration)))
+            ));
+            if let Some(ref title) = session.app_name {
+                if !session.is_idle {
+                    ics.push_str(&format!("DESCRIPTION:{}\r\n", ics_text(title)));
+                }
+            }
+            if session.is_idle {
+                ics.push_str("TRANSP:TRANSPARENT\r\n");
+            }
+            ics.push_str("END:VEVENT\r\n");
+        }
+        ics.push_str("END:VCALENDAR\r\n");
+
+        Ok(ics)
+    }
+}
+
+impl Database {
+    /// Render a local day's sessions as an SVG strip: one rectangle per session,
+    /// positioned by local time with width proportional to duration. Active
+    /// sessions are colored per app, idle ones gray. Open sessions are skipped.
+    pub fn render_day_timeline_svg(&self, day: NaiveDate) -> Result<String, rusqlite::Error> {
+        let (day_start, day_end) = self.report_timezone().day_bounds(day);
+        let day_secs = ((day_end - day_start).num_seconds() + 1) as f64;
+        let x = |time: DateTime<Utc>| {
+            let secs = (time - day_start).num_seconds().clamp(0, day_secs as i64) as f64;
+            secs / day_secs * TIMELINE_WIDTH
+        };
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+            w = TIMELINE_WIDTH,
+            h = TIMELINE_HEIGHT
+        );
+        for session in self.get_day_sessions(day)? {
+            let Some(session_end) = session.end_time else { continue };
+            let (start, end) = (x(session.start_time), x(session_end));
+            let (color, label) = if session.is_idle {
+                (TIMELINE_IDLE_COLOR, IDLE_APP_ID)
+            } else {
+                (timeline_color(&session.app_id), session.app_id.as_str())
+            };
+            let duration = (session_end - session.start_time).num_seconds();
+            svg.push_str(&format!(
+                "<rect x=\"{:.2}\" y=\"0\" width=\"{:.2}\" height=\"{}\" fill=\"{}\"><title>{}</title></rect>\n",
+                start,
+                end - start,
+                TIMELINE_HEIGHT,
+                color,
+                xml_text(&format!("{} ({})", label, format_duration_short(duration)))
+            ));
+        }
+        svg.push_str("</svg>\n");
+
+        Ok(svg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn session(app_id: &str, app_name: Option<&str>, start: i64, duration: i64, is_idle: bool) -> Session {
+        Session {
+            id: None,
+            app_id: app_id.to_string(),
+            app_name: app_name.map(|s| s.to_string()),
+            start_time: Utc.timestamp_opt(start, 0).unwrap(),
+            end_time: Some(Utc.timestamp_opt(start + duration, 0).unwrap()),
+            duration_seconds: Some(duration),
+            is_idle,
+            manual: false,
+            process_path: None,
+            interruptions: 0,
+            input_rate: None,
+        }
+    }
+
+    #[test]
+    fn test_export_sessions_csv_to_file() {
+        let db = Database::in_memory();
+        // 2024-01-01T09:00:00Z
+        let base = 1_704_099_600;
+        db.insert_session(&session("Code.exe", Some("main.rs, project"), base, 600, false)).unwrap();
+        db.insert_session(&session("Idle", Some("Idle"), base + 600, 300, true)).unwrap();
+        db.insert_session(&session("firefox.exe", Some("Say \"hi\""), base + 900, 120, false)).unwrap();
+        // Outside the range
+        db.insert_session(&session("Code.exe", None, base + 86_400, 60, false)).unwrap();
+
+        let path = std::env::temp_dir().join("timewarden_export_test.csv");
+        let mut file = std::fs::File::create(&path).unwrap();
+        let rows = db
+            .export_sessions_csv_to_writer(
+                &mut file,
+                Utc.timestamp_opt(base, 0).unwrap(),
+                Utc.timestamp_opt(base + 3_600, 0).unwrap(),
+                None,
+            )
+            .unwrap();
+        drop(file);
+
+        let expected = "\
+id,app_id,app_name,start_time,end_time,duration_seconds,is_idle
+1,Code.exe,\"main.rs, project\",2024-01-01T09:00:00Z,2024-01-01T09:10:00Z,600,false
+2,Idle,Idle,2024-01-01T09:10:00Z,2024-01-01T09:15:00Z,300,true
+3,firefox.exe,\"Say \"\"hi\"\"\",2024-01-01T09:15:00Z,2024-01-01T09:17:00Z,120,false
+";
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(rows, 3);
+        assert_eq!(contents, expected);
+    }
+
+    #[test]
+    fn test_round_duration() {
+        let six_up = DurationRounding { increment_minutes: 6, mode: RoundingMode::Up };
+        assert_eq!(round_duration(7 * 60, six_up), 12 * 60);
+        assert_eq!(round_duration(6 * 60, six_up), 6 * 60);
+        assert_eq!(round_duration(0, six_up), 0);
+
+        let quarter = DurationRounding { increment_minutes: 15, mode: RoundingMode::Nearest };
+        assert_eq!(round_duration(7 * 60, quarter), 0);
+        assert_eq!(round_duration(7 * 60 + 30, quarter), 15 * 60);
+        assert_eq!(round_duration(50 * 60, quarter), 45 * 60);
+
+        let off = DurationRounding { increment_minutes: 0, mode: RoundingMode::Up };
+        assert_eq!(round_duration(421, off), 421);
+    }
+
+    #[test]
+    fn test_export_app_totals_csv_rounded() {
+        let db = Database::in_memory();
+        // 2024-01-01T09:00:00Z
+        let base = 1_704_099_600;
+        db.insert_session(&session("Code.exe", None, base, 7 * 60, false)).unwrap();
+        db.insert_session(&session("Slack.exe", None, base + 600, 60, false)).unwrap();
+
+        let mut out = Vec::new();
+        let rounding = DurationRounding { increment_minutes: 6, mode: RoundingMode::Up };
+        let rows = db
+            .export_app_totals_csv_to_writer(
+                &mut out,
+                Utc.timestamp_opt(base, 0).unwrap(),
+                Utc.timestamp_opt(base + 3_600, 0).unwrap(),
+                Some(rounding),
+            )
+            .unwrap();
+
+        assert_eq!(rows, 2);
+        assert_eq!(String::from_utf8(out).unwrap(), "app_id,total_seconds\nCode.exe,720\nSlack.exe,360\n");
+        // Stored durations are untouched
+        let totals = db
+            .get_app_totals(Utc.timestamp_opt(base, 0).unwrap(), Utc.timestamp_opt(base + 3_600, 0).unwrap(), false)
+            .unwrap();
+        assert_eq!(totals[0], ("Code.exe".to_string(), 420));
+    }
+
+    #[test]
+    fn test_export_sessions_csv_filtered() {
+        let db = Database::in_memory();
+        db.insert_project_rule(&crate::models::ProjectRule {
+            id: None,
+            project: "Client A".to_string(),
+            process: "Code.exe".to_string(),
+            title_pattern: Some("acme".to_string()),
+        })
+        .unwrap();
+        // 2024-01-01T09:00:00Z
+        let base = 1_704_099_600;
+        db.insert_session(&session("Code.exe", Some("acme - Code"), base, 600, false)).unwrap();
+        db.insert_session(&session("Code.exe", Some("notes - Code"), base + 600, 300, false)).unwrap();
+        db.insert_session(&session("firefox.exe", Some("acme docs"), base + 900, 120, false)).unwrap();
+        let (start, end) = (Utc.timestamp_opt(base, 0).unwrap(), Utc.timestamp_opt(base + 3_600, 0).unwrap());
+
+        let apps = |csv: String| -> Vec<String> {
+            csv.lines()
+                .skip(1)
+                .map(|line| line.split(',').nth(2).unwrap().to_string())
+                .collect()
+        };
+        let csv = db
+            .export_sessions_csv_filtered(start, end, Some("Client A".to_string()), None)
+            .unwrap();
+        assert!(csv.starts_with(SESSIONS_CSV_HEADER));
+        assert_eq!(apps(csv), vec!["acme - Code"]);
+
+        let csv = db
+            .export_sessions_csv_filtered(start, end, None, Some("FIREFOX".to_string()))
+            .unwrap();
+        assert_eq!(apps(csv), vec!["acme docs"]);
+
+        let csv = db
+            .export_sessions_csv_filtered(start, end, Some(String::new()), Some(String::new()))
+            .unwrap();
+        let mut unfiltered = Vec::new();
+        db.export_sessions_csv_to_writer(&mut unfiltered, start, end, None).unwrap();
+        assert_eq!(csv, String::from_utf8(unfiltered).unwrap());
+    }
+
+    #[test]
+    fn test_export_pivot_csv() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(crate::timezone::ReportTimezone::parse("UTC").unwrap());
+        // 2024-01-01T09:00:00Z
+        let base = 1_704_099_600;
+        db.insert_session(&session("Code.exe", None, base, 3_000, false)).unwrap();
+        db.insert_session(&session("Slack.exe", None, base + 3_000, 600, false)).unwrap();
+        db.insert_session(&session("Idle", Some("Idle"), base + 3_600, 900, true)).unwrap();
+        db.insert_session(&session("Code.exe", None, base + 86_400, 1_200, false)).unwrap();
+        db.insert_session(&session("Spotify.exe", None, base + 86_400 + 1_200, 300, false)).unwrap();
+        db.insert_session(&session("Slack.exe", None, base + 86_400 + 1_500, 60, false)).unwrap();
+
+        let day = |d: u32| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        let csv = db.export_pivot_csv(day(1), day(3), 2).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], "day,Code.exe,Slack.exe,Other");
+        assert_eq!(lines[1], "2024-01-01,3000,600,0");
+        assert_eq!(lines[2], "2024-01-02,1200,60,300");
+        assert_eq!(lines[3], "2024-01-03,0,0,0");
+
+        let csv = db.export_pivot_csv(day(1), day(1), 5).unwrap();
+        assert_eq!(csv, "day,Code.exe,Slack.exe\n2024-01-01,3000,600\n");
+    }
+
+    #[test]
+    fn test_export_day_ics() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(crate::timezone::ReportTimezone::parse("UTC").unwrap());
+        // 2024-01-01T09:00:00Z
+        let base = 1_704_099_600;
+        db.insert_session(&session("Code.exe", Some("main.rs"), base, 4_320, false)).unwrap();
+        db.insert_session(&session("Idle", Some("Idle"), base + 4_320, 600, true)).unwrap();
+        db.insert_session(&session("firefox.exe", None, base + 4_920, 300, false)).unwrap();
+        // Next day
+        db.insert_session(&session("Code.exe", None, base + 86_400, 60, false)).unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let ics = db.export_day_ics(day, false).unwrap();
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("SUMMARY:Code.exe (1h12m)\r\n"));
+        assert!(ics.contains("DTSTART:20240101T090000Z\r\n"));
+        assert!(ics.contains("DTEND:20240101T101200Z\r\n"));
+        assert!(!ics.contains("TRANSP:TRANSPARENT"));
+
+        let ics = db.export_day_ics(day, true).unwrap();
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 3);
+        assert_eq!(ics.matches("TRANSP:TRANSPARENT").count(), 1);
+    }
+
+    #[test]
+    fn test_render_day_timeline_svg() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(crate::timezone::ReportTimezone::parse("UTC").unwrap());
+        // 2024-01-01T06:00:00Z, a quarter into the day
+        let base = 1_704_088_800;
+        db.insert_session(&session("Code.exe", None, base, 3_600, false)).unwrap();
+        db.insert_session(&session("Idle", Some("Idle"), base + 3_600, 600, true)).unwrap();
+        db.insert_session(&session("<Game>", None, base + 4_200, 300, false)).unwrap();
+
+        let svg = db.render_day_timeline_svg(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()).unwrap();
+        assert_eq!(svg.matches("<rect ").count(), 3);
+        assert!(svg.contains("<rect x=\"360.00\" y=\"0\" width=\"60.00\""));
+        assert!(svg.contains(&format!("fill=\"{}\"><title>Idle (10m)</title>", TIMELINE_IDLE_COLOR)));
+        assert!(svg.contains("<title>&lt;Game&gt; (5m)</title>"));
+    }
+
+    #[test]
+    fn test_format_duration_short() {
+        assert_eq!(format_duration_short(4_320), "1h12m");
+        assert_eq!(format_duration_short(3_600), "1h");
+        assert_eq!(format_duration_short(300), "5m");
+        assert_eq!(format_duration_short(40), "40s");
+    }
+}
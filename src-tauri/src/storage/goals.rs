@@ -0,0 +1,163 @@
+use crate::models::Goal;
+use crate::storage::db::Database;
+use crate::storage::schedules::{days_to_string, parse_days};
+use chrono::{Datelike, NaiveDate};
+use rusqlite::{params, OptionalExtension};
+
+impl Database {
+    /// Insert a new goal
+    pub fn insert_goal(&self, goal: &Goal) -> Result<i64, rusqlite::Error> {
+        self.connection().execute(
+            "INSERT INTO goals (name, apps, target_seconds, days) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                goal.name,
+                goal.apps.join(","),
+                goal.target_seconds,
+                days_to_string(&goal.days)
+            ],
+        )?;
+
+        Ok(self.connection().last_insert_rowid())
+    }
+
+    /// Delete a goal by ID
+    pub fn delete_goal(&self, id: i64) -> Result<(), rusqlite::Error> {
+        self.connection()
+            .execute("DELETE FROM goals WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Get all goals
+    pub fn get_all_goals(&self) -> Result<Vec<Goal>, rusqlite::Error> {
+        let mut stmt = self
+            .connection()
+            .prepare("SELECT id, name, apps, target_seconds, days FROM goals ORDER BY id")?;
+        let goals = stmt.query_map([], goal_from_row)?.collect();
+        goals
+    }
+
+    /// Get a goal by ID
+    pub fn get_goal(&self, id: i64) -> Result<Option<Goal>, rusqlite::Error> {
+        self.connection()
+            .query_row(
+                "SELECT id, name, apps, target_seconds, days FROM goals WHERE id = ?1",
+                params![id],
+                goal_from_row,
+            )
+            .optional()
+    }
+
+    /// Whether the goal was met on each day from `start` to `end` (inclusive) it applies to.
+    /// Days outside the goal's weekdays are left out.
+    pub fn goal_adherence(
+        &self,
+        goal_id: i64,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, bool)>, rusqlite::Error> {
+        let goal = self
+            .get_goal(goal_id)?
+            .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+
+        start
+            .iter_days()
+            .take_while(|day| *day <= end)
+            .filter(|day| goal.days.contains(&day.weekday()))
+            .map(|day| {
+                let total: i64 = self
+                    .get_day_app_totals(day)?
+                    .into_iter()
+                    .filter(|(app_id, _)| goal.apps.contains(app_id))
+                    .map(|(_, seconds)| seconds)
+                    .sum();
+                Ok((day, total >= goal.target_seconds))
+            })
+            .collect()
+    }
+}
+
+fn goal_from_row(row: &rusqlite::Row) -> Result<Goal, rusqlite::Error> {
+    let apps: String = row.get(2)?;
+    let days: String = row.get(4)?;
+    Ok(Goal {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        apps: apps
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect(),
+        target_seconds: row.get(3)?,
+        days: parse_days(&days),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Session;
+    use chrono::{TimeZone, Utc, Weekday};
+
+    fn seed(db: &Database, app_id: &str, day: u32, hours: i64) {
+        let start = Utc.with_ymd_and_hms(2024, 3, day, 8, 0, 0).unwrap();
+        db.insert_session(&Session {
+            id: None,
+            app_id: app_id.to_string(),
+            app_name: None,
+            start_time: start,
+            end_time: Some(start + chrono::Duration::hours(hours)),
+            duration_seconds: Some(hours * 3600),
+            is_idle: false,
+            manual: false,
+            process_path: None,
+            interruptions: 0,
+            input_rate: None,
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_goal_adherence_flags_applicable_days() {
+        let db = Database::in_memory();
+        let goal_id = db
+            .insert_goal(&Goal {
+                id: None,
+                name: "Coding".to_string(),
+                apps: vec!["Code.exe".to_string(), "devenv.exe".to_string()],
+                target_seconds: 4 * 3600,
+                days: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+            })
+            .unwrap();
+
+        // 2024-03-04 is a Monday
+        seed(&db, "Code.exe", 4, 5);
+        seed(&db, "Code.exe", 5, 2);
+        seed(&db, "devenv.exe", 5, 1);
+        seed(&db, "Code.exe", 6, 2);
+        seed(&db, "devenv.exe", 6, 2);
+        seed(&db, "Slack.exe", 7, 6);
+        // Weekend time doesn't count, and weekends aren't reported
+        seed(&db, "Code.exe", 9, 8);
+
+        let adherence = db
+            .goal_adherence(
+                goal_id,
+                NaiveDate::from_ymd_opt(2024, 3, 4).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 3, 10).unwrap(),
+            )
+            .unwrap();
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        assert_eq!(
+            adherence,
+            vec![
+                (day(4), true),
+                (day(5), false),
+                (day(6), true),
+                (day(7), false),
+                (day(8), false),
+            ]
+        );
+
+        assert!(db.goal_adherence(goal_id + 1, day(4), day(10)).is_err());
+    }
+}
@@ -0,0 +1,62 @@
+//! Single-instance guard: an exclusive lock on a file next to the database, so
+//! two copies of the app can't both write sessions into it.
+
+use crate::storage::db::DbError;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+
+/// File name of the lock file inside the database directory
+pub const LOCK_FILE_NAME: &str = "timewarden.lock";
+
+/// Set to skip the guard, e.g. to run a second copy against a scratch database
+pub const ALLOW_MULTIPLE_ENV_VAR: &str = "TIMEWARDEN_ALLOW_MULTIPLE_INSTANCES";
+
+/// Held for as long as this instance owns the database. The OS releases the
+/// lock when it is dropped or the process dies, so a crash never leaves it stale.
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    /// Lock the file next to `db_path`. Fails with `DbError::AlreadyRunning` if
+    /// another instance holds it.
+    pub fn acquire(db_path: &Path) -> Result<Self, DbError> {
+        let path = db_path.with_file_name(LOCK_FILE_NAME);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Self { _file: file, path }),
+            Err(TryLockError::WouldBlock) => Err(DbError::AlreadyRunning(path)),
+            Err(TryLockError::Error(e)) => Err(DbError::Io(e)),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_acquire_fails_until_released() {
+        let dir = std::env::temp_dir().join(format!("timewarden_lock_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("timewarden.db");
+
+        let first = InstanceLock::acquire(&db_path).unwrap();
+        assert_eq!(first.path(), dir.join(LOCK_FILE_NAME));
+        assert!(matches!(InstanceLock::acquire(&db_path), Err(DbError::AlreadyRunning(_))));
+
+        drop(first);
+        assert!(InstanceLock::acquire(&db_path).is_ok());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
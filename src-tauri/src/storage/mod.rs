@@ -1,5 +1,16 @@
+pub mod apps;
+pub mod bundle;
+pub mod collector_errors;
 pub mod db;
+pub mod export;
+pub mod goals;
+pub mod instance;
+pub mod neglect;
+pub mod projects;
+pub mod reports;
 pub mod sessions;
 pub mod schedules;
+pub mod settings;
+pub mod totals_cache;
 
-pub use db::{Database, DbError};
+pub use db::{resolve_db_path, Database, DbError, DbLocation};
@@ -0,0 +1,53 @@
+use crate::models::NeglectReminder;
+use crate::storage::db::Database;
+use chrono::NaiveTime;
+use rusqlite::params;
+
+impl Database {
+    /// Insert a new neglect reminder
+    pub fn insert_neglect_reminder(&self, reminder: &NeglectReminder) -> Result<i64, rusqlite::Error> {
+        self.connection().execute(
+            "INSERT INTO neglect_reminders (app_id, remind_after_secs, window_start, window_end, enabled)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                reminder.app_id,
+                reminder.remind_after_secs,
+                reminder.window_start.format("%H:%M").to_string(),
+                reminder.window_end.format("%H:%M").to_string(),
+                reminder.enabled
+            ],
+        )?;
+
+        Ok(self.connection().last_insert_rowid())
+    }
+
+    /// Delete a neglect reminder by ID
+    pub fn delete_neglect_reminder(&self, id: i64) -> Result<(), rusqlite::Error> {
+        self.connection()
+            .execute("DELETE FROM neglect_reminders WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Get all neglect reminders
+    pub fn get_all_neglect_reminders(&self) -> Result<Vec<NeglectReminder>, rusqlite::Error> {
+        let mut stmt = self.connection().prepare(
+            "SELECT id, app_id, remind_after_secs, window_start, window_end, enabled
+             FROM neglect_reminders ORDER BY id",
+        )?;
+        let reminders = stmt
+            .query_map([], |row| {
+                let window_start: String = row.get(3)?;
+                let window_end: String = row.get(4)?;
+                Ok(NeglectReminder {
+                    id: Some(row.get(0)?),
+                    app_id: row.get(1)?,
+                    remind_after_secs: row.get(2)?,
+                    window_start: NaiveTime::parse_from_str(&window_start, "%H:%M").unwrap_or(NaiveTime::MIN),
+                    window_end: NaiveTime::parse_from_str(&window_end, "%H:%M").unwrap_or(NaiveTime::MIN),
+                    enabled: row.get(5)?,
+                })
+            })?
+            .collect();
+        reminders
+    }
+}
@@ -0,0 +1,256 @@
+use crate::models::{ProjectRule, Session, TimesheetGrid, TimesheetRow};
+use crate::storage::db::Database;
+use crate::storage::export::{round_duration, DurationRounding};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use regex::Regex;
+use rusqlite::params;
+use std::collections::HashMap;
+
+/// Project of sessions that match no rule
+pub const NO_PROJECT: &str = "No Project";
+
+/// Project rules with their title patterns compiled, in matching order.
+/// Rules with an invalid pattern match nothing.
+pub(crate) struct ProjectMatcher {
+    rules: Vec<(ProjectRule, Option<Regex>)>,
+}
+
+impl ProjectMatcher {
+    fn new(rules: Vec<ProjectRule>) -> Self {
+        let rules = rules
+            .into_iter()
+            .filter_map(|rule| match rule.title_pattern.as_deref() {
+                None => Some((rule, None)),
+                Some(pattern) => Regex::new(pattern).ok().map(|regex| (rule, Some(regex))),
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Project of the first rule matching the session's process and, if the rule
+    /// has one, its title pattern; `NO_PROJECT` if none does
+    pub(crate) fn project_of(&self, session: &Session) -> &str {
+        let title = session.app_name.as_deref().unwrap_or("");
+        self.rules
+            .iter()
+            .find(|(rule, regex)| {
+                rule.process.eq_ignore_ascii_case(&session.app_id)
+                    && regex.as_ref().is_none_or(|regex| regex.is_match(title))
+            })
+            .map_or(NO_PROJECT, |(rule, _)| rule.project.as_str())
+    }
+}
+
+impl Database {
+    /// Insert a new project rule
+    pub fn insert_project_rule(&self, rule: &ProjectRule) -> Result<i64, rusqlite::Error> {
+        self.connection().execute(
+            "INSERT INTO project_rules (project, process, title_pattern) VALUES (?1, ?2, ?3)",
+            params![rule.project, rule.process, rule.title_pattern],
+        )?;
+
+        Ok(self.connection().last_insert_rowid())
+    }
+
+    /// Delete a project rule by ID
+    pub fn delete_project_rule(&self, id: i64) -> Result<(), rusqlite::Error> {
+        self.connection()
+            .execute("DELETE FROM project_rules WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Get all project rules in the order they are matched
+    pub fn get_all_project_rules(&self) -> Result<Vec<ProjectRule>, rusqlite::Error> {
+        let mut stmt = self
+            .connection()
+            .prepare("SELECT id, project, process, title_pattern FROM project_rules ORDER BY id")?;
+        let rules = stmt
+            .query_map([], |row| {
+                Ok(ProjectRule {
+                    id: Some(row.get(0)?),
+                    project: row.get(1)?,
+                    process: row.get(2)?,
+                    title_pattern: row.get(3)?,
+                })
+            })?
+            .collect();
+        rules
+    }
+
+    /// Matcher over the current project rules
+    pub(crate) fn project_matcher(&self) -> Result<ProjectMatcher, rusqlite::Error> {
+        Ok(ProjectMatcher::new(self.get_all_project_rules()?))
+    }
+
+    /// Active time per project for sessions starting in a range, most first.
+    /// Sessions are assigned as by `ProjectMatcher::project_of`.
+    pub fn get_project_totals(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(String, i64)>, rusqlite::Error> {
+        let matcher = self.project_matcher()?;
+
+        let mut totals: HashMap<String, i64> = HashMap::new();
+        for session in self.get_sessions_in_range(start, end)? {
+            if session.is_idle {
+                continue;
+            }
+            let project = matcher.project_of(&session);
+            *totals.entry(project.to_string()).or_default() += session.duration_seconds.unwrap_or(0);
+        }
+
+        let mut totals: Vec<_> = totals.into_iter().collect();
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(totals)
+    }
+
+    /// Active time per project and local day for the seven days from
+    /// `week_start`, using each day's sessions (see `get_day_sessions`). With
+    /// `rounding`, each cell is rounded and the totals add up the rounded cells,
+    /// so they match what is billed.
+    pub fn weekly_timesheet(
+        &self,
+        week_start: NaiveDate,
+        rounding: Option<DurationRounding>,
+    ) -> Result<TimesheetGrid, rusqlite::Error> {
+        let matcher = self.project_matcher()?;
+        let days: Vec<NaiveDate> = (0..7).map(|offset| week_start + Duration::days(offset)).collect();
+
+        let mut cells: HashMap<String, Vec<i64>> = HashMap::new();
+        for (column, day) in days.iter().enumerate() {
+            for session in self.get_day_sessions(*day)? {
+                if session.is_idle {
+                    continue;
+                }
+                let project = matcher.project_of(&session);
+                let row = cells.entry(project.to_string()).or_insert_with(|| vec![0; days.len()]);
+                row[column] += session.duration_seconds.unwrap_or(0);
+            }
+        }
+
+        let mut rows: Vec<TimesheetRow> = cells
+            .into_iter()
+            .map(|(project, mut seconds)| {
+                if let Some(rounding) = rounding {
+                    seconds.iter_mut().for_each(|cell| *cell = round_duration(*cell, rounding));
+                }
+                let total_seconds = seconds.iter().sum();
+                TimesheetRow { project, seconds, total_seconds }
+            })
+            .collect();
+        rows.sort_by(|a, b| b.total_seconds.cmp(&a.total_seconds).then_with(|| a.project.cmp(&b.project)));
+
+        let day_totals: Vec<i64> = (0..days.len()).map(|column| rows.iter().map(|row| row.seconds[column]).sum()).collect();
+        let total_seconds = day_totals.iter().sum();
+        Ok(TimesheetGrid { days, rows, day_totals, total_seconds })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// Monday 2024-01-01 09:00 UTC
+    const BASE: i64 = 1_704_099_600;
+    const DAY: i64 = 86_400;
+
+    /// A session starting `start` seconds after `BASE`
+    fn session(start: i64, app_id: &str, title: Option<&str>, duration: i64) -> Session {
+        Session {
+            id: None,
+            app_id: app_id.to_string(),
+            app_name: title.map(|t| t.to_string()),
+            start_time: Utc.timestamp_opt(BASE + start, 0).unwrap(),
+            end_time: Some(Utc.timestamp_opt(BASE + start + duration, 0).unwrap()),
+            duration_seconds: Some(duration),
+            is_idle: false,
+            manual: false,
+            process_path: None,
+            interruptions: 0,
+            input_rate: None,
+        }
+    }
+
+    fn rule(project: &str, process: &str, title_pattern: Option<&str>) -> ProjectRule {
+        ProjectRule {
+            id: None,
+            project: project.to_string(),
+            process: process.to_string(),
+            title_pattern: title_pattern.map(|p| p.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_project_totals_route_by_title() {
+        let db = Database::in_memory();
+        db.insert_project_rule(&rule("Acme", "code.exe", Some("acme-"))).unwrap();
+        db.insert_project_rule(&rule("Internal", "Code.exe", None)).unwrap();
+
+        db.insert_session(&session(0, "Code.exe", Some("main.rs - acme-portal"), 600)).unwrap();
+        db.insert_session(&session(600, "Code.exe", Some("notes.md - wiki"), 300)).unwrap();
+        db.insert_session(&session(900, "Slack.exe", Some("acme-portal"), 120)).unwrap();
+
+        let totals = db
+            .get_project_totals(Utc.timestamp_opt(BASE, 0).unwrap(), Utc.timestamp_opt(BASE + 3_600, 0).unwrap())
+            .unwrap();
+        assert_eq!(
+            totals,
+            vec![
+                ("Acme".to_string(), 600),
+                ("Internal".to_string(), 300),
+                (NO_PROJECT.to_string(), 120),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_weekly_timesheet_grid() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(crate::timezone::ReportTimezone::parse("UTC").unwrap());
+        db.insert_project_rule(&rule("Acme", "Code.exe", None)).unwrap();
+
+        db.insert_session(&session(0, "Code.exe", None, 3_000)).unwrap();
+        db.insert_session(&session(3_600, "Code.exe", None, 600)).unwrap();
+        db.insert_session(&session(2 * DAY, "Code.exe", None, 1_000)).unwrap();
+        db.insert_session(&session(2 * DAY + 3_600, "Slack.exe", None, 300)).unwrap();
+        // The following Monday is outside the week
+        db.insert_session(&session(7 * DAY, "Code.exe", None, 3_600)).unwrap();
+
+        let monday = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let grid = db.weekly_timesheet(monday, None).unwrap();
+        assert_eq!(grid.days.len(), 7);
+        assert_eq!(grid.rows[0].project, "Acme");
+        assert_eq!(grid.rows[0].seconds[0], 3_600);
+        assert_eq!(grid.rows[0].total_seconds, 4_600);
+        assert_eq!(grid.day_totals[2], 1_300);
+        assert_eq!(grid.total_seconds, 4_900);
+
+        // Rounded up to 15 minutes per cell: 60 + 30 for Acme, 15 for the rest
+        let rounding = DurationRounding { increment_minutes: 15, mode: Default::default() };
+        let rounded = db.weekly_timesheet(monday, Some(rounding)).unwrap();
+        assert_eq!(rounded.rows[0].total_seconds, 90 * 60);
+        assert_eq!(rounded.total_seconds, 105 * 60);
+
+        let csv = db.export_timesheet_csv(monday, None).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "project,2024-01-01,2024-01-02,2024-01-03,2024-01-04,2024-01-05,2024-01-06,2024-01-07,total");
+        assert_eq!(lines[1], "Acme,3600,0,1000,0,0,0,0,4600");
+        assert_eq!(lines[3], "Total,3600,0,1300,0,0,0,0,4900");
+    }
+
+    #[test]
+    fn test_timesheet_project_named_total() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(crate::timezone::ReportTimezone::parse("UTC").unwrap());
+        db.insert_project_rule(&rule("Total", "Code.exe", None)).unwrap();
+        db.insert_session(&session(0, "Code.exe", None, 600)).unwrap();
+
+        let monday = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let csv = db.export_timesheet_csv(monday, None).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[1], "Total (project),600,0,0,0,0,0,0,600");
+        assert_eq!(lines[2], "Total,600,0,0,0,0,0,0,600");
+    }
+}
@@ -0,0 +1,1274 @@
+use crate::models::{AppInfo, DayScheduleResult, Schedule, ScheduleStats, Session};
+use crate::scheduler::engine::window_intervals_on;
+use crate::scheduler::SchedulerEngine;
+use crate::timezone::ReportTimezone;
+use crate::storage::db::{Database, DbError};
+use crate::storage::sessions::attribute_to_days;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Timelike, Utc, Weekday};
+use rusqlite::params;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Default session length bucket boundaries: <1m, 1-5m, 5-15m, >=15m
+pub const DEFAULT_LENGTH_BUCKETS: [i64; 3] = [60, 300, 900];
+
+/// Minimum seconds of use within an hour for it to count towards a pattern
+const SUGGEST_MIN_SECS_PER_HOUR: i64 = 15 * 60;
+/// Minimum number of distinct days an (app, weekday, hour) must recur on
+const SUGGEST_MIN_OCCURRENCES: usize = 2;
+/// How far back schedule suggestions look
+const SUGGEST_LOOKBACK_DAYS: i64 = 14;
+
+/// Idle reason reported for sessions recorded without one
+pub const UNKNOWN_IDLE_REASON: &str = "unknown";
+
+/// Focus score weights; they sum to 1 so the score spans 0-100
+pub const FOCUS_WEIGHT_SESSION_LENGTH: f64 = 0.3;
+pub const FOCUS_WEIGHT_SWITCHING: f64 = 0.25;
+pub const FOCUS_WEIGHT_COMPLIANCE: f64 = 0.25;
+pub const FOCUS_WEIGHT_ACTIVITY: f64 = 0.2;
+/// Average session length that earns the full session-length component
+pub const FOCUS_TARGET_SESSION_SECS: f64 = 30.0 * 60.0;
+/// App switches per active hour at which the switching component halves
+pub const FOCUS_SWITCH_HALF_RATE: f64 = 4.0;
+
+/// Analytics and reporting queries
+impl Database {
+    /// Count sessions by length. `buckets` are ascending boundaries in seconds; the
+    /// result has one `(lower_bound, count)` entry per bucket, starting at 0, so
+    /// `[60, 300]` yields counts for `0..60`, `60..300` and `300..`.
+    pub fn session_length_histogram(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        buckets: &[i64],
+        include_idle: bool,
+    ) -> Result<Vec<(i64, i64)>, rusqlite::Error> {
+        let mut boundaries = buckets.to_vec();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let mut histogram: Vec<(i64, i64)> = std::iter::once(0)
+            .chain(boundaries.iter().copied().filter(|b| *b > 0))
+            .map(|lower| (lower, 0))
+            .collect();
+
+        let mut stmt = self.connection().prepare(
+            "SELECT duration_seconds
+             FROM sessions
+             WHERE start_time >= ?1 AND start_time <= ?2
+               AND duration_seconds IS NOT NULL
+               AND (?3 OR is_idle = FALSE)",
+        )?;
+        let durations = stmt.query_map(params![start.timestamp(), end.timestamp(), include_idle], |row| {
+            row.get::<_, i64>(0)
+        })?;
+
+        for duration in durations {
+            let duration = duration?;
+            if let Some(bucket) = histogram.iter_mut().rev().find(|(lower, _)| duration >= *lower) {
+                bucket.1 += 1;
+            }
+        }
+
+        Ok(histogram)
+    }
+
+    /// Mean non-idle session length per app for sessions starting in a range,
+    /// apps with the most sessions first (ties by longer average, then name)
+    pub fn avg_session_length_per_app(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(String, f64)>, rusqlite::Error> {
+        let mut stmt = self.connection().prepare(
+            "SELECT app_id, AVG(duration_seconds) AS average
+             FROM sessions
+             WHERE start_time >= ?1 AND start_time <= ?2
+               AND duration_seconds IS NOT NULL AND is_idle = FALSE
+             GROUP BY app_id
+             ORDER BY COUNT(*) DESC, average DESC, app_id ASC",
+        )?;
+        let averages = stmt.query_map(params![start.timestamp(), end.timestamp()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+        })?;
+        averages.collect()
+    }
+
+    /// Daily compliance rate (compliant logs / all logs) for a schedule, bucketed
+    /// by local day. Days without any logs are omitted rather than reported as 0.
+    pub fn compliance_trend(
+        &self,
+        schedule_id: i64,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(NaiveDate, f64)>, rusqlite::Error> {
+        let tz = self.report_timezone();
+        let mut stmt = self.connection().prepare(
+            "SELECT timestamp, is_compliant
+             FROM compliance_logs
+             WHERE schedule_id = ?1 AND timestamp >= ?2 AND timestamp <= ?3",
+        )?;
+        let logs = stmt.query_map(params![schedule_id, start.timestamp(), end.timestamp()], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?))
+        })?;
+
+        // day -> (compliant, total)
+        let mut days: BTreeMap<NaiveDate, (u32, u32)> = BTreeMap::new();
+        for log in logs {
+            let (timestamp, is_compliant) = log?;
+            let Some(at) = DateTime::from_timestamp(timestamp, 0) else {
+                continue;
+            };
+            let counts = days.entry(tz.to_local(at).date()).or_default();
+            counts.0 += is_compliant as u32;
+            counts.1 += 1;
+        }
+
+        Ok(days
+            .into_iter()
+            .map(|(day, (compliant, total))| (day, compliant as f64 / total as f64))
+            .collect())
+    }
+
+    /// `(compliant, total)` compliance log counts of a schedule per local hour of
+    /// day (index 0 = midnight), to spot the hours it's most often broken
+    pub fn compliance_by_hour(
+        &self,
+        schedule_id: i64,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<[(u32, u32); 24], rusqlite::Error> {
+        let tz = self.report_timezone();
+        let mut stmt = self.connection().prepare(
+            "SELECT timestamp, is_compliant
+             FROM compliance_logs
+             WHERE schedule_id = ?1 AND timestamp >= ?2 AND timestamp <= ?3",
+        )?;
+        let logs = stmt.query_map(params![schedule_id, start.timestamp(), end.timestamp()], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?))
+        })?;
+
+        let mut hours = [(0u32, 0u32); 24];
+        for log in logs {
+            let (timestamp, is_compliant) = log?;
+            let Some(at) = DateTime::from_timestamp(timestamp, 0) else {
+                continue;
+            };
+            let counts = &mut hours[tz.to_local(at).hour() as usize];
+            counts.0 += is_compliant as u32;
+            counts.1 += 1;
+        }
+        Ok(hours)
+    }
+
+    /// App most often in the foreground when a schedule was logged as
+    /// non-compliant, ties broken alphabetically
+    pub fn most_common_distraction(
+        &self,
+        schedule_id: i64,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Option<String>, rusqlite::Error> {
+        let mut stmt = self.connection().prepare(
+            "SELECT current_app
+             FROM compliance_logs
+             WHERE schedule_id = ?1 AND timestamp >= ?2 AND timestamp <= ?3
+               AND is_compliant = FALSE AND current_app IS NOT NULL
+             GROUP BY current_app
+             ORDER BY COUNT(*) DESC, current_app ASC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![schedule_id, start.timestamp(), end.timestamp()])?;
+        rows.next()?.map(|row| row.get(0)).transpose()
+    }
+
+    /// Apps used right before switching to a distraction (an app containing
+    /// `distraction_pattern`, case-insensitive), with how often, most first.
+    /// Only active sessions starting between `start` and `end` are considered;
+    /// idle time in between is skipped, and one distraction following another
+    /// isn't counted.
+    pub fn distraction_precursors(
+        &self,
+        distraction_pattern: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(String, i64)>, rusqlite::Error> {
+        let pattern = distraction_pattern.to_lowercase();
+        let is_distraction = |session: &Session| session.app_id.to_lowercase().contains(&pattern);
+
+        let sessions = self.get_sessions_in_range(start, end)?;
+        let active: Vec<&Session> = sessions.iter().filter(|s| !s.is_idle).collect();
+        let mut counts: HashMap<String, i64> = HashMap::new();
+        for pair in active.windows(2) {
+            let (before, session) = (pair[0], pair[1]);
+            if is_distraction(session) && !is_distraction(before) {
+                *counts.entry(before.app_id.clone()).or_default() += 1;
+            }
+        }
+
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(counts)
+    }
+
+    /// Compliance rate, notifications, top distraction and violation frequency
+    /// of a schedule between `start` and `end`
+    pub fn schedule_stats(
+        &self,
+        schedule_id: i64,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<ScheduleStats, rusqlite::Error> {
+        let trend = self.compliance_trend(schedule_id, start, end)?;
+        let average_compliance_rate =
+            (!trend.is_empty()).then(|| trend.iter().map(|(_, rate)| rate).sum::<f64>() / trend.len() as f64);
+
+        let violations: i64 = self.connection().query_row(
+            "SELECT COUNT(*) FROM compliance_logs
+             WHERE schedule_id = ?1 AND timestamp >= ?2 AND timestamp <= ?3 AND is_compliant = FALSE",
+            params![schedule_id, start.timestamp(), end.timestamp()],
+            |row| row.get(0),
+        )?;
+        let violations_per_active_day = if trend.is_empty() {
+            0.0
+        } else {
+            violations as f64 / trend.len() as f64
+        };
+
+        Ok(ScheduleStats {
+            schedule_id,
+            average_compliance_rate,
+            notifications_sent: self.notification_count(schedule_id, start, end)?,
+            most_common_distraction: self.most_common_distraction(schedule_id, start, end)?,
+            violations_per_active_day,
+        })
+    }
+
+    /// Focus score of a local day, 0-100 (rounded to two decimals). Weighted sum of:
+    /// - session length: average active session / `FOCUS_TARGET_SESSION_SECS`, capped at 1
+    /// - switching: `1 / (1 + switches_per_active_hour / FOCUS_SWITCH_HALF_RATE)`, where a
+    ///   switch is a change of app between consecutive active sessions
+    /// - compliance: compliant / all compliance logs of the day (1 without logs)
+    /// - activity: active / (active + idle) time
+    ///
+    /// A day without active time scores 0.
+    pub fn focus_score(&self, day: NaiveDate) -> Result<f64, rusqlite::Error> {
+        let sessions = self.get_day_sessions(day)?;
+        let active: Vec<&Session> = sessions.iter().filter(|s| !s.is_idle).collect();
+        let active_secs: i64 = active.iter().map(|s| s.duration_seconds.unwrap_or(0)).sum();
+        if active_secs <= 0 {
+            return Ok(0.0);
+        }
+        let idle_secs: i64 = sessions
+            .iter()
+            .filter(|s| s.is_idle)
+            .map(|s| s.duration_seconds.unwrap_or(0))
+            .sum();
+
+        let average_session = active_secs as f64 / active.len() as f64;
+        let session_length = (average_session / FOCUS_TARGET_SESSION_SECS).min(1.0);
+
+        let switches = active.windows(2).filter(|pair| pair[0].app_id != pair[1].app_id).count();
+        let switches_per_hour = switches as f64 / (active_secs as f64 / 3_600.0);
+        let switching = 1.0 / (1.0 + switches_per_hour / FOCUS_SWITCH_HALF_RATE);
+
+        let (day_start, day_end) = self.report_timezone().day_bounds(day);
+        let (compliant, logged): (i64, i64) = self.connection().query_row(
+            "SELECT COALESCE(SUM(is_compliant), 0), COUNT(*) FROM compliance_logs
+             WHERE timestamp >= ?1 AND timestamp <= ?2",
+            params![day_start.timestamp(), day_end.timestamp()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let compliance = if logged == 0 {
+            1.0
+        } else {
+            compliant as f64 / logged as f64
+        };
+
+        let activity = active_secs as f64 / (active_secs + idle_secs) as f64;
+
+        let score = 100.0
+            * (FOCUS_WEIGHT_SESSION_LENGTH * session_length
+                + FOCUS_WEIGHT_SWITCHING * switching
+                + FOCUS_WEIGHT_COMPLIANCE * compliance
+                + FOCUS_WEIGHT_ACTIVITY * activity);
+        Ok((score * 100.0).round() / 100.0)
+    }
+
+    /// Seconds from the start of a local day's first non-idle session to the start
+    /// of its first non-idle session lasting at least `min_focus_secs`. None if the
+    /// day has no such session.
+    pub fn time_to_first_focus(&self, day: NaiveDate, min_focus_secs: i64) -> Result<Option<i64>, rusqlite::Error> {
+        let sessions = self.get_day_sessions(day)?;
+        let active: Vec<&Session> = sessions.iter().filter(|s| !s.is_idle).collect();
+        let Some(first) = active.first() else {
+            return Ok(None);
+        };
+        Ok(active
+            .iter()
+            .find(|s| s.duration_seconds.unwrap_or(0) >= min_focus_secs)
+            .map(|focus| (focus.start_time - first.start_time).num_seconds()))
+    }
+
+    /// Total idle seconds per recorded idle reason, largest first.
+    /// Idle sessions without a reason are grouped under `UNKNOWN_IDLE_REASON`.
+    pub fn idle_breakdown(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(String, i64)>, rusqlite::Error> {
+        let mut stmt = self.connection().prepare(
+            "SELECT COALESCE(idle_reason, ?3) AS reason, COALESCE(SUM(duration_seconds), 0) AS total
+             FROM sessions
+             WHERE start_time >= ?1 AND start_time <= ?2 AND is_idle = TRUE
+             GROUP BY reason
+             ORDER BY total DESC, reason ASC",
+        )?;
+        let breakdown = stmt.query_map(
+            params![start.timestamp(), end.timestamp(), UNKNOWN_IDLE_REASON],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+        )?;
+        breakdown.collect()
+    }
+
+    /// Longest non-idle session of each local day between `start` and `end`
+    /// (sessions crossing midnight are assigned per the `DayAttribution`). Ties
+    /// go to the earlier session; days without activity are omitted.
+    pub fn deepest_focus_by_day(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(NaiveDate, Session)>, rusqlite::Error> {
+        let tz = self.report_timezone();
+        let mut days: BTreeMap<NaiveDate, Session> = BTreeMap::new();
+
+        let sessions = self.get_sessions_in_range(start, end)?;
+        let pieces = sessions
+            .iter()
+            .filter(|session| !session.is_idle)
+            .flat_map(|session| attribute_to_days(session, tz, self.day_attribution()));
+        for (day, session) in pieces {
+            let duration = session.duration_seconds.unwrap_or(0);
+            match days.get(&day) {
+                Some(best) if best.duration_seconds.unwrap_or(0) >= duration => {}
+                _ => {
+                    days.insert(day, session);
+                }
+            }
+        }
+
+        Ok(days.into_iter().collect())
+    }
+
+    /// Number of non-idle sessions and their active seconds on local `weekday`s
+    /// between `start` and `end`. Sessions crossing midnight are assigned per the
+    /// `DayAttribution`.
+    pub fn stats_for_weekday(
+        &self,
+        weekday: Weekday,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<(i64, i64), rusqlite::Error> {
+        let tz = self.report_timezone();
+        let sessions = self.get_sessions_in_range(start, end)?;
+        let (count, active_secs) = sessions
+            .iter()
+            .filter(|session| !session.is_idle)
+            .flat_map(|session| attribute_to_days(session, tz, self.day_attribution()))
+            .filter(|(day, _)| day.weekday() == weekday)
+            .fold((0, 0), |(count, secs), (_, piece)| (count + 1, secs + piece.duration_seconds.unwrap_or(0)));
+        Ok((count, active_secs))
+    }
+
+    /// Mean active seconds per local day over the `days` days ending on `ending`
+    /// (inclusive), counting days without activity as zero. 0 when `days` is 0.
+    pub fn rolling_daily_average(&self, days: u32, ending: NaiveDate) -> Result<f64, rusqlite::Error> {
+        if days == 0 {
+            return Ok(0.0);
+        }
+        let start = ending - Duration::days(i64::from(days) - 1);
+        let total: i64 = self
+            .get_daily_totals(start, ending)?
+            .iter()
+            .flat_map(|(_, totals)| totals.iter().map(|(_, secs)| secs))
+            .sum();
+        Ok(total as f64 / f64::from(days))
+    }
+
+    /// Number of consecutive local days with active time, counting back from
+    /// `ending` (inclusive). 0 if `ending` itself has none.
+    pub fn active_day_streak(&self, ending: NaiveDate) -> Result<u32, rusqlite::Error> {
+        let mut streak = 0;
+        let mut day = Some(ending);
+        while let Some(current) = day {
+            let active: i64 = self
+                .get_daily_totals(current, current)?
+                .iter()
+                .flat_map(|(_, totals)| totals.iter().map(|(_, secs)| secs))
+                .sum();
+            if active <= 0 {
+                break;
+            }
+            streak += 1;
+            day = current.pred_opt();
+        }
+        Ok(streak)
+    }
+
+    fn find_schedule(&self, schedule_id: i64) -> Result<Schedule, DbError> {
+        self.get_all_schedules()?
+            .into_iter()
+            .find(|s| s.id == Some(schedule_id))
+            .ok_or_else(|| DbError::InvalidInput(format!("schedule {} not found", schedule_id)))
+    }
+
+    /// Seconds of `from..to` that fall inside the schedule's windows (local days)
+    fn seconds_in_schedule(&self, schedule: &Schedule, from: DateTime<Utc>, to: DateTime<Utc>) -> i64 {
+        let tz = self.report_timezone();
+        let first_day = tz.to_local(from).date();
+        let last_day = tz.to_local(to).date();
+        first_day
+            .iter_days()
+            .take_while(|day| *day <= last_day)
+            .flat_map(|day| window_intervals_on(schedule, day))
+            .map(|(window_start, window_end)| {
+                let overlap_start = from.max(tz.to_utc(window_start));
+                let overlap_end = to.min(tz.to_utc(window_end));
+                (overlap_end - overlap_start).num_seconds().max(0)
+            })
+            .sum()
+    }
+
+    /// Active seconds per app (most first) that fell inside a schedule's windows
+    /// between `start` and `end`, with sessions clipped to the windows.
+    pub fn apps_during_schedule(
+        &self,
+        schedule_id: i64,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(String, i64)>, DbError> {
+        let schedule = self.find_schedule(schedule_id)?;
+
+        let mut totals: HashMap<String, i64> = HashMap::new();
+        for session in self.get_sessions_in_range(start, end)? {
+            let Some(session_end) = session.end_time else { continue };
+            if session.is_idle {
+                continue;
+            }
+            let inside = self.seconds_in_schedule(&schedule, session.start_time.max(start), session_end.min(end));
+            if inside > 0 {
+                *totals.entry(session.app_id).or_default() += inside;
+            }
+        }
+
+        let mut totals: Vec<_> = totals.into_iter().collect();
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(totals)
+    }
+
+    /// Active seconds of sessions starting between `start` and `end` (clipped to
+    /// the range) during which at least one enabled schedule was in effect and
+    /// the app was compliant with it, using the allowlist of the schedule window
+    /// active at the time. Time outside every schedule, or spent on apps no
+    /// active schedule allows, is excluded.
+    pub fn compliant_active_seconds(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<i64, DbError> {
+        let schedules: Vec<Schedule> = self.get_all_schedules()?.into_iter().filter(|s| s.enabled).collect();
+        if schedules.is_empty() {
+            return Ok(0);
+        }
+        let tz = self.report_timezone();
+        let scheduler = SchedulerEngine::new();
+
+        let mut total = 0;
+        for session in self.get_sessions_in_range(start, end)? {
+            let Some(session_end) = session.end_time else { continue };
+            let (from, to) = (session.start_time.max(start), session_end.min(end));
+            if session.is_idle || to <= from {
+                continue;
+            }
+            let app = session_app(&session);
+            // Compliance can only change where a schedule or one of its windows opens or closes
+            let bounds = compliance_bounds(&schedules, tz, from, to);
+            for pair in bounds.windows(2) {
+                let local = tz.to_local(pair[0]);
+                let compliant = scheduler
+                    .active_schedules_at(&schedules, local)
+                    .into_iter()
+                    .any(|schedule| scheduler.is_app_compliant(schedule, scheduler.active_window_at(schedule, local), &app));
+                if compliant {
+                    total += (pair[1] - pair[0]).num_seconds();
+                }
+            }
+        }
+        Ok(total)
+    }
+
+    /// Compliant and total active seconds inside the windows of each enabled
+    /// schedule that applies on a local day, using the sessions owned by that day
+    /// (see `get_day_sessions`)
+    pub fn day_schedule_report(&self, day: NaiveDate) -> Result<Vec<DayScheduleResult>, DbError> {
+        let tz = self.report_timezone();
+        let scheduler = SchedulerEngine::new();
+        let sessions: Vec<Session> =
+            self.get_day_sessions(day)?.into_iter().filter(|session| !session.is_idle).collect();
+
+        let mut results = Vec::new();
+        for schedule in self.get_all_schedules()?.into_iter().filter(|s| s.enabled) {
+            let intervals = window_intervals_on(&schedule, day);
+            if intervals.is_empty() {
+                continue;
+            }
+            let (mut compliant_seconds, mut in_window_seconds) = (0, 0);
+            for session in &sessions {
+                let Some(session_end) = session.end_time else { continue };
+                let app = session_app(session);
+                for (window_start, window_end) in &intervals {
+                    let from = session.start_time.max(tz.to_utc(*window_start));
+                    let to = session_end.min(tz.to_utc(*window_end));
+                    if to <= from {
+                        continue;
+                    }
+                    in_window_seconds += (to - from).num_seconds();
+                    // The allowlist can change where one of the schedule's windows opens or closes
+                    let bounds = compliance_bounds(std::slice::from_ref(&schedule), tz, from, to);
+                    for pair in bounds.windows(2) {
+                        let local = tz.to_local(pair[0]);
+                        if scheduler.is_app_compliant(&schedule, scheduler.active_window_at(&schedule, local), &app) {
+                            compliant_seconds += (pair[1] - pair[0]).num_seconds();
+                        }
+                    }
+                }
+            }
+            results.push(DayScheduleResult {
+                schedule_id: schedule.id.unwrap_or_default(),
+                schedule_name: schedule.name,
+                compliant_seconds,
+                in_window_seconds,
+                compliance_rate: (in_window_seconds > 0)
+                    .then(|| compliant_seconds as f64 / in_window_seconds as f64),
+            });
+        }
+        Ok(results)
+    }
+
+    /// Idle seconds that fell inside a schedule's windows between `start` and
+    /// `end`, with idle sessions clipped to the windows
+    pub fn idle_during_schedule(
+        &self,
+        schedule_id: i64,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<i64, DbError> {
+        let schedule = self.find_schedule(schedule_id)?;
+        Ok(self
+            .get_sessions_in_range(start, end)?
+            .into_iter()
+            .filter(|session| session.is_idle)
+            .filter_map(|session| {
+                let session_end = session.end_time?;
+                Some(self.seconds_in_schedule(&schedule, session.start_time.max(start), session_end.min(end)))
+            })
+            .sum())
+    }
+
+    /// Suggest schedules from recurring app usage over the past two weeks.
+    /// Suggestions are disabled and not persisted.
+    pub fn suggest_schedules(&self) -> Result<Vec<Schedule>, rusqlite::Error> {
+        let now = Utc::now();
+        self.suggest_schedules_between(now - Duration::days(SUGGEST_LOOKBACK_DAYS), now)
+    }
+
+    /// Suggest schedules from recurring app usage between `start` and `end`.
+    /// Usage is bucketed by local weekday and hour; an hour qualifies when the app was
+    /// used for at least 15 minutes of it on two or more days. Consecutive qualifying
+    /// hours on the same weekdays are joined into one window.
+    pub fn suggest_schedules_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Schedule>, rusqlite::Error> {
+        let tz = self.report_timezone();
+
+        // Seconds of use per (app, local date, hour)
+        let mut usage: HashMap<(String, NaiveDate, u32), i64> = HashMap::new();
+        for session in self.get_sessions_in_range(start, end)? {
+            let Some(session_end) = session.end_time else { continue };
+            if session.is_idle {
+                continue;
+            }
+
+            let mut cursor = tz.to_local(session.start_time);
+            let local_end = tz.to_local(session_end);
+            while cursor < local_end {
+                let hour_start = cursor.date().and_hms_opt(cursor.hour(), 0, 0).unwrap();
+                let next_hour = hour_start + Duration::hours(1);
+                let slice_end = next_hour.min(local_end);
+                *usage
+                    .entry((session.app_id.clone(), cursor.date(), cursor.hour()))
+                    .or_default() += (slice_end - cursor).num_seconds();
+                cursor = slice_end;
+            }
+        }
+
+        // Distinct dates each (app, weekday, hour) qualified on
+        let mut occurrences: HashMap<(String, Weekday, u32), HashSet<NaiveDate>> = HashMap::new();
+        for ((app_id, date, hour), secs) in usage {
+            if secs >= SUGGEST_MIN_SECS_PER_HOUR {
+                occurrences
+                    .entry((app_id, date.weekday(), hour))
+                    .or_default()
+                    .insert(date);
+            }
+        }
+
+        // Weekdays each app recurs on, per hour
+        let mut recurring: BTreeMap<String, BTreeMap<u32, Vec<Weekday>>> = BTreeMap::new();
+        for ((app_id, weekday, hour), dates) in occurrences {
+            if dates.len() >= SUGGEST_MIN_OCCURRENCES {
+                recurring
+                    .entry(app_id)
+                    .or_default()
+                    .entry(hour)
+                    .or_default()
+                    .push(weekday);
+            }
+        }
+
+        let mut suggestions = Vec::new();
+        for (app_id, hours) in recurring {
+            let mut window: Option<(u32, u32, Vec<Weekday>)> = None;
+            for (hour, mut days) in hours {
+                days.sort_by_key(|d| d.num_days_from_monday());
+                window = match window {
+                    Some((first, last, window_days)) if last + 1 == hour && window_days == days => {
+                        Some((first, hour, window_days))
+                    }
+                    Some(done) => {
+                        suggestions.push(suggested_schedule(&app_id, done));
+                        Some((hour, hour, days))
+                    }
+                    None => Some((hour, hour, days)),
+                };
+            }
+            if let Some(done) = window {
+                suggestions.push(suggested_schedule(&app_id, done));
+            }
+        }
+
+        Ok(suggestions)
+    }
+}
+
+/// `from`, `to` and every time strictly between them at which one of the
+/// schedules or their windows opens or closes (ends are inclusive, so they close
+/// a second later), in order
+fn compliance_bounds(schedules: &[Schedule], tz: ReportTimezone, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    let one_second = Duration::seconds(1);
+    let first_day = tz.to_local(from).date().pred_opt().unwrap_or(NaiveDate::MIN);
+    let last_day = tz.to_local(to).date();
+
+    let mut bounds = vec![from, to];
+    for day in first_day.iter_days().take_while(|day| *day <= last_day) {
+        let midnight = day.and_time(NaiveTime::MIN);
+        bounds.push(tz.to_utc(midnight));
+        let edges = schedules.iter().flat_map(|schedule| {
+            std::iter::once((schedule.start_time, schedule.end_time))
+                .chain(schedule.windows.iter().map(|window| (window.start_time, window.end_time)))
+        });
+        for (start_time, end_time) in edges {
+            bounds.push(tz.to_utc(day.and_time(start_time)));
+            if let Some(end_time) = end_time {
+                bounds.push(tz.to_utc(day.and_time(end_time) + one_second));
+            }
+        }
+    }
+    bounds.retain(|bound| (from..=to).contains(bound));
+    bounds.sort();
+    bounds.dedup();
+    bounds
+}
+
+/// The app a session tracked, as schedules match it
+fn session_app(session: &Session) -> AppInfo {
+    AppInfo {
+        process_name: session.app_id.clone(),
+        app_title: session.app_name.clone(),
+        bundle_id: None,
+        process_path: session.process_path.clone(),
+    }
+}
+
+/// Build a disabled schedule covering hours `first..=last` on `days`
+fn suggested_schedule(app_id: &str, (first, last, days): (u32, u32, Vec<Weekday>)) -> Schedule {
+    let start_time = NaiveTime::from_hms_opt(first, 0, 0).unwrap();
+    // Usage through 23:59 runs to the end of the day
+    let end_time = NaiveTime::from_hms_opt(last + 1, 0, 0);
+
+    Schedule {
+        id: None,
+        name: format!(
+            "{} {}-{}",
+            app_id,
+            start_time.format("%H:%M"),
+            end_time.map_or("24:00".to_string(), |t| t.format("%H:%M").to_string())
+        ),
+        start_time,
+        end_time,
+        days,
+        expected_apps: vec![app_id.to_string()],
+        enabled: false,
+        ..Schedule::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timezone::ReportTimezone;
+    use chrono::TimeZone;
+
+    fn session(app_id: &str, start: DateTime<Utc>, minutes: i64) -> Session {
+        Session {
+            id: None,
+            app_id: app_id.to_string(),
+            app_name: None,
+            start_time: start,
+            end_time: Some(start + Duration::minutes(minutes)),
+            duration_seconds: Some(minutes * 60),
+            is_idle: false,
+            manual: false,
+            process_path: None,
+            interruptions: 0,
+            input_rate: None,
+        }
+    }
+
+    #[test]
+    fn test_session_length_histogram() {
+        let db = Database::in_memory();
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+        for (offset, secs) in [(0, 30), (1, 59), (2, 60), (3, 240), (4, 600), (5, 900), (6, 3_600)] {
+            let start = base + Duration::hours(offset);
+            let mut s = session("Code.exe", start, 0);
+            s.end_time = Some(start + Duration::seconds(secs));
+            s.duration_seconds = Some(secs);
+            db.insert_session(&s).unwrap();
+        }
+        let mut idle = session("Idle", base + Duration::hours(8), 2);
+        idle.is_idle = true;
+        db.insert_session(&idle).unwrap();
+
+        let range_end = base + Duration::days(1);
+        let histogram = db
+            .session_length_histogram(base, range_end, &DEFAULT_LENGTH_BUCKETS, false)
+            .unwrap();
+        assert_eq!(histogram, vec![(0, 2), (60, 2), (300, 1), (900, 2)]);
+
+        let histogram = db
+            .session_length_histogram(base, range_end, &DEFAULT_LENGTH_BUCKETS, true)
+            .unwrap();
+        assert_eq!(histogram, vec![(0, 2), (60, 3), (300, 1), (900, 2)]);
+    }
+
+    #[test]
+    fn test_avg_session_length_per_app() {
+        let db = Database::in_memory();
+        let t0 = Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap();
+        for (app, offset, minutes) in [("Code.exe", 0, 30), ("Slack.exe", 30, 4), ("Code.exe", 34, 55)] {
+            db.insert_session(&session(app, t0 + Duration::minutes(offset), minutes)).unwrap();
+        }
+        db.insert_session(&Session {
+            is_idle: true,
+            ..session("Code.exe", t0 + Duration::minutes(89), 60)
+        })
+        .unwrap();
+
+        let averages = db.avg_session_length_per_app(t0, t0 + Duration::hours(3)).unwrap();
+        assert_eq!(
+            averages,
+            vec![("Code.exe".to_string(), 42.5 * 60.0), ("Slack.exe".to_string(), 4.0 * 60.0)]
+        );
+    }
+
+    #[test]
+    fn test_suggests_recurring_weekday_window() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+
+        // VS Code 09:00-11:00 every weekday for two weeks starting Mon 2024-01-01
+        for week in 0..2 {
+            for weekday in 0..5 {
+                let day = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap()
+                    + Duration::days(week * 7 + weekday);
+                db.insert_session(&session("Code.exe", day, 120)).unwrap();
+                // One-off Slack use shouldn't produce a suggestion
+                if week == 0 && weekday == 2 {
+                    db.insert_session(&session("Slack.exe", day + Duration::hours(5), 30)).unwrap();
+                }
+            }
+        }
+
+        let suggestions = db
+            .suggest_schedules_between(
+                Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 1, 15, 0, 0, 0).unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(suggestions.len(), 1);
+        let suggestion = &suggestions[0];
+        assert_eq!(suggestion.expected_apps, vec!["Code.exe".to_string()]);
+        assert_eq!(suggestion.start_time, NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+        assert_eq!(suggestion.end_time, NaiveTime::from_hms_opt(11, 0, 0));
+        assert_eq!(
+            suggestion.days,
+            vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri]
+        );
+        assert!(!suggestion.enabled);
+        assert!(suggestion.id.is_none());
+    }
+
+    #[test]
+    fn test_compliance_trend_daily_ratios() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+        let id = db.insert_schedule(&Schedule::default()).unwrap();
+        let other = db.insert_schedule(&Schedule::default()).unwrap();
+
+        let monday = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+        for (minutes, compliant) in [(0, true), (5, true), (10, true), (15, false)] {
+            db.insert_compliance_log_at(id, compliant, None, monday + Duration::minutes(minutes)).unwrap();
+        }
+        // Tuesday: no logs. Wednesday: half compliant.
+        let wednesday = monday + Duration::days(2);
+        db.insert_compliance_log_at(id, true, None, wednesday).unwrap();
+        db.insert_compliance_log_at(id, false, None, wednesday + Duration::minutes(5)).unwrap();
+        db.insert_compliance_log_at(other, false, None, wednesday).unwrap();
+
+        let trend = db
+            .compliance_trend(id, monday - Duration::hours(9), monday + Duration::days(7))
+            .unwrap();
+        assert_eq!(
+            trend,
+            vec![
+                (NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(), 0.75),
+                (NaiveDate::from_ymd_opt(2024, 1, 3).unwrap(), 0.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compliance_by_hour() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("America/New_York").unwrap());
+        let id = db.insert_schedule(&Schedule::default()).unwrap();
+        let other = db.insert_schedule(&Schedule::default()).unwrap();
+
+        // 14:00 UTC = 09:00 in New York in January
+        let morning = Utc.with_ymd_and_hms(2024, 1, 8, 14, 0, 0).unwrap();
+        db.insert_compliance_log_at(id, true, None, morning).unwrap();
+        db.insert_compliance_log_at(id, true, None, morning + Duration::minutes(30)).unwrap();
+        // The 3pm slump, two days running
+        let slump = morning + Duration::hours(6);
+        db.insert_compliance_log_at(id, false, None, slump).unwrap();
+        db.insert_compliance_log_at(id, true, None, slump + Duration::minutes(10)).unwrap();
+        db.insert_compliance_log_at(id, false, None, slump + Duration::days(1)).unwrap();
+        db.insert_compliance_log_at(other, false, None, morning).unwrap();
+
+        let hours = db
+            .compliance_by_hour(id, morning - Duration::hours(14), morning + Duration::days(7))
+            .unwrap();
+        assert_eq!(hours[9], (2, 2));
+        assert_eq!(hours[15], (1, 3));
+        let total: u32 = hours.iter().map(|(_, total)| total).sum();
+        assert_eq!(total, 5);
+    }
+
+    #[test]
+    fn test_distraction_precursors_rank_by_count() {
+        let db = Database::in_memory();
+        let t0 = Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap();
+        let apps = [
+            "Code.exe", "Slack.exe", "YouTube.exe",
+            "Code.exe", "Slack.exe", "Idle", "YouTube.exe", "YouTube.exe",
+            "Outlook.exe", "YouTube.exe",
+            "Code.exe", "Slack.exe", "YouTube.exe",
+        ];
+        for (i, app) in apps.into_iter().enumerate() {
+            let start = t0 + Duration::minutes(10 * i as i64);
+            db.insert_session(&Session { is_idle: app == "Idle", ..session(app, start, 10) }).unwrap();
+        }
+
+        let precursors = db.distraction_precursors("youtube", t0, t0 + Duration::hours(3)).unwrap();
+        assert_eq!(precursors, vec![("Slack.exe".to_string(), 3), ("Outlook.exe".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_schedule_stats() {
+        let db = Database::in_memory();
+        let day1 = Utc.with_ymd_and_hms(2024, 3, 4, 10, 0, 0).unwrap();
+        let day2 = day1 + Duration::days(1);
+        let id = db.insert_schedule(&Schedule::default()).unwrap();
+        let other = db.insert_schedule(&Schedule::default()).unwrap();
+        let log = |at: DateTime<Utc>, compliant: bool, app: &str| {
+            db.insert_compliance_log_at(id, compliant, Some(app), at).unwrap();
+        };
+        // Day 1: 1 of 4 compliant; day 2: 1 of 2 compliant
+        log(day1, true, "Code.exe");
+        log(day1 + Duration::minutes(5), false, "Discord.exe");
+        log(day1 + Duration::minutes(10), false, "Discord.exe");
+        log(day1 + Duration::minutes(15), false, "Steam.exe");
+        log(day2, true, "Code.exe");
+        log(day2 + Duration::minutes(5), false, "Steam.exe");
+        // Another schedule's logs don't count
+        db.insert_compliance_log_at(other, false, Some("Steam.exe"), day2).unwrap();
+        for at in [day1 + Duration::minutes(11), day2 + Duration::minutes(6)] {
+            db.insert_notification_log_at(id, Some("Discord.exe"), at).unwrap();
+        }
+        db.insert_notification_log_at(other, Some("Steam.exe"), day2).unwrap();
+
+        let stats = db.schedule_stats(id, day1, day2 + Duration::hours(1)).unwrap();
+        assert_eq!(
+            stats,
+            ScheduleStats {
+                schedule_id: id,
+                average_compliance_rate: Some(0.375),
+                notifications_sent: 2,
+                most_common_distraction: Some("Discord.exe".to_string()),
+                violations_per_active_day: 2.0,
+            }
+        );
+
+        let empty = db.schedule_stats(id, day1 + Duration::days(7), day2 + Duration::days(7)).unwrap();
+        assert_eq!(empty.average_compliance_rate, None);
+        assert_eq!(empty.most_common_distraction, None);
+        assert_eq!(empty.violations_per_active_day, 0.0);
+    }
+
+    #[test]
+    fn test_focus_score() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+        let nine = Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap();
+        for (app, offset, minutes) in [("Code.exe", 0, 60), ("Slack.exe", 60, 20), ("Code.exe", 80, 40)] {
+            db.insert_session(&session(app, nine + Duration::minutes(offset), minutes)).unwrap();
+        }
+        db.insert_session(&Session {
+            is_idle: true,
+            ..session("Idle", nine + Duration::minutes(120), 30)
+        })
+        .unwrap();
+        let schedule = db.insert_schedule(&Schedule::default()).unwrap();
+        for (minute, compliant) in [(0, true), (20, true), (40, true), (70, false)] {
+            db.insert_compliance_log_at(schedule, compliant, None, nine + Duration::minutes(minute)).unwrap();
+        }
+
+        // Session length: 40m avg -> 1; switching: 2 switches in 2h -> 1 / (1 + 1/4) = 0.8;
+        // compliance: 3/4; activity: 120m / 150m = 0.8
+        // 100 * (0.3 * 1 + 0.25 * 0.8 + 0.25 * 0.75 + 0.2 * 0.8) = 84.75
+        assert_eq!(db.focus_score(nine.date_naive()).unwrap(), 84.75);
+        assert_eq!(db.focus_score(nine.date_naive().succ_opt().unwrap()).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_time_to_first_focus() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+        let eight = Utc.with_ymd_and_hms(2024, 3, 4, 8, 0, 0).unwrap();
+        db.insert_session(&Session {
+            is_idle: true,
+            ..session("Idle", eight - Duration::minutes(30), 30)
+        })
+        .unwrap();
+        for (app, offset, minutes) in [("Slack.exe", 0, 5), ("firefox.exe", 5, 10), ("Code.exe", 15, 50), ("Code.exe", 70, 90)] {
+            db.insert_session(&session(app, eight + Duration::minutes(offset), minutes)).unwrap();
+        }
+
+        let day = eight.date_naive();
+        assert_eq!(db.time_to_first_focus(day, 45 * 60).unwrap(), Some(15 * 60));
+        assert_eq!(db.time_to_first_focus(day, 60).unwrap(), Some(0));
+        assert_eq!(db.time_to_first_focus(day, 2 * 3_600).unwrap(), None);
+        assert_eq!(db.time_to_first_focus(day.succ_opt().unwrap(), 60).unwrap(), None);
+    }
+
+    #[test]
+    fn test_idle_breakdown_groups_by_reason() {
+        let db = Database::in_memory();
+        let t0 = Utc.with_ymd_and_hms(2024, 3, 4, 12, 0, 0).unwrap();
+        let idle = |start: DateTime<Utc>, minutes: i64, reason: Option<&str>| {
+            let id = db
+                .insert_session(&Session {
+                    is_idle: true,
+                    ..session("Idle", start, minutes)
+                })
+                .unwrap();
+            if let Some(reason) = reason {
+                db.set_idle_reason(id, reason).unwrap();
+            }
+        };
+        idle(t0, 45, Some("no-input"));
+        idle(t0 + Duration::hours(1), 15, Some("no-input"));
+        idle(t0 + Duration::hours(2), 40, Some("locked"));
+        idle(t0 + Duration::hours(3), 20, None);
+        db.insert_session(&session("Code.exe", t0 + Duration::hours(4), 90)).unwrap();
+
+        let breakdown = db.idle_breakdown(t0, t0 + Duration::hours(5)).unwrap();
+        assert_eq!(
+            breakdown,
+            vec![
+                ("no-input".to_string(), 3_600),
+                ("locked".to_string(), 2_400),
+                (UNKNOWN_IDLE_REASON.to_string(), 1_200),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deepest_focus_by_day() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+        let monday = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+        db.insert_session(&session("Slack.exe", monday, 20)).unwrap();
+        db.insert_session(&session("Code.exe", monday + Duration::hours(1), 95)).unwrap();
+        db.insert_session(&session("firefox.exe", monday + Duration::hours(3), 40)).unwrap();
+        let mut idle = session("Idle", monday + Duration::hours(5), 180);
+        idle.is_idle = true;
+        db.insert_session(&idle).unwrap();
+        // Tuesday: only idle time, so it is omitted
+        let mut tuesday_idle = session("Idle", monday + Duration::days(1), 60);
+        tuesday_idle.is_idle = true;
+        db.insert_session(&tuesday_idle).unwrap();
+        db.insert_session(&session("Figma.exe", monday + Duration::days(2), 30)).unwrap();
+
+        let deepest = db
+            .deepest_focus_by_day(monday - Duration::hours(9), monday + Duration::days(7))
+            .unwrap();
+        let summary: Vec<_> = deepest
+            .iter()
+            .map(|(day, s)| (day.to_string(), s.app_id.as_str(), s.duration_seconds))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("2024-01-01".to_string(), "Code.exe", Some(95 * 60)),
+                ("2024-01-03".to_string(), "Figma.exe", Some(30 * 60)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stats_for_weekday_uses_local_day() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("Europe/Berlin").unwrap());
+        // Monday 2024-01-01
+        let monday = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+        db.insert_session(&session("Code.exe", monday, 60)).unwrap();
+        db.insert_session(&session("Slack.exe", monday + Duration::days(7), 30)).unwrap();
+        // Sunday 23:30 UTC is already Monday in Berlin
+        db.insert_session(&session("Code.exe", monday - Duration::minutes(570), 10)).unwrap();
+        db.insert_session(&session("Code.exe", monday + Duration::days(1), 45)).unwrap();
+        let mut idle = session("Idle", monday + Duration::hours(2), 20);
+        idle.is_idle = true;
+        db.insert_session(&idle).unwrap();
+
+        let range = (monday - Duration::days(1), monday + Duration::days(14));
+        assert_eq!(db.stats_for_weekday(Weekday::Mon, range.0, range.1).unwrap(), (3, 100 * 60));
+        assert_eq!(db.stats_for_weekday(Weekday::Tue, range.0, range.1).unwrap(), (1, 45 * 60));
+        assert_eq!(db.stats_for_weekday(Weekday::Sun, range.0, range.1).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn test_rolling_daily_average_counts_empty_days() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+        // Jan 1: 2h, Jan 2: nothing, Jan 3: 1h + 30m, Jan 4: 30m
+        db.insert_session(&session("Code.exe", base, 120)).unwrap();
+        db.insert_session(&session("Code.exe", base + Duration::days(2), 60)).unwrap();
+        db.insert_session(&session("Slack.exe", base + Duration::days(2) + Duration::hours(2), 30)).unwrap();
+        db.insert_session(&session("Code.exe", base + Duration::days(3), 30)).unwrap();
+        let mut idle = session("Idle", base + Duration::days(1), 300);
+        idle.is_idle = true;
+        db.insert_session(&idle).unwrap();
+
+        let jan = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        assert_eq!(db.rolling_daily_average(3, jan(3)).unwrap(), (120.0 + 90.0) * 60.0 / 3.0);
+        assert_eq!(db.rolling_daily_average(4, jan(4)).unwrap(), 240.0 * 60.0 / 4.0);
+        assert_eq!(db.rolling_daily_average(1, jan(2)).unwrap(), 0.0);
+        assert_eq!(db.rolling_daily_average(0, jan(4)).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_active_day_streak_stops_at_gap() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+        // Active Jan 1-2 and Jan 4-7; Jan 3 only has idle time
+        for day in [0, 1, 3, 4, 5, 6] {
+            db.insert_session(&session("Code.exe", base + Duration::days(day), 30)).unwrap();
+        }
+        let mut idle = session("Idle", base + Duration::days(2), 60);
+        idle.is_idle = true;
+        db.insert_session(&idle).unwrap();
+
+        let jan = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        assert_eq!(db.active_day_streak(jan(7)).unwrap(), 4);
+        assert_eq!(db.active_day_streak(jan(2)).unwrap(), 2);
+        assert_eq!(db.active_day_streak(jan(3)).unwrap(), 0);
+        assert_eq!(db.active_day_streak(jan(8)).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_apps_during_schedule_clips_to_windows() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+        let schedule_id = db
+            .insert_schedule(&Schedule {
+                name: "Deep Work".to_string(),
+                start_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                end_time: NaiveTime::from_hms_opt(11, 0, 0),
+                days: vec![Weekday::Mon],
+                ..Schedule::default()
+            })
+            .unwrap();
+
+        // Monday 2024-01-01
+        let monday = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        // 08:30-09:30: only the last half hour counts
+        db.insert_session(&session("Code.exe", monday + Duration::minutes(510), 60)).unwrap();
+        db.insert_session(&session("firefox.exe", monday + Duration::minutes(600), 20)).unwrap();
+        // After the window, and on Tuesday
+        db.insert_session(&session("Steam.exe", monday + Duration::hours(12), 60)).unwrap();
+        db.insert_session(&session("Code.exe", monday + Duration::days(1) + Duration::hours(9), 60)).unwrap();
+
+        let totals = db.apps_during_schedule(schedule_id, monday, monday + Duration::days(7)).unwrap();
+        assert_eq!(totals, vec![("Code.exe".to_string(), 30 * 60), ("firefox.exe".to_string(), 20 * 60)]);
+        assert!(db.apps_during_schedule(999, monday, monday + Duration::days(7)).is_err());
+    }
+
+    #[test]
+    fn test_idle_during_schedule_counts_in_window_only() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+        let schedule_id = db
+            .insert_schedule(&Schedule {
+                name: "Deep Work".to_string(),
+                start_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                end_time: NaiveTime::from_hms_opt(11, 0, 0),
+                days: vec![Weekday::Mon],
+                ..Schedule::default()
+            })
+            .unwrap();
+
+        // Monday 2024-01-01
+        let monday = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let idle = |start: DateTime<Utc>, minutes: i64| Session::idle(start, Some(start + Duration::minutes(minutes)));
+        // 08:50-09:10: ten minutes inside
+        db.insert_session(&idle(monday + Duration::minutes(530), 20)).unwrap();
+        db.insert_session(&idle(monday + Duration::minutes(570), 15)).unwrap();
+        // Active time in the window, idle outside it and on Tuesday
+        db.insert_session(&session("Code.exe", monday + Duration::hours(9), 30)).unwrap();
+        db.insert_session(&idle(monday + Duration::hours(13), 60)).unwrap();
+        db.insert_session(&idle(monday + Duration::days(1) + Duration::hours(9), 60)).unwrap();
+
+        let idle_secs = db.idle_during_schedule(schedule_id, monday, monday + Duration::days(7)).unwrap();
+        assert_eq!(idle_secs, 25 * 60);
+        assert!(db.idle_during_schedule(999, monday, monday + Duration::days(7)).is_err());
+    }
+
+    #[test]
+    fn test_day_schedule_report_counts_in_window_time() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let schedule_id = db
+            .insert_schedule(&Schedule {
+                name: "Deep Work".to_string(),
+                start_time: time(9, 0),
+                end_time: Some(time(11, 0)),
+                days: vec![Weekday::Mon],
+                expected_apps: vec!["code".to_string()],
+                windows: vec![crate::models::ScheduleWindow {
+                    start_time: time(10, 0),
+                    end_time: Some(time(11, 0)),
+                    expected_apps: vec!["code".to_string(), "slack".to_string()],
+                }],
+                ..Schedule::default()
+            })
+            .unwrap();
+        db.insert_schedule(&Schedule { name: "Weekend".to_string(), days: vec![Weekday::Sat], ..Schedule::default() })
+            .unwrap();
+
+        // Monday 2024-01-01
+        let monday = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let at = |h: i64, m: i64| monday + Duration::hours(h) + Duration::minutes(m);
+        // Half of this falls before the schedule
+        db.insert_session(&session("Code.exe", at(8, 30), 60)).unwrap();
+        // Chat is off-plan until 10:00, so half of this is compliant
+        db.insert_session(&session("Slack.exe", at(9, 50), 20)).unwrap();
+        db.insert_session(&Session { is_idle: true, ..session("Idle", at(10, 10), 10) }).unwrap();
+        db.insert_session(&session("firefox.exe", at(10, 20), 20)).unwrap();
+        db.insert_session(&session("Code.exe", at(10, 40), 30)).unwrap();
+
+        let report = db.day_schedule_report(monday.date_naive()).unwrap();
+        assert_eq!(report.len(), 1);
+        let result = &report[0];
+        assert_eq!(result.schedule_id, schedule_id);
+        assert_eq!(result.schedule_name, "Deep Work");
+        // 11:00 is the schedule's last second, so the window closes at 11:00:01
+        assert_eq!(result.in_window_seconds, (30 + 20 + 20 + 20) * 60 + 1);
+        assert_eq!(result.compliant_seconds, (30 + 10 + 20) * 60 + 1);
+
+        assert!(db.day_schedule_report(monday.date_naive() + Duration::days(1)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compliant_active_seconds_excludes_off_plan_time() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        db.insert_schedule(&Schedule {
+            name: "Deep Work".to_string(),
+            start_time: time(9, 0),
+            end_time: Some(time(11, 0)),
+            days: vec![Weekday::Mon],
+            expected_apps: vec!["code".to_string()],
+            // Chat is fine in the second hour
+            windows: vec![crate::models::ScheduleWindow {
+                start_time: time(10, 0),
+                end_time: Some(time(11, 0)),
+                expected_apps: vec!["code".to_string(), "slack".to_string()],
+            }],
+            ..Schedule::default()
+        })
+        .unwrap();
+        db.insert_schedule(&Schedule {
+            name: "Browsing".to_string(),
+            start_time: time(0, 0),
+            end_time: None,
+            days: vec![Weekday::Mon],
+            expected_apps: vec!["firefox".to_string()],
+            enabled: false,
+            ..Schedule::default()
+        })
+        .unwrap();
+
+        // Monday 2024-01-01
+        let monday = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let at = |h: i64, m: i64| monday + Duration::hours(h) + Duration::minutes(m);
+        // 30 minutes before the schedule don't count
+        db.insert_session(&session("Code.exe", at(8, 30), 60)).unwrap();
+        // Chat before its window opens is off-plan
+        db.insert_session(&session("Slack.exe", at(9, 30), 15)).unwrap();
+        db.insert_session(&Session { is_idle: true, ..session("Idle", at(9, 45), 15) }).unwrap();
+        db.insert_session(&session("Slack.exe", at(10, 0), 20)).unwrap();
+        // Only a disabled schedule allows the browser
+        db.insert_session(&session("firefox.exe", at(10, 20), 20)).unwrap();
+        db.insert_session(&session("Code.exe", at(10, 40), 20)).unwrap();
+        db.insert_session(&session("Code.exe", at(14, 0), 60)).unwrap();
+
+        let secs = db.compliant_active_seconds(monday, monday + Duration::days(7)).unwrap();
+        assert_eq!(secs, (30 + 20 + 20) * 60);
+        assert_eq!(db.compliant_active_seconds(at(8, 0), at(9, 10)).unwrap(), 10 * 60);
+    }
+}
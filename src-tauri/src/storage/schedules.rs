@@ -1,65 +1,128 @@
-use crate::models::{ComplianceLog, Schedule};
+use crate::models::{ComplianceLog, ComplianceLogDto, MatchMode, Schedule, ScheduleWindow};
+use crate::scheduler::engine::ScheduleState;
 use crate::storage::db::Database;
-use chrono::{NaiveTime, Utc, Weekday};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc, Weekday};
 use rusqlite::params;
+use std::collections::{HashMap, HashSet};
+
+/// Store weekdays as comma-separated day numbers (Monday = 0)
+pub(crate) fn days_to_string(days: &[Weekday]) -> String {
+    days.iter()
+        .map(|d| d.num_days_from_monday().to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Parse weekdays stored by `days_to_string`, skipping invalid entries
+pub(crate) fn parse_days(days: &str) -> Vec<Weekday> {
+    days.split(',')
+        .filter_map(|s| s.parse::<u32>().ok())
+        .filter_map(|n| match n {
+            0 => Some(Weekday::Mon),
+            1 => Some(Weekday::Tue),
+            2 => Some(Weekday::Wed),
+            3 => Some(Weekday::Thu),
+            4 => Some(Weekday::Fri),
+            5 => Some(Weekday::Sat),
+            6 => Some(Weekday::Sun),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Store schedule windows as JSON, or NULL when there are none
+fn windows_to_json(windows: &[ScheduleWindow]) -> Result<Option<String>, rusqlite::Error> {
+    if windows.is_empty() {
+        return Ok(None);
+    }
+    serde_json::to_string(windows)
+        .map(Some)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
 
 impl Database {
-    /// Insert a new schedule
+    /// `name` if no schedule other than `id` uses it (case-insensitively), else
+    /// the first free of "name (2)", "name (3)", ..., so schedules stay
+    /// distinguishable in the UI and notifications
+    fn unique_schedule_name(&self, name: &str, id: Option<i64>) -> Result<String, rusqlite::Error> {
+        let mut stmt = self.connection().prepare("SELECT name FROM schedules WHERE id IS NOT ?1")?;
+        let taken = stmt
+            .query_map(params![id], |row| row.get::<_, String>(0))?
+            .map(|name| name.map(|name| name.to_lowercase()))
+            .collect::<Result<HashSet<_>, _>>()?;
+
+        let mut candidate = name.to_string();
+        let mut suffix = 2;
+        while taken.contains(&candidate.to_lowercase()) {
+            candidate = format!("{} ({})", name, suffix);
+            suffix += 1;
+        }
+        Ok(candidate)
+    }
+
+    /// Insert a new schedule. A name already in use gets a numeric suffix.
     pub fn insert_schedule(&self, schedule: &Schedule) -> Result<i64, rusqlite::Error> {
-        let days_str = schedule
-            .days
-            .iter()
-            .map(|d| d.num_days_from_monday().to_string())
-            .collect::<Vec<_>>()
-            .join(",");
+        let name = self.unique_schedule_name(&schedule.name, None)?;
+        let days_str = days_to_string(&schedule.days);
         let apps_str = schedule.expected_apps.join(",");
+        let windows = windows_to_json(&schedule.windows)?;
 
         self.connection().execute(
             r#"
-            INSERT INTO schedules (name, start_time, end_time, days, expected_apps, check_interval_secs, grace_period_secs, enabled)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            INSERT INTO schedules (name, start_time, end_time, days, expected_apps, check_interval_secs, grace_period_secs, enabled, match_mode, tag, windows, active_until, end_notified, webhook_url)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
             "#,
             params![
-                schedule.name,
+                name,
                 schedule.start_time.format("%H:%M").to_string(),
-                schedule.end_time.format("%H:%M").to_string(),
+                schedule.end_time.map(|t| t.format("%H:%M").to_string()),
                 days_str,
                 apps_str,
                 schedule.check_interval_secs,
                 schedule.grace_period_secs,
-                schedule.enabled
+                schedule.enabled,
+                schedule.match_mode.as_str(),
+                schedule.tag,
+                windows,
+                schedule.active_until.map(|d| d.to_string()),
+                schedule.end_notified,
+                schedule.webhook_url
             ],
         )?;
 
         Ok(self.connection().last_insert_rowid())
     }
 
-    /// Update an existing schedule
+    /// Update an existing schedule. The end notice is re-armed if `active_until` changes.
+    /// Renaming to a name another schedule uses adds a numeric suffix.
     pub fn update_schedule(&self, schedule: &Schedule) -> Result<(), rusqlite::Error> {
-        let days_str = schedule
-            .days
-            .iter()
-            .map(|d| d.num_days_from_monday().to_string())
-            .collect::<Vec<_>>()
-            .join(",");
+        let name = self.unique_schedule_name(&schedule.name, schedule.id)?;
+        let days_str = days_to_string(&schedule.days);
         let apps_str = schedule.expected_apps.join(",");
+        let windows = windows_to_json(&schedule.windows)?;
 
         self.connection().execute(
             r#"
             UPDATE schedules 
             SET name = ?1, start_time = ?2, end_time = ?3, days = ?4, expected_apps = ?5, 
-                check_interval_secs = ?6, grace_period_secs = ?7, enabled = ?8
-            WHERE id = ?9
+                check_interval_secs = ?6, grace_period_secs = ?7, enabled = ?8, match_mode = ?9, tag = ?10, windows = ?11,
+                end_notified = end_notified AND active_until IS ?12, active_until = ?12, webhook_url = ?13
+            WHERE id = ?14
             "#,
             params![
-                schedule.name,
+                name,
                 schedule.start_time.format("%H:%M").to_string(),
-                schedule.end_time.format("%H:%M").to_string(),
+                schedule.end_time.map(|t| t.format("%H:%M").to_string()),
                 days_str,
                 apps_str,
                 schedule.check_interval_secs,
                 schedule.grace_period_secs,
                 schedule.enabled,
+                schedule.match_mode.as_str(),
+                schedule.tag,
+                windows,
+                schedule.active_until.map(|d| d.to_string()),
+                schedule.webhook_url,
                 schedule.id
             ],
         )?;
@@ -69,12 +132,13 @@ impl Database {
 
     /// Delete a schedule by ID
     pub fn delete_schedule(&self, id: i64) -> Result<(), rusqlite::Error> {
-        self.connection()
-            .execute("DELETE FROM schedules WHERE id = ?1", params![id])?;
-        // Also delete related compliance logs
-        self.connection()
-            .execute("DELETE FROM compliance_logs WHERE schedule_id = ?1", params![id])?;
-        Ok(())
+        // Delete related logs and engine state first (foreign keys)
+        let tx = self.connection().unchecked_transaction()?;
+        tx.execute("DELETE FROM compliance_logs WHERE schedule_id = ?1", params![id])?;
+        tx.execute("DELETE FROM notification_logs WHERE schedule_id = ?1", params![id])?;
+        tx.execute("DELETE FROM schedule_state WHERE schedule_id = ?1", params![id])?;
+        tx.execute("DELETE FROM schedules WHERE id = ?1", params![id])?;
+        tx.commit()
     }
 
     /// Toggle schedule enabled state
@@ -86,43 +150,56 @@ impl Database {
         Ok(())
     }
 
+    /// Disable a schedule whose date range has passed and record that its end
+    /// notice was sent
+    pub fn mark_schedule_ended(&self, id: i64) -> Result<(), rusqlite::Error> {
+        self.connection().execute(
+            "UPDATE schedules SET enabled = FALSE, end_notified = TRUE WHERE id = ?1",
+            params![id],
+        )?;
+        Ok(())
+    }
+
+    /// Enable or disable every schedule tagged `tag`. Returns how many changed.
+    pub fn set_enabled_by_tag(&self, tag: &str, enabled: bool) -> Result<usize, rusqlite::Error> {
+        self.connection().execute(
+            "UPDATE schedules SET enabled = ?1 WHERE tag = ?2 AND enabled != ?1",
+            params![enabled, tag],
+        )
+    }
+
     /// Get all schedules
     pub fn get_all_schedules(&self) -> Result<Vec<Schedule>, rusqlite::Error> {
         let mut stmt = self
             .connection()
-            .prepare("SELECT id, name, start_time, end_time, days, expected_apps, check_interval_secs, grace_period_secs, enabled FROM schedules")?;
+            .prepare("SELECT id, name, start_time, end_time, days, expected_apps, check_interval_secs, grace_period_secs, enabled, match_mode, tag, windows, active_until, end_notified, webhook_url FROM schedules")?;
 
         let schedules = stmt
             .query_map([], |row| {
                 let id: i64 = row.get(0)?;
                 let name: String = row.get(1)?;
                 let start_time_str: String = row.get(2)?;
-                let end_time_str: String = row.get(3)?;
+                let end_time_str: Option<String> = row.get(3)?;
                 let days_str: String = row.get(4)?;
                 let apps_str: String = row.get(5)?;
                 let check_interval_secs: u32 = row.get(6)?;
                 let grace_period_secs: u32 = row.get(7)?;
                 let enabled: bool = row.get(8)?;
+                let match_mode: String = row.get(9)?;
+                let tag: Option<String> = row.get(10)?;
+                let windows_json: Option<String> = row.get(11)?;
+                let active_until: Option<String> = row.get(12)?;
+                let end_notified: bool = row.get(13)?;
+                let webhook_url: Option<String> = row.get(14)?;
 
                 let start_time = NaiveTime::parse_from_str(&start_time_str, "%H:%M")
                     .unwrap_or_else(|_| NaiveTime::from_hms_opt(9, 0, 0).unwrap());
-                let end_time = NaiveTime::parse_from_str(&end_time_str, "%H:%M")
-                    .unwrap_or_else(|_| NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+                let end_time = end_time_str.map(|s| {
+                    NaiveTime::parse_from_str(&s, "%H:%M")
+                        .unwrap_or_else(|_| NaiveTime::from_hms_opt(17, 0, 0).unwrap())
+                });
 
-                let days: Vec<Weekday> = days_str
-                    .split(',')
-                    .filter_map(|s| s.parse::<u32>().ok())
-                    .filter_map(|n| match n {
-                        0 => Some(Weekday::Mon),
-                        1 => Some(Weekday::Tue),
-                        2 => Some(Weekday::Wed),
-                        3 => Some(Weekday::Thu),
-                        4 => Some(Weekday::Fri),
-                        5 => Some(Weekday::Sat),
-                        6 => Some(Weekday::Sun),
-                        _ => None,
-                    })
-                    .collect();
+                let days = parse_days(&days_str);
 
                 let expected_apps: Vec<String> = apps_str
                     .split(',')
@@ -130,6 +207,10 @@ impl Database {
                     .map(|s| s.to_string())
                     .collect();
 
+                let windows = windows_json
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                    .unwrap_or_default();
+
                 Ok(Schedule {
                     id: Some(id),
                     name,
@@ -140,6 +221,12 @@ impl Database {
                     check_interval_secs,
                     grace_period_secs,
                     enabled,
+                    match_mode: MatchMode::from_stored(&match_mode),
+                    tag,
+                    windows,
+                    active_until: active_until.and_then(|d| d.parse::<NaiveDate>().ok()),
+                    end_notified,
+                    webhook_url,
                 })
             })?
             .filter_map(|r| r.ok())
@@ -161,7 +248,18 @@ impl Database {
         is_compliant: bool,
         current_app: Option<&str>,
     ) -> Result<i64, rusqlite::Error> {
-        let timestamp = Utc::now().timestamp();
+        self.insert_compliance_log_at(schedule_id, is_compliant, current_app, Utc::now())
+    }
+
+    /// Insert a compliance log entry recorded at `at`
+    pub fn insert_compliance_log_at(
+        &self,
+        schedule_id: i64,
+        is_compliant: bool,
+        current_app: Option<&str>,
+        at: DateTime<Utc>,
+    ) -> Result<i64, rusqlite::Error> {
+        let timestamp = at.timestamp();
 
         self.connection().execute(
             r#"
@@ -174,6 +272,35 @@ impl Database {
         Ok(self.connection().last_insert_rowid())
     }
 
+    /// Record that a non-compliance notification was sent for a schedule at `at`
+    pub fn insert_notification_log_at(
+        &self,
+        schedule_id: i64,
+        current_app: Option<&str>,
+        at: DateTime<Utc>,
+    ) -> Result<i64, rusqlite::Error> {
+        self.connection().execute(
+            "INSERT INTO notification_logs (schedule_id, timestamp, current_app) VALUES (?1, ?2, ?3)",
+            params![schedule_id, at.timestamp(), current_app],
+        )?;
+        Ok(self.connection().last_insert_rowid())
+    }
+
+    /// Number of notifications sent for a schedule between `start` and `end`
+    pub fn notification_count(
+        &self,
+        schedule_id: i64,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<i64, rusqlite::Error> {
+        self.connection().query_row(
+            "SELECT COUNT(*) FROM notification_logs
+             WHERE schedule_id = ?1 AND timestamp >= ?2 AND timestamp <= ?3",
+            params![schedule_id, start.timestamp(), end.timestamp()],
+            |row| row.get(0),
+        )
+    }
+
     /// Get compliance logs for a schedule
     pub fn get_compliance_logs(&self, schedule_id: i64) -> Result<Vec<ComplianceLog>, rusqlite::Error> {
         let mut stmt = self.connection().prepare(
@@ -202,4 +329,264 @@ impl Database {
 
         Ok(logs)
     }
+
+    /// Compliance logs of every schedule between `start` and `end`, newest first
+    pub fn get_all_compliance_logs(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<ComplianceLogDto>, rusqlite::Error> {
+        let mut stmt = self.connection().prepare(
+            "SELECT l.id, l.schedule_id, l.timestamp, l.is_compliant, l.current_app, s.name
+             FROM compliance_logs l
+             JOIN schedules s ON s.id = l.schedule_id
+             WHERE l.timestamp >= ?1 AND l.timestamp <= ?2
+             ORDER BY l.timestamp DESC, l.id DESC
+             LIMIT ?3",
+        )?;
+
+        let logs = stmt.query_map(params![start.timestamp(), end.timestamp(), limit], |row| {
+            let timestamp: i64 = row.get(2)?;
+            Ok(ComplianceLogDto {
+                log: ComplianceLog {
+                    id: Some(row.get(0)?),
+                    schedule_id: row.get(1)?,
+                    timestamp: DateTime::from_timestamp(timestamp, 0).unwrap_or_else(Utc::now),
+                    is_compliant: row.get(3)?,
+                    current_app: row.get(4)?,
+                },
+                schedule_name: row.get(5)?,
+            })
+        })?;
+
+        logs.collect()
+    }
+
+    /// Persist the scheduler engine state for a schedule
+    pub fn save_schedule_state(&self, schedule_id: i64, state: &ScheduleState) -> Result<(), rusqlite::Error> {
+        self.connection().execute(
+            r#"
+            INSERT INTO schedule_state (schedule_id, grace_started, last_notification, consecutive_non_compliant)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(schedule_id) DO UPDATE SET
+                grace_started = excluded.grace_started,
+                last_notification = excluded.last_notification,
+                consecutive_non_compliant = excluded.consecutive_non_compliant
+            "#,
+            params![
+                schedule_id,
+                state.grace_started.map(|t| t.timestamp()),
+                state.last_notification.map(|t| t.timestamp()),
+                state.consecutive_non_compliant
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Load all persisted scheduler engine states, keyed by schedule ID
+    pub fn load_schedule_states(&self) -> Result<HashMap<i64, ScheduleState>, rusqlite::Error> {
+        let mut stmt = self.connection().prepare(
+            "SELECT schedule_id, grace_started, last_notification, consecutive_non_compliant FROM schedule_state",
+        )?;
+
+        let states = stmt
+            .query_map([], |row| {
+                let schedule_id: i64 = row.get(0)?;
+                let grace_started: Option<i64> = row.get(1)?;
+                let last_notification: Option<i64> = row.get(2)?;
+                let consecutive_non_compliant: u32 = row.get(3)?;
+
+                Ok((
+                    schedule_id,
+                    ScheduleState {
+                        grace_started: grace_started.and_then(|ts| DateTime::from_timestamp(ts, 0)),
+                        last_notification: last_notification.and_then(|ts| DateTime::from_timestamp(ts, 0)),
+                        consecutive_non_compliant,
+                        ..ScheduleState::default()
+                    },
+                ))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(states)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::SchedulerEngine;
+
+    #[test]
+    fn test_grace_state_survives_restart() {
+        let db = Database::in_memory();
+        let mut schedule = Schedule {
+            name: "Focus".to_string(),
+            grace_period_secs: 60,
+            ..Schedule::default()
+        };
+        let id = db.insert_schedule(&schedule).unwrap();
+        schedule.id = Some(id);
+
+        let engine = SchedulerEngine::new();
+        engine.start_grace(id);
+        engine.mark_notified(id);
+        for (schedule_id, state) in engine.take_dirty_states() {
+            db.save_schedule_state(schedule_id, &state).unwrap();
+        }
+        let before = engine.status(&schedule);
+
+        // Simulate a restart with a fresh engine
+        let restarted = SchedulerEngine::new();
+        restarted.restore_states(db.load_schedule_states().unwrap());
+        restarted.start_grace(id);
+
+        let after = restarted.status_at(&schedule, Utc::now() + chrono::Duration::seconds(30));
+        assert!(after.in_grace);
+        assert!(after.grace_remaining_secs.unwrap() <= 30);
+        assert_eq!(after.consecutive_non_compliant, before.consecutive_non_compliant);
+        // Grace resumed rather than restarting, so nothing new needs saving
+        assert!(restarted.take_dirty_states().is_empty());
+    }
+
+    #[test]
+    fn test_delete_schedule_with_notification_logs() {
+        let db = Database::in_memory();
+        let schedule = Schedule { name: "Focus".to_string(), ..Schedule::default() };
+        let id = db.insert_schedule(&schedule).unwrap();
+        db.insert_compliance_log(id, false, Some("game.exe")).unwrap();
+        db.insert_notification_log_at(id, Some("game.exe"), Utc::now()).unwrap();
+
+        db.delete_schedule(id).unwrap();
+        assert!(db.get_all_schedules().unwrap().is_empty());
+        let start = Utc::now() - chrono::Duration::days(1);
+        assert_eq!(db.notification_count(id, start, Utc::now()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_duplicate_schedule_names_get_suffixed() {
+        let db = Database::in_memory();
+        let named = |name: &str| Schedule { name: name.to_string(), ..Schedule::default() };
+        let first = db.insert_schedule(&named("Focus")).unwrap();
+        let second = db.insert_schedule(&named("focus")).unwrap();
+        db.insert_schedule(&named("Focus")).unwrap();
+        let reading = db.insert_schedule(&named("Reading")).unwrap();
+        // Keeping its own name is not a clash; taking another's is
+        db.update_schedule(&Schedule { id: Some(first), ..named("Focus") }).unwrap();
+        db.update_schedule(&Schedule { id: Some(reading), ..named("FOCUS (2)") }).unwrap();
+
+        let mut names: Vec<_> = db.get_all_schedules().unwrap().into_iter().map(|s| (s.id.unwrap(), s.name)).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                (first, "Focus".to_string()),
+                (second, "focus (2)".to_string()),
+                (second + 1, "Focus (3)".to_string()),
+                (reading, "FOCUS (2) (2)".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_all_compliance_logs_interleaved() {
+        let db = Database::in_memory();
+        let focus = db
+            .insert_schedule(&Schedule { name: "Focus".to_string(), ..Schedule::default() })
+            .unwrap();
+        let reading = db
+            .insert_schedule(&Schedule { name: "Reading".to_string(), ..Schedule::default() })
+            .unwrap();
+        let at = |secs: i64| DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap();
+        db.insert_compliance_log_at(focus, true, Some("Code.exe"), at(0)).unwrap();
+        db.insert_compliance_log_at(reading, false, Some("Steam.exe"), at(60)).unwrap();
+        db.insert_compliance_log_at(focus, false, Some("Slack.exe"), at(120)).unwrap();
+        db.insert_compliance_log_at(reading, true, Some("Kindle.exe"), at(9_000)).unwrap();
+
+        let logs = db.get_all_compliance_logs(at(0), at(600), 50).unwrap();
+        let summary: Vec<_> = logs
+            .iter()
+            .map(|l| (l.schedule_name.as_str(), l.log.current_app.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("Focus", "Slack.exe"), ("Reading", "Steam.exe"), ("Focus", "Code.exe")]
+        );
+        assert_eq!(db.get_all_compliance_logs(at(0), at(600), 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_schedule_windows_round_trip() {
+        let db = Database::in_memory();
+        let window = ScheduleWindow {
+            start_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end_time: None,
+            expected_apps: vec!["chrome".to_string()],
+        };
+        db.insert_schedule(&Schedule { windows: vec![window.clone()], ..Schedule::default() })
+            .unwrap();
+        db.insert_schedule(&Schedule::default()).unwrap();
+
+        let windows: Vec<_> = db.get_all_schedules().unwrap().into_iter().map(|s| s.windows).collect();
+        assert_eq!(windows, vec![vec![window], Vec::new()]);
+    }
+
+    #[test]
+    fn test_changing_active_until_rearms_end_notice() {
+        let db = Database::in_memory();
+        let until = NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+        let id = db
+            .insert_schedule(&Schedule { active_until: Some(until), ..Schedule::default() })
+            .unwrap();
+        db.mark_schedule_ended(id).unwrap();
+        let ended = db.get_all_schedules().unwrap().remove(0);
+        assert!(!ended.enabled && ended.end_notified);
+        assert_eq!(ended.active_until, Some(until));
+
+        // Saving without date changes keeps the flag; a new end date clears it
+        db.update_schedule(&Schedule { end_notified: false, ..ended.clone() }).unwrap();
+        assert!(db.get_all_schedules().unwrap()[0].end_notified);
+        db.update_schedule(&Schedule { active_until: until.succ_opt(), ..ended }).unwrap();
+        assert!(!db.get_all_schedules().unwrap()[0].end_notified);
+    }
+
+    #[test]
+    fn test_set_enabled_by_tag_flips_only_tagged() {
+        let db = Database::in_memory();
+        let tagged = |name: &str, tag: Option<&str>, enabled: bool| {
+            db.insert_schedule(&Schedule {
+                name: name.to_string(),
+                tag: tag.map(|t| t.to_string()),
+                enabled,
+                ..Schedule::default()
+            })
+            .unwrap()
+        };
+        tagged("Standup", Some("work"), true);
+        tagged("Deep work", Some("work"), true);
+        tagged("Already off", Some("work"), false);
+        tagged("Reading", Some("home"), true);
+        tagged("Untagged", None, true);
+
+        assert_eq!(db.set_enabled_by_tag("work", false).unwrap(), 2);
+        let enabled: Vec<_> = db
+            .get_all_schedules()
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.name, s.enabled))
+            .collect();
+        assert_eq!(
+            enabled,
+            vec![
+                ("Standup".to_string(), false),
+                ("Deep work".to_string(), false),
+                ("Already off".to_string(), false),
+                ("Reading".to_string(), true),
+                ("Untagged".to_string(), true),
+            ]
+        );
+        assert_eq!(db.get_all_schedules().unwrap()[3].tag.as_deref(), Some("home"));
+    }
 }
@@ -1,7 +1,84 @@
-use rusqlite::{params, OptionalExtension};
+use rusqlite::{params, OptionalExtension, Row};
 use crate::models::Session;
-use crate::storage::db::Database;
-use chrono::{DateTime, Utc, TimeZone};
+use crate::storage::db::{Database, DbError};
+use crate::timezone::{DayAttribution, ReportTimezone};
+use chrono::{DateTime, NaiveDate, Utc, TimeZone};
+use std::collections::HashMap;
+
+/// First and last time an app was seen
+pub type UsageBounds = (DateTime<Utc>, DateTime<Utc>);
+
+/// Largest gap (seconds) between two sessions of the same app that still counts as adjacent
+const MERGE_GAP_TOLERANCE_SECS: i64 = 1;
+
+/// Columns read by `session_from_row`, in order
+pub(crate) const SESSION_COLUMNS: &str =
+    "id, app_id, app_name, start_time, end_time, duration_seconds, is_idle, manual, process_path, interruptions, start_time_ms, end_time_ms, input_rate";
+
+/// Millisecond part of a timestamp, stored next to its whole seconds
+fn subsec_millis(time: DateTime<Utc>) -> u32 {
+    // Leap seconds report 1000+
+    time.timestamp_subsec_millis().min(999)
+}
+
+/// Timestamp from whole seconds and a millisecond part
+fn from_millis_parts(secs: i64, millis: u32) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(secs, millis.min(999) * 1_000_000).single()
+}
+
+/// Build a `Session` from a row selected with `SESSION_COLUMNS`
+pub(crate) fn session_from_row(row: &Row) -> Result<Session, rusqlite::Error> {
+    let start_time: i64 = row.get(3)?;
+    let end_time: Option<i64> = row.get(4)?;
+    let start_ms: u32 = row.get(10)?;
+    let end_ms: u32 = row.get(11)?;
+
+    Ok(Session {
+        id: Some(row.get(0)?),
+        app_id: row.get(1)?,
+        app_name: row.get(2)?,
+        start_time: from_millis_parts(start_time, start_ms).unwrap_or_else(Utc::now),
+        end_time: end_time.and_then(|ts| from_millis_parts(ts, end_ms)),
+        duration_seconds: row.get(5)?,
+        is_idle: row.get(6)?,
+        manual: row.get(7)?,
+        process_path: row.get(8)?,
+        interruptions: row.get(9)?,
+        input_rate: row.get(12)?,
+    })
+}
+
+/// Assign a session to the local day(s) that own it. `Split` yields one piece per
+/// day the session touches, with start, end and duration clipped to that day.
+pub fn attribute_to_days(session: &Session, tz: ReportTimezone, mode: DayAttribution) -> Vec<(NaiveDate, Session)> {
+    let start_day = tz.to_local(session.start_time).date();
+    let end = session.end_time.unwrap_or(session.start_time);
+    let end_day = tz.to_local(end).date();
+
+    match mode {
+        DayAttribution::StartDay => vec![(start_day, session.clone())],
+        DayAttribution::EndDay => vec![(end_day, session.clone())],
+        DayAttribution::Split if start_day == end_day => vec![(start_day, session.clone())],
+        DayAttribution::Split => start_day
+            .iter_days()
+            .take_while(|day| *day <= end_day)
+            .filter_map(|day| {
+                let (day_start, day_end) = tz.day_bounds(day);
+                let piece_start = session.start_time.max(day_start);
+                let piece_end = end.min(day_end + chrono::Duration::seconds(1));
+                (piece_end > piece_start).then(|| {
+                    let piece = Session {
+                        start_time: piece_start,
+                        end_time: Some(piece_end),
+                        duration_seconds: Some((piece_end - piece_start).num_seconds()),
+                        ..session.clone()
+                    };
+                    (day, piece)
+                })
+            })
+            .collect(),
+    }
+}
 
 /// Session storage operations
 impl Database {
@@ -11,8 +88,8 @@ impl Database {
         let end_ts = session.end_time.map(|t| t.timestamp());
         
         self.connection().execute(
-            "INSERT INTO sessions (app_id, app_name, start_time, end_time, duration_seconds, is_idle, is_pending)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            "INSERT INTO sessions (app_id, app_name, start_time, end_time, duration_seconds, is_idle, is_pending, manual, process_path, interruptions, start_time_ms, end_time_ms, input_rate)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
             params![
                 session.app_id,
                 session.app_name,
@@ -20,13 +97,151 @@ impl Database {
                 end_ts,
                 session.duration_seconds,
                 session.is_idle,
-                false // Mark as not pending since it's complete
+                false, // Mark as not pending since it's complete
+                session.manual,
+                session.process_path,
+                session.interruptions,
+                subsec_millis(session.start_time),
+                session.end_time.map_or(0, subsec_millis),
+                session.input_rate
             ],
         )?;
         
         Ok(self.connection().last_insert_rowid())
     }
 
+    /// Insert an unfinished session marked as pending, to be closed by `close_pending_sessions`
+    pub fn insert_pending_session(&self, session: &Session) -> Result<i64, rusqlite::Error> {
+        self.connection().execute(
+            "INSERT INTO sessions (app_id, app_name, start_time, end_time, duration_seconds, is_idle, is_pending, manual, process_path, interruptions, start_time_ms, input_rate)
+             VALUES (?1, ?2, ?3, NULL, NULL, ?4, TRUE, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                session.app_id,
+                session.app_name,
+                session.start_time.timestamp(),
+                session.is_idle,
+                session.manual,
+                session.process_path,
+                session.interruptions,
+                subsec_millis(session.start_time),
+                session.input_rate
+            ],
+        )?;
+
+        Ok(self.connection().last_insert_rowid())
+    }
+
+    /// Insert a completed session, extending the most recent stored session instead
+    /// if it belongs to the same app (window title and executable path) and ends where this one starts.
+    /// Idle sessions are never merged, so chunks capped by the sessionizer stay separate.
+    /// Returns the row ID and whether a merge happened.
+    pub fn insert_or_merge_session(&self, session: &Session) -> Result<(i64, bool), rusqlite::Error> {
+        let last = self
+            .connection()
+            .query_row(
+                "SELECT id, app_id, app_name, end_time, is_idle, manual, process_path
+                 FROM sessions
+                 WHERE is_pending = FALSE
+                 ORDER BY start_time DESC, start_time_ms DESC, id DESC
+                 LIMIT 1",
+                [],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<i64>>(3)?,
+                        row.get::<_, bool>(4)?,
+                        row.get::<_, bool>(5)?,
+                        row.get::<_, Option<String>>(6)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        if let (Some((id, app_id, app_name, Some(last_end), is_idle, manual, process_path)), Some(end)) =
+            (last, session.end_time)
+        {
+            let gap = session.start_time.timestamp() - last_end;
+            let adjacent = (0..=MERGE_GAP_TOLERANCE_SECS).contains(&gap);
+            if adjacent
+                && app_id == session.app_id
+                && app_name == session.app_name
+                && process_path == session.process_path
+                && !is_idle
+                && !session.is_idle
+                && !manual
+                && !session.manual
+            {
+                // Durations are summed rather than taken from the wall-clock span, which
+                // would add back soft-idle time. The merged input rate is the
+                // duration-weighted mean of both parts.
+                self.connection().execute(
+                    "UPDATE sessions SET end_time = ?1, end_time_ms = ?2, duration_seconds = duration_seconds + ?3,
+                         interruptions = interruptions + ?4,
+                         input_rate = CASE
+                             WHEN ?6 IS NULL THEN input_rate
+                             WHEN input_rate IS NULL THEN ?6
+                             WHEN duration_seconds + ?3 <= 0 THEN (input_rate + ?6) / 2
+                             ELSE (input_rate * duration_seconds + ?6 * ?3) / (duration_seconds + ?3)
+                         END
+                     WHERE id = ?5",
+                    params![
+                        end.timestamp(),
+                        subsec_millis(end),
+                        session.duration_seconds.unwrap_or(0),
+                        session.interruptions,
+                        id,
+                        session.input_rate
+                    ],
+                )?;
+                return Ok((id, true));
+            }
+        }
+
+        Ok((self.insert_session(session)?, false))
+    }
+
+    /// Record why an idle session was idle (e.g. "no-input", "locked", "screensaver")
+    pub fn set_idle_reason(&self, session_id: i64, reason: &str) -> Result<(), rusqlite::Error> {
+        self.connection().execute(
+            "UPDATE sessions SET idle_reason = ?1 WHERE id = ?2 AND is_idle = TRUE",
+            params![reason, session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Backfill a session the collector missed (e.g. while the app was off).
+    /// Manual sessions are never idle or pending.
+    pub fn insert_manual_session(
+        &self,
+        app_id: &str,
+        app_name: Option<&str>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<i64, DbError> {
+        if start >= end {
+            return Err(DbError::InvalidInput("start must be before end".to_string()));
+        }
+
+        let session = Session {
+            id: None,
+            app_id: app_id.to_string(),
+            app_name: app_name.map(|s| s.to_string()),
+            start_time: start,
+            end_time: Some(end),
+            duration_seconds: Some((end - start).num_seconds()),
+            is_idle: false,
+            manual: true,
+            process_path: None,
+            interruptions: 0,
+            input_rate: None,
+        };
+        let id = self.insert_session(&session)?;
+        self.update_totals_cache(&session)?;
+        Ok(id)
+    }
+
     /// Get sessions within a time range
     pub fn get_sessions_in_range(
         &self,
@@ -36,60 +251,115 @@ impl Database {
         let start_ts = start.timestamp();
         let end_ts = end.timestamp();
         
-        let mut stmt = self.connection().prepare(
-            "SELECT id, app_id, app_name, start_time, end_time, duration_seconds, is_idle
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT {}
              FROM sessions
              WHERE start_time >= ?1 AND start_time <= ?2
-             ORDER BY start_time ASC"
-        )?;
+             ORDER BY start_time ASC, start_time_ms ASC",
+            SESSION_COLUMNS
+        ))?;
         
-        let sessions = stmt.query_map(params![start_ts, end_ts], |row| {
-            let start_time: i64 = row.get(3)?;
-            let end_time: Option<i64> = row.get(4)?;
-            
-            Ok(Session {
-                id: Some(row.get(0)?),
-                app_id: row.get(1)?,
-                app_name: row.get(2)?,
-                start_time: Utc.timestamp_opt(start_time, 0).single().unwrap_or_else(Utc::now),
-                end_time: end_time.and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
-                duration_seconds: row.get(5)?,
-                is_idle: row.get(6)?,
-            })
-        })?;
+        let sessions = stmt.query_map(params![start_ts, end_ts], session_from_row)?;
         
         sessions.collect()
     }
 
-    /// Get today's sessions
+    /// Sessions owned by a local day in the report timezone, per the configured
+    /// `DayAttribution` (split sessions are clipped to the day)
+    pub fn get_day_sessions(&self, day: NaiveDate) -> Result<Vec<Session>, rusqlite::Error> {
+        let tz = self.report_timezone();
+        let (day_start, day_end) = tz.day_bounds(day);
+
+        // Anything overlapping the day could be attributed to it
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT {}
+             FROM sessions
+             WHERE start_time <= ?2 AND COALESCE(end_time, start_time) >= ?1
+             ORDER BY start_time ASC, start_time_ms ASC",
+            SESSION_COLUMNS
+        ))?;
+        let sessions = stmt
+            .query_map(params![day_start.timestamp(), day_end.timestamp()], session_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(sessions
+            .iter()
+            .flat_map(|session| attribute_to_days(session, tz, self.day_attribution()))
+            .filter(|(piece_day, _)| *piece_day == day)
+            .map(|(_, piece)| piece)
+            .collect())
+    }
+
+    /// Get today's sessions (day boundaries in the report timezone)
     pub fn get_today_sessions(&self) -> Result<Vec<Session>, rusqlite::Error> {
-        let today_start = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap();
-        let today_end = Utc::now().date_naive().and_hms_opt(23, 59, 59).unwrap();
-        
-        self.get_sessions_in_range(
-            Utc.from_utc_datetime(&today_start),
-            Utc.from_utc_datetime(&today_end),
-        )
+        self.get_day_sessions(self.report_timezone().today())
+    }
+
+    /// Total active time per app on a local day, most used first
+    pub fn get_day_app_totals(&self, day: NaiveDate) -> Result<Vec<(String, i64)>, rusqlite::Error> {
+        self.get_day_app_totals_live(day, None)
     }
 
-    /// Get total time per app for a date range
+    /// Like `get_day_app_totals`, with the part of a not yet stored session
+    /// owned by the day added to its app
+    pub fn get_day_app_totals_live(
+        &self,
+        day: NaiveDate,
+        in_progress: Option<&Session>,
+    ) -> Result<Vec<(String, i64)>, rusqlite::Error> {
+        let mut sessions = self.get_day_sessions(day)?;
+        if let Some(session) = in_progress {
+            sessions.extend(
+                attribute_to_days(session, self.report_timezone(), self.day_attribution())
+                    .into_iter()
+                    .filter(|(piece_day, _)| *piece_day == day)
+                    .map(|(_, piece)| piece),
+            );
+        }
+
+        let mut totals: HashMap<String, i64> = HashMap::new();
+        for session in sessions {
+            if !session.is_idle {
+                *totals.entry(session.app_id).or_default() += session.duration_seconds.unwrap_or(0);
+            }
+        }
+
+        let mut totals: Vec<_> = totals.into_iter().collect();
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(totals)
+    }
+
+    /// Get total time per app for today (day boundaries in the report timezone)
+    pub fn get_today_app_totals(&self) -> Result<Vec<(String, i64)>, rusqlite::Error> {
+        self.get_day_app_totals(self.report_timezone().today())
+    }
+
+    /// Get total time per app for a date range.
+    /// With `count_open_sessions`, sessions without an end (pending or crash-interrupted)
+    /// count up to `end` or now, whichever is earlier; otherwise they count as zero.
     pub fn get_app_totals(
         &self,
         start: DateTime<Utc>,
         end: DateTime<Utc>,
+        count_open_sessions: bool,
     ) -> Result<Vec<(String, i64)>, rusqlite::Error> {
         let start_ts = start.timestamp();
         let end_ts = end.timestamp();
+        let open_until_ts = end.min(Utc::now()).timestamp();
         
         let mut stmt = self.connection().prepare(
-            "SELECT app_id, SUM(duration_seconds) as total
+            "SELECT app_id,
+                    COALESCE(SUM(CASE
+                        WHEN duration_seconds IS NULL AND ?3 THEN MAX(0, ?4 - start_time)
+                        ELSE duration_seconds
+                    END), 0) as total
              FROM sessions
              WHERE start_time >= ?1 AND start_time <= ?2 AND is_idle = FALSE
              GROUP BY app_id
              ORDER BY total DESC"
         )?;
         
-        let totals = stmt.query_map(params![start_ts, end_ts], |row| {
+        let totals = stmt.query_map(params![start_ts, end_ts, count_open_sessions, open_until_ts], |row| {
             Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
         })?;
         
@@ -98,41 +368,472 @@ impl Database {
 
     /// Get the most recent pending session (for crash recovery)
     pub fn get_pending_session(&self) -> Result<Option<Session>, rusqlite::Error> {
-        let mut stmt = self.connection().prepare(
-            "SELECT id, app_id, app_name, start_time, end_time, duration_seconds, is_idle
+        let mut stmt = self.connection().prepare(&format!(
+            "SELECT {}
              FROM sessions
              WHERE is_pending = TRUE
-             ORDER BY start_time DESC
-             LIMIT 1"
-        )?;
+             ORDER BY start_time DESC, start_time_ms DESC
+             LIMIT 1",
+            SESSION_COLUMNS
+        ))?;
         
-        stmt.query_row([], |row| {
-            let start_time: i64 = row.get(3)?;
-            let end_time: Option<i64> = row.get(4)?;
-            
-            Ok(Session {
-                id: Some(row.get(0)?),
-                app_id: row.get(1)?,
-                app_name: row.get(2)?,
-                start_time: Utc.timestamp_opt(start_time, 0).single().unwrap_or_else(Utc::now),
-                end_time: end_time.and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
-                duration_seconds: row.get(5)?,
-                is_idle: row.get(6)?,
-            })
-        }).optional()
+        stmt.query_row([], session_from_row).optional()
     }
 
-    /// Close a pending session (used on crash recovery)
+    /// Close a pending session (used on crash recovery), refreshing the cached
+    /// totals of the days it counts towards
     pub fn close_pending_sessions(&self, end_time: DateTime<Utc>) -> Result<usize, rusqlite::Error> {
         let end_ts = end_time.timestamp();
         
-        self.connection().execute(
+        let tx = self.connection().unchecked_transaction()?;
+        let pending = tx
+            .prepare(&format!("SELECT {} FROM sessions WHERE is_pending = TRUE", SESSION_COLUMNS))?
+            .query_map([], session_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        let closed = tx.execute(
             "UPDATE sessions
              SET end_time = ?1,
+                 end_time_ms = ?2,
                  duration_seconds = ?1 - start_time,
                  is_pending = FALSE
              WHERE is_pending = TRUE",
-            params![end_ts],
-        )
+            params![end_ts, subsec_millis(end_time)],
+        )?;
+        for session in pending {
+            self.update_totals_cache(&Session { end_time: Some(end_time), ..session })?;
+        }
+        tx.commit()?;
+        Ok(closed)
+    }
+
+    /// Get the first start and last end time recorded for an app (active sessions only).
+    /// Returns None if the app has never been seen.
+    pub fn app_usage_bounds(
+        &self,
+        app_id: &str,
+    ) -> Result<Option<UsageBounds>, rusqlite::Error> {
+        let (first, last): (Option<i64>, Option<i64>) = self.connection().query_row(
+            "SELECT MIN(start_time), MAX(COALESCE(end_time, start_time))
+             FROM sessions
+             WHERE app_id = ?1 AND is_idle = FALSE",
+            params![app_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        Ok(match (first, last) {
+            (Some(first), Some(last)) => Utc
+                .timestamp_opt(first, 0)
+                .single()
+                .zip(Utc.timestamp_opt(last, 0).single()),
+            _ => None,
+        })
+    }
+
+    /// Reassign every active session of `from` to `to`, optionally rewriting the
+    /// `current_app` of compliance logs too. Returns the number of sessions moved.
+    pub fn rename_app(&self, from: &str, to: &str, include_compliance_logs: bool) -> Result<usize, rusqlite::Error> {
+        let tx = self.connection().unchecked_transaction()?;
+        let moved = tx.execute(
+            "UPDATE sessions SET app_id = ?2 WHERE app_id = ?1 AND is_idle = FALSE",
+            params![from, to],
+        )?;
+        // Keep the earliest first-seen time of the two apps
+        tx.execute(
+            "INSERT INTO known_apps (app_id, first_seen)
+             SELECT ?2, first_seen FROM known_apps WHERE app_id = ?1
+             ON CONFLICT(app_id) DO UPDATE SET first_seen = MIN(first_seen, excluded.first_seen)",
+            params![from, to],
+        )?;
+        tx.execute("DELETE FROM known_apps WHERE app_id = ?1", params![from])?;
+        // Totals are additive, so cached days can be merged in place
+        tx.execute(
+            "INSERT INTO daily_totals_cache (day, app_id, total_seconds)
+             SELECT day, ?2, total_seconds FROM daily_totals_cache WHERE app_id = ?1
+             ON CONFLICT(day, app_id) DO UPDATE SET total_seconds = total_seconds + excluded.total_seconds",
+            params![from, to],
+        )?;
+        tx.execute("DELETE FROM daily_totals_cache WHERE app_id = ?1", params![from])?;
+        if include_compliance_logs {
+            tx.execute(
+                "UPDATE compliance_logs SET current_app = ?2 WHERE current_app = ?1",
+                params![from, to],
+            )?;
+        }
+        tx.commit()?;
+        Ok(moved)
+    }
+
+    /// Id pairs `(earlier, later)` of completed sessions that overlap in time,
+    /// e.g. after a clock jump. The earlier session starts first (lower id on ties).
+    pub fn find_overlapping_sessions(&self) -> Result<Vec<(i64, i64)>, rusqlite::Error> {
+        let mut stmt = self.connection().prepare(
+            "SELECT a.id, b.id
+             FROM sessions a
+             JOIN sessions b
+               ON (a.start_time, a.start_time_ms, a.id) < (b.start_time, b.start_time_ms, b.id)
+              AND (a.end_time, a.end_time_ms) > (b.start_time, b.start_time_ms)
+             WHERE a.is_pending = FALSE AND b.is_pending = FALSE
+               AND a.end_time IS NOT NULL AND b.end_time IS NOT NULL
+             ORDER BY a.start_time, a.start_time_ms, a.id, b.start_time, b.start_time_ms, b.id",
+        )?;
+        let pairs = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect();
+        pairs
+    }
+
+    /// Truncate every completed session that runs past the start of the next one
+    /// to end there, shortening its duration by the cut. Returns how many sessions
+    /// were adjusted; cached totals of the affected days are refreshed.
+    pub fn fix_overlapping_sessions(&self) -> Result<usize, rusqlite::Error> {
+        let tx = self.connection().unchecked_transaction()?;
+        let sessions: Vec<Session> = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT {}
+                 FROM sessions
+                 WHERE is_pending = FALSE AND end_time IS NOT NULL
+                 ORDER BY start_time, start_time_ms, id",
+                SESSION_COLUMNS
+            ))?;
+            let sessions = stmt.query_map([], session_from_row)?.collect::<Result<_, _>>()?;
+            sessions
+        };
+
+        let mut adjusted = Vec::new();
+        for pair in sessions.windows(2) {
+            let (session, next) = (&pair[0], &pair[1]);
+            let Some(end) = session.end_time.filter(|end| *end > next.start_time) else {
+                continue;
+            };
+            let cut = (end - next.start_time).num_seconds();
+            tx.execute(
+                "UPDATE sessions
+                 SET end_time = ?1, end_time_ms = ?2, duration_seconds = MAX(0, duration_seconds - ?3)
+                 WHERE id = ?4",
+                params![next.start_time.timestamp(), subsec_millis(next.start_time), cut, session.id],
+            )?;
+            adjusted.push(session);
+        }
+        tx.commit()?;
+
+        // Recomputed from the fixed rows; the original span covers every day touched
+        for session in &adjusted {
+            self.update_totals_cache(session)?;
+        }
+        Ok(adjusted.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::IDLE_APP_ID;
+
+    fn session(app_id: &str, start: i64, duration: i64) -> Session {
+        Session {
+            id: None,
+            app_id: app_id.to_string(),
+            app_name: None,
+            start_time: Utc.timestamp_opt(start, 0).unwrap(),
+            end_time: Some(Utc.timestamp_opt(start + duration, 0).unwrap()),
+            duration_seconds: Some(duration),
+            is_idle: false,
+            manual: false,
+            process_path: None,
+            interruptions: 0,
+            input_rate: None,
+        }
+    }
+
+    #[test]
+    fn test_app_named_idle_is_not_filtered() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(crate::timezone::ReportTimezone::parse("UTC").unwrap());
+        // 2024-01-01T09:00:00Z
+        let base = 1_704_099_600;
+        let start = |offset| Utc.timestamp_opt(base + offset, 0).unwrap();
+        db.insert_session(&session(IDLE_APP_ID, base, 600)).unwrap();
+        db.insert_session(&Session::idle(start(600), Some(start(900)))).unwrap();
+        db.insert_session(&session("Code.exe", base + 900, 120)).unwrap();
+
+        let totals = db.get_app_totals(start(0), start(3_600), false).unwrap();
+        assert_eq!(totals, vec![(IDLE_APP_ID.to_string(), 600), ("Code.exe".to_string(), 120)]);
+        let day = chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        assert_eq!(db.get_day_app_totals(day).unwrap(), totals);
+
+        let (first, last) = db.app_usage_bounds(IDLE_APP_ID).unwrap().unwrap();
+        assert_eq!((first, last), (start(0), start(600)));
+
+        // Renaming the real app leaves idle sessions alone
+        assert_eq!(db.rename_app(IDLE_APP_ID, "Idle.exe", false).unwrap(), 1);
+        let idle: Vec<_> = db
+            .get_sessions_in_range(start(0), start(3_600))
+            .unwrap()
+            .into_iter()
+            .filter(|s| s.is_idle)
+            .map(|s| s.app_id)
+            .collect();
+        assert_eq!(idle, vec![IDLE_APP_ID.to_string()]);
+    }
+
+    #[test]
+    fn test_app_usage_bounds() {
+        let db = Database::in_memory();
+        db.insert_session(&session("Code.exe", 2_000, 100)).unwrap();
+        db.insert_session(&session("Code.exe", 1_000, 50)).unwrap();
+        db.insert_session(&session("Code.exe", 5_000, 300)).unwrap();
+        db.insert_session(&session("Slack.exe", 9_000, 60)).unwrap();
+
+        let (first, last) = db.app_usage_bounds("Code.exe").unwrap().unwrap();
+        assert_eq!(first.timestamp(), 1_000);
+        assert_eq!(last.timestamp(), 5_300);
+
+        assert!(db.app_usage_bounds("Unknown.exe").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_manual_session_counts_in_totals() {
+        let db = Database::in_memory();
+        db.insert_session(&session("Figma.exe", 1_000, 600)).unwrap();
+        let start = Utc.timestamp_opt(5_000, 0).unwrap();
+        let end = Utc.timestamp_opt(8_600, 0).unwrap();
+        db.insert_manual_session("Figma.exe", Some("Figma"), start, end).unwrap();
+
+        let totals = db
+            .get_app_totals(Utc.timestamp_opt(0, 0).unwrap(), Utc.timestamp_opt(10_000, 0).unwrap(), false)
+            .unwrap();
+        assert_eq!(totals, vec![("Figma.exe".to_string(), 4_200)]);
+
+        let sessions = db
+            .get_sessions_in_range(Utc.timestamp_opt(0, 0).unwrap(), Utc.timestamp_opt(10_000, 0).unwrap())
+            .unwrap();
+        assert!(!sessions[0].manual);
+        assert!(sessions[1].manual);
+        assert!(!sessions[1].is_idle);
+    }
+
+    #[test]
+    fn test_open_sessions_counted_when_requested() {
+        let db = Database::in_memory();
+        let now = Utc::now();
+        db.insert_session(&Session {
+            start_time: now - chrono::Duration::seconds(3_600),
+            end_time: Some(now - chrono::Duration::seconds(3_000)),
+            ..session("Code.exe", 0, 600)
+        })
+        .unwrap();
+        // Interrupted before it could be closed
+        db.insert_pending_session(&Session {
+            start_time: now - chrono::Duration::seconds(600),
+            end_time: None,
+            duration_seconds: None,
+            ..session("Code.exe", 0, 0)
+        })
+        .unwrap();
+
+        let range = (now - chrono::Duration::hours(2), now + chrono::Duration::hours(2));
+        let totals = db.get_app_totals(range.0, range.1, false).unwrap();
+        assert_eq!(totals, vec![("Code.exe".to_string(), 600)]);
+
+        let totals = db.get_app_totals(range.0, range.1, true).unwrap();
+        assert_eq!(totals.len(), 1);
+        assert!((1_200..=1_202).contains(&totals[0].1), "got {}", totals[0].1);
+
+        // Capped at the end of the range
+        let totals = db.get_app_totals(range.0, now - chrono::Duration::seconds(300), true).unwrap();
+        assert_eq!(totals, vec![("Code.exe".to_string(), 900)]);
+    }
+
+    #[test]
+    fn test_manual_session_rejects_empty_range() {
+        let db = Database::in_memory();
+        let at = Utc.timestamp_opt(5_000, 0).unwrap();
+        assert!(matches!(
+            db.insert_manual_session("Figma.exe", None, at, at),
+            Err(DbError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_adjacent_identical_sessions_merge() {
+        let db = Database::in_memory();
+        let (first_id, merged) = db.insert_or_merge_session(&session("Code.exe", 1_000, 600)).unwrap();
+        assert!(!merged);
+        let (second_id, merged) = db.insert_or_merge_session(&session("Code.exe", 1_600, 300)).unwrap();
+        assert!(merged);
+        assert_eq!(first_id, second_id);
+
+        // A different app or a real gap starts a new row
+        let (_, merged) = db.insert_or_merge_session(&session("Slack.exe", 1_900, 60)).unwrap();
+        assert!(!merged);
+        let (_, merged) = db.insert_or_merge_session(&session("Slack.exe", 2_500, 60)).unwrap();
+        assert!(!merged);
+
+        let sessions = db
+            .get_sessions_in_range(Utc.timestamp_opt(0, 0).unwrap(), Utc.timestamp_opt(10_000, 0).unwrap())
+            .unwrap();
+        assert_eq!(sessions.len(), 3);
+        assert_eq!(sessions[0].duration_seconds, Some(900));
+        assert_eq!(sessions[0].end_time.unwrap().timestamp(), 1_900);
+    }
+
+    #[test]
+    fn test_capped_idle_chunks_are_not_merged() {
+        let db = Database::in_memory();
+        let at = |ts: i64| Utc.timestamp_opt(ts, 0).unwrap();
+        let (first_id, merged) = db.insert_or_merge_session(&Session::idle(at(1_000), Some(at(4_600)))).unwrap();
+        assert!(!merged);
+        let (second_id, merged) = db.insert_or_merge_session(&Session::idle(at(4_600), Some(at(8_200)))).unwrap();
+        assert!(!merged);
+        assert_ne!(first_id, second_id);
+
+        let sessions = db.get_sessions_in_range(at(0), at(10_000)).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.iter().all(|s| s.duration_seconds == Some(3_600)));
+    }
+
+    #[test]
+    fn test_merge_sums_engaged_durations() {
+        let db = Database::in_memory();
+        // 600s spans with soft-idle time already taken out of the duration
+        db.insert_or_merge_session(&Session { duration_seconds: Some(450), ..session("Code.exe", 1_000, 600) })
+            .unwrap();
+        let (_, merged) = db
+            .insert_or_merge_session(&Session { duration_seconds: Some(500), ..session("Code.exe", 1_600, 600) })
+            .unwrap();
+        assert!(merged);
+
+        let sessions = db
+            .get_sessions_in_range(Utc.timestamp_opt(0, 0).unwrap(), Utc.timestamp_opt(10_000, 0).unwrap())
+            .unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].duration_seconds, Some(950));
+        assert_eq!(sessions[0].end_time.unwrap().timestamp(), 2_200);
+    }
+
+    #[test]
+    fn test_timestamps_keep_milliseconds() {
+        let db = Database::in_memory();
+        let at = |ms: i64| Utc.timestamp_millis_opt(1_700_000_000_000 + ms).unwrap();
+        // Two sessions starting within the same second, stored out of order
+        db.insert_session(&Session {
+            start_time: at(870),
+            end_time: Some(at(2_005)),
+            duration_seconds: Some(1),
+            ..session("Slack.exe", 0, 0)
+        })
+        .unwrap();
+        db.insert_session(&Session {
+            start_time: at(120),
+            end_time: Some(at(870)),
+            ..session("Code.exe", 0, 0)
+        })
+        .unwrap();
+        db.insert_pending_session(&Session { start_time: at(2_005), end_time: None, ..session("Code.exe", 0, 0) })
+            .unwrap();
+        db.close_pending_sessions(at(3_999)).unwrap();
+
+        let stored: Vec<_> = db
+            .get_sessions_in_range(at(0), at(5_000))
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.app_id, s.start_time, s.end_time.unwrap()))
+            .collect();
+        assert_eq!(
+            stored,
+            vec![
+                ("Code.exe".to_string(), at(120), at(870)),
+                ("Slack.exe".to_string(), at(870), at(2_005)),
+                ("Code.exe".to_string(), at(2_005), at(3_999)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fix_overlapping_sessions() {
+        let db = Database::in_memory();
+        db.insert_session(&session("Code.exe", 1_000, 600)).unwrap();
+        // Starts 200s before Code.exe ends, e.g. after the clock jumped back
+        db.insert_session(&session("Slack.exe", 1_400, 300)).unwrap();
+        db.insert_session(&session("firefox.exe", 2_000, 100)).unwrap();
+        let range = (Utc.timestamp_opt(0, 0).unwrap(), Utc.timestamp_opt(10_000, 0).unwrap());
+        let total = |db: &Database| -> i64 {
+            db.get_app_totals(range.0, range.1, false).unwrap().iter().map(|(_, secs)| secs).sum()
+        };
+        assert_eq!(total(&db), 1_000);
+
+        let overlaps = db.find_overlapping_sessions().unwrap();
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(db.fix_overlapping_sessions().unwrap(), 1);
+
+        assert!(db.find_overlapping_sessions().unwrap().is_empty());
+        assert_eq!(total(&db), 800);
+        let sessions = db.get_sessions_in_range(range.0, range.1).unwrap();
+        assert_eq!(sessions[0].id, Some(overlaps[0].0));
+        assert_eq!(sessions[0].end_time, Some(sessions[1].start_time));
+        assert_eq!(sessions[0].duration_seconds, Some(400));
+        assert_eq!(db.fix_overlapping_sessions().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_overlaps_compare_milliseconds() {
+        let db = Database::in_memory();
+        let at = |secs: i64, millis: i64| Utc.timestamp_opt(secs, 0).unwrap() + chrono::Duration::milliseconds(millis);
+        // Code.exe runs 400ms into Slack.exe, both within second 1_010
+        let code = Session { end_time: Some(at(1_010, 600)), ..session("Code.exe", 1_000, 10) };
+        let slack = Session { start_time: at(1_010, 200), ..session("Slack.exe", 1_010, 20) };
+        let code_id = db.insert_session(&code).unwrap();
+        let slack_id = db.insert_session(&slack).unwrap();
+
+        assert_eq!(db.find_overlapping_sessions().unwrap(), vec![(code_id, slack_id)]);
+        assert_eq!(db.fix_overlapping_sessions().unwrap(), 1);
+        assert!(db.find_overlapping_sessions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rename_app_merges_totals() {
+        let db = Database::in_memory();
+        db.insert_session(&session("code.exe", 1_000, 300)).unwrap();
+        db.insert_session(&session("code.exe", 3_000, 200)).unwrap();
+        db.insert_session(&session("Code.exe", 5_000, 600)).unwrap();
+        db.insert_session(&session("Slack.exe", 7_000, 60)).unwrap();
+
+        assert_eq!(db.rename_app("code.exe", "Code.exe", false).unwrap(), 2);
+
+        let totals = db
+            .get_app_totals(Utc.timestamp_opt(0, 0).unwrap(), Utc.timestamp_opt(10_000, 0).unwrap(), false)
+            .unwrap();
+        assert_eq!(
+            totals,
+            vec![("Code.exe".to_string(), 1_100), ("Slack.exe".to_string(), 60)]
+        );
+        assert_eq!(db.rename_app("code.exe", "Code.exe", false).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_day_attribution_modes() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+        // 23:45 - 00:15 across 2024-01-01/02
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 23, 45, 0).unwrap().timestamp();
+        db.insert_session(&session("Code.exe", start, 1_800)).unwrap();
+
+        let first = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let second = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let totals = |db: &Database, day| db.get_day_app_totals(day).unwrap();
+        let code = |secs| vec![("Code.exe".to_string(), secs)];
+
+        db.set_day_attribution(DayAttribution::StartDay);
+        assert_eq!(totals(&db, first), code(1_800));
+        assert!(totals(&db, second).is_empty());
+
+        db.set_day_attribution(DayAttribution::EndDay);
+        assert!(totals(&db, first).is_empty());
+        assert_eq!(totals(&db, second), code(1_800));
+
+        db.set_day_attribution(DayAttribution::Split);
+        assert_eq!(totals(&db, first), code(900));
+        assert_eq!(totals(&db, second), code(900));
+        let pieces = db.get_day_sessions(second).unwrap();
+        assert_eq!(pieces[0].start_time, Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap());
+        assert_eq!(pieces[0].end_time.unwrap().timestamp(), start + 1_800);
     }
 }
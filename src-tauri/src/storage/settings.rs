@@ -0,0 +1,107 @@
+use crate::storage::db::{Database, DbError};
+use rusqlite::{params, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Display;
+use std::str::FromStr;
+
+impl Database {
+    /// Raw value of a setting, if it has been set
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>, rusqlite::Error> {
+        self.connection()
+            .query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()
+    }
+
+    /// Set a setting, replacing any previous value
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<(), rusqlite::Error> {
+        self.connection().execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// All stored settings, ordered by key
+    pub fn get_all_settings(&self) -> Result<Vec<(String, String)>, rusqlite::Error> {
+        let mut stmt = self
+            .connection()
+            .prepare("SELECT key, value FROM settings ORDER BY key")?;
+        let settings = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        settings.collect()
+    }
+
+    /// Remove a setting so its default applies again
+    pub fn delete_setting(&self, key: &str) -> Result<(), rusqlite::Error> {
+        self.connection()
+            .execute("DELETE FROM settings WHERE key = ?1", params![key])?;
+        Ok(())
+    }
+
+    /// Setting parsed with `FromStr` (numbers, booleans, ...)
+    pub fn get_setting_parsed<T: FromStr>(&self, key: &str) -> Result<Option<T>, DbError> {
+        match self.get_setting(key)? {
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| DbError::InvalidInput(format!("setting {} has invalid value {:?}", key, value))),
+            None => Ok(None),
+        }
+    }
+
+    /// Store a value using its `Display` form
+    pub fn set_setting_display<T: Display>(&self, key: &str, value: T) -> Result<(), rusqlite::Error> {
+        self.set_setting(key, &value.to_string())
+    }
+
+    /// Setting stored as JSON (enums, lists, options)
+    pub fn get_setting_json<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, DbError> {
+        match self.get_setting(key)? {
+            Some(value) => serde_json::from_str(&value)
+                .map(Some)
+                .map_err(|e| DbError::InvalidInput(format!("setting {}: {}", key, e))),
+            None => Ok(None),
+        }
+    }
+
+    /// Store a value as JSON
+    pub fn set_setting_json<T: Serialize>(&self, key: &str, value: &T) -> Result<(), DbError> {
+        let json = serde_json::to_string(value).map_err(|e| DbError::InvalidInput(e.to_string()))?;
+        self.set_setting(key, &json)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setting_round_trip_and_overwrite() {
+        let db = Database::in_memory();
+        assert_eq!(db.get_setting("idle_threshold_seconds").unwrap(), None);
+
+        db.set_setting_display("idle_threshold_seconds", 300).unwrap();
+        assert_eq!(db.get_setting_parsed::<u64>("idle_threshold_seconds").unwrap(), Some(300));
+
+        db.set_setting("idle_threshold_seconds", "600").unwrap();
+        assert_eq!(db.get_setting("idle_threshold_seconds").unwrap().as_deref(), Some("600"));
+
+        db.set_setting("idle_threshold_seconds", "soon").unwrap();
+        assert!(db.get_setting_parsed::<u64>("idle_threshold_seconds").is_err());
+
+        db.delete_setting("idle_threshold_seconds").unwrap();
+        assert_eq!(db.get_setting("idle_threshold_seconds").unwrap(), None);
+    }
+
+    #[test]
+    fn test_json_setting_round_trip() {
+        let db = Database::in_memory();
+        let allowlist = Some(vec!["code".to_string(), "firefox".to_string()]);
+        db.set_setting_json("title_capture_allowlist", &allowlist).unwrap();
+
+        let loaded: Option<Option<Vec<String>>> = db.get_setting_json("title_capture_allowlist").unwrap();
+        assert_eq!(loaded, Some(allowlist));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::models::Session;
+use crate::storage::db::Database;
+use crate::storage::sessions::attribute_to_days;
+use chrono::{DateTime, NaiveDate};
+use rusqlite::{params, OptionalExtension};
+use std::collections::BTreeSet;
+
+/// Seconds per app, most used first
+pub type AppTotals = Vec<(String, i64)>;
+
+/// Precomputed per-app totals by local day, so dashboards over long histories
+/// don't have to re-read every session. Days are keyed in the report timezone
+/// and day attribution in effect when they were cached; rebuild after changing either.
+impl Database {
+    /// Recompute the cache from all stored sessions, covering every local day from
+    /// the first session through today. Returns the number of days cached.
+    pub fn rebuild_totals_cache(&self) -> Result<usize, rusqlite::Error> {
+        let tx = self.connection().unchecked_transaction()?;
+        tx.execute_batch("DELETE FROM daily_totals_cache; DELETE FROM daily_totals_cache_days;")?;
+
+        let first: Option<i64> = tx.query_row("SELECT MIN(start_time) FROM sessions", [], |row| row.get(0))?;
+        let Some(first) = first.and_then(|ts| DateTime::from_timestamp(ts, 0)) else {
+            tx.commit()?;
+            return Ok(0);
+        };
+
+        let tz = self.report_timezone();
+        let first_day = tz.to_local(first).date();
+        let last_day = tz.today().max(first_day);
+        let mut cached = 0;
+        for day in first_day.iter_days().take_while(|day| *day <= last_day) {
+            self.store_cached_day(day)?;
+            cached += 1;
+        }
+        tx.commit()?;
+        Ok(cached)
+    }
+
+    /// Refresh the days a newly saved session counts towards. Today is always
+    /// cached this way; other days are only refreshed if already cached.
+    pub fn update_totals_cache(&self, session: &Session) -> Result<(), rusqlite::Error> {
+        if session.is_idle {
+            return Ok(());
+        }
+        let today = self.report_timezone().today();
+        let days: BTreeSet<NaiveDate> = attribute_to_days(session, self.report_timezone(), self.day_attribution())
+            .into_iter()
+            .map(|(day, _)| day)
+            .collect();
+        for day in days {
+            if day == today || self.is_day_cached(day)? {
+                self.store_cached_day(day)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Per-app totals (most used first) for each local day from `start` to `end`
+    /// inclusive. Cached days come from the cache, the rest are computed live.
+    pub fn get_daily_totals(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, AppTotals)>, rusqlite::Error> {
+        start
+            .iter_days()
+            .take_while(|day| *day <= end)
+            .map(|day| {
+                let totals = match self.cached_day_totals(day)? {
+                    Some(totals) => totals,
+                    None => self.get_day_app_totals(day)?,
+                };
+                Ok((day, totals))
+            })
+            .collect()
+    }
+
+    fn is_day_cached(&self, day: NaiveDate) -> Result<bool, rusqlite::Error> {
+        self.connection()
+            .query_row(
+                "SELECT 1 FROM daily_totals_cache_days WHERE day = ?1",
+                params![day.to_string()],
+                |_| Ok(()),
+            )
+            .optional()
+            .map(|row| row.is_some())
+    }
+
+    fn cached_day_totals(&self, day: NaiveDate) -> Result<Option<AppTotals>, rusqlite::Error> {
+        if !self.is_day_cached(day)? {
+            return Ok(None);
+        }
+        let mut stmt = self.connection().prepare(
+            "SELECT app_id, total_seconds FROM daily_totals_cache
+             WHERE day = ?1
+             ORDER BY total_seconds DESC, app_id ASC",
+        )?;
+        let totals = stmt
+            .query_map(params![day.to_string()], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(totals))
+    }
+
+    /// Recompute one day live and replace its cached totals
+    fn store_cached_day(&self, day: NaiveDate) -> Result<(), rusqlite::Error> {
+        let key = day.to_string();
+        let totals = self.get_day_app_totals(day)?;
+        self.connection()
+            .execute("DELETE FROM daily_totals_cache WHERE day = ?1", params![key])?;
+        for (app_id, total) in totals {
+            self.connection().execute(
+                "INSERT INTO daily_totals_cache (day, app_id, total_seconds) VALUES (?1, ?2, ?3)",
+                params![key, app_id, total],
+            )?;
+        }
+        self.connection().execute(
+            "INSERT OR IGNORE INTO daily_totals_cache_days (day) VALUES (?1)",
+            params![key],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timezone::ReportTimezone;
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn session(app_id: &str, start: DateTime<chrono::Utc>, minutes: i64) -> Session {
+        Session {
+            id: None,
+            app_id: app_id.to_string(),
+            app_name: None,
+            start_time: start,
+            end_time: Some(start + Duration::minutes(minutes)),
+            duration_seconds: Some(minutes * 60),
+            is_idle: false,
+            manual: false,
+            process_path: None,
+            interruptions: 0,
+            input_rate: None,
+        }
+    }
+
+    #[test]
+    fn test_cache_matches_live_totals() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+        let day1 = Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap();
+        for (app, start, minutes) in [
+            ("Code.exe", day1, 90),
+            ("Slack.exe", day1 + Duration::hours(2), 20),
+            ("Code.exe", day1 + Duration::hours(3), 30),
+            ("Code.exe", day1 + Duration::days(2), 45),
+        ] {
+            db.insert_session(&session(app, start, minutes)).unwrap();
+        }
+        let first = day1.date_naive();
+        let last = first + Duration::days(3);
+        let live: Vec<_> = first
+            .iter_days()
+            .take_while(|day| *day <= last)
+            .map(|day| (day, db.get_day_app_totals(day).unwrap()))
+            .collect();
+
+        assert!(db.rebuild_totals_cache().unwrap() >= 4);
+        assert!(db.is_day_cached(first).unwrap());
+        assert_eq!(db.get_daily_totals(first, last).unwrap(), live);
+        assert_eq!(live[0].1, vec![("Code.exe".to_string(), 7_200), ("Slack.exe".to_string(), 1_200)]);
+        assert!(live[1].1.is_empty());
+
+        // A new session on a cached day refreshes it
+        let late = session("Slack.exe", day1 + Duration::hours(8), 40);
+        db.insert_session(&late).unwrap();
+        db.update_totals_cache(&late).unwrap();
+        assert_eq!(db.get_daily_totals(first, first).unwrap()[0].1, db.get_day_app_totals(first).unwrap());
+    }
+
+    #[test]
+    fn test_recovered_sessions_refresh_cache() {
+        let mut db = Database::in_memory();
+        db.set_report_timezone(ReportTimezone::parse("UTC").unwrap());
+        let start = Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap();
+        db.insert_session(&session("Code.exe", start, 60)).unwrap();
+        db.rebuild_totals_cache().unwrap();
+
+        // Interrupted before it could be closed, then recovered
+        let crashed = Session {
+            end_time: None,
+            duration_seconds: None,
+            ..session("Slack.exe", start + Duration::hours(2), 0)
+        };
+        db.insert_pending_session(&crashed).unwrap();
+        assert_eq!(db.close_pending_sessions(start + Duration::hours(3)).unwrap(), 1);
+
+        let day = start.date_naive();
+        assert_eq!(
+            db.get_daily_totals(day, day).unwrap()[0].1,
+            vec![("Code.exe".to_string(), 3_600), ("Slack.exe".to_string(), 3_600)]
+        );
+    }
+
+    #[test]
+    fn test_uncached_days_fall_back_to_live() {
+        let db = Database::in_memory();
+        let start = Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap();
+        db.insert_session(&session("Code.exe", start, 60)).unwrap();
+
+        let day = db.report_timezone().to_local(start).date();
+        assert!(!db.is_day_cached(day).unwrap());
+        assert_eq!(db.get_daily_totals(day, day).unwrap()[0].1, vec![("Code.exe".to_string(), 3_600)]);
+    }
+}
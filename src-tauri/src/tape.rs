@@ -0,0 +1,208 @@
+//! Recorded collector readings ("tapes") for reproducing sessionizer bugs.
+//! Record mode appends one JSON line per tick; `replay_file` feeds a tape
+//! through a fresh engine so the same sessions come out every time.
+
+use crate::collectors::mock::{MockCollector, MockReading};
+use crate::config::Config;
+use crate::engine::TrackingEngine;
+use crate::models::{AppInfo, Session};
+use crate::scheduler::SchedulerEngine;
+use crate::sessionizer::{ForegroundDebouncer, Sessionizer};
+use crate::storage::{Database, DbError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Schema version written to every record. Bump when the record layout changes.
+pub const TAPE_VERSION: u32 = 1;
+
+/// File name of the tape inside the app data directory
+pub const TAPE_FILE_NAME: &str = "tape.jsonl";
+
+#[derive(Error, Debug)]
+pub enum TapeError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid record on line {line}: {source}")]
+    Json { line: usize, source: serde_json::Error },
+    #[error("Unsupported tape version {version} on line {line}")]
+    UnsupportedVersion { line: usize, version: u32 },
+    #[error("Replay database error: {0}")]
+    Db(#[from] DbError),
+}
+
+/// One tick's collector readings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TapeRecord {
+    pub version: u32,
+    pub timestamp: DateTime<Utc>,
+    pub app: Option<AppInfo>,
+    pub idle_seconds: u64,
+    #[serde(default)]
+    pub in_call: bool,
+}
+
+/// Appends records to a tape file
+pub struct TapeRecorder {
+    writer: BufWriter<File>,
+}
+
+impl TapeRecorder {
+    /// Open `path` for appending, creating it if needed
+    pub fn open(path: &Path) -> Result<Self, TapeError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    /// Append one tick. Flushed immediately so a crash doesn't lose the tail.
+    pub fn record(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        app: Option<&AppInfo>,
+        idle_seconds: u64,
+        in_call: bool,
+    ) -> Result<(), TapeError> {
+        let record = TapeRecord {
+            version: TAPE_VERSION,
+            timestamp,
+            app: app.cloned(),
+            idle_seconds,
+            in_call,
+        };
+        let line = serde_json::to_string(&record).map_err(std::io::Error::other)?;
+        writeln!(self.writer, "{}", line)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Read every record of a tape, skipping blank lines
+pub fn read_tape(path: &Path) -> Result<Vec<TapeRecord>, TapeError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: TapeRecord =
+            serde_json::from_str(&line).map_err(|source| TapeError::Json { line: index + 1, source })?;
+        if record.version > TAPE_VERSION {
+            return Err(TapeError::UnsupportedVersion {
+                line: index + 1,
+                version: record.version,
+            });
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Feed a recorded tape through a fresh engine (in-memory database, sessionizer
+/// and debouncer built from `config`) and return the sessions it completes.
+pub async fn replay_file(path: &Path, config: &Config) -> Result<Vec<Session>, TapeError> {
+    let records = read_tape(path)?;
+    let script = records.iter().map(|record| MockReading {
+        app: record.app.clone(),
+        idle_seconds: record.idle_seconds,
+        in_call: record.in_call,
+        // Recorded idle already reflects `treat_as_active`
+        running: Vec::new(),
+        // Tapes don't record input rates
+        input_rate: None,
+    });
+
+    let mut sessionizer = Sessionizer::new(config.sessionizer_config());
+    sessionizer.set_timezone(config.report_timezone);
+    let engine = TrackingEngine::new(
+        Arc::new(MockCollector::new(script.collect::<Vec<_>>())),
+        sessionizer,
+        Database::open_in_memory()?,
+        Arc::new(SchedulerEngine::new()),
+        ForegroundDebouncer::new(config.foreground_debounce_ticks),
+    );
+
+    let mut sessions = Vec::new();
+    for record in &records {
+        sessions.extend(engine.tick(record.timestamp).await.completed_sessions);
+    }
+    Ok(sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::ForegroundCollector;
+
+    #[tokio::test]
+    async fn test_recorded_tape_replays_to_identical_sessions() {
+        let path = std::env::temp_dir().join("timewarden_tape_test.jsonl");
+        let _ = std::fs::remove_file(&path);
+        let config = Config::default();
+
+        let script = vec![
+            MockReading::app("Code.exe"),
+            MockReading::app("Code.exe"),
+            MockReading::app("firefox.exe"),
+            MockReading::app("firefox.exe"),
+            MockReading::app("firefox.exe").with_idle(400),
+            MockReading::app("Code.exe"),
+            MockReading::none(),
+        ];
+        let collector = MockCollector::new(script);
+        let mut sessionizer = Sessionizer::new(config.sessionizer_config());
+        let mut recorder = TapeRecorder::open(&path).unwrap();
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let mut live = Vec::new();
+        for tick in 0..7 {
+            let now = start + chrono::Duration::seconds(tick * 30);
+            let app = collector.get_foreground_app();
+            let idle = collector.get_idle_seconds();
+            recorder.record(now, app.as_ref(), idle, false).unwrap();
+            sessionizer.update_at(app, idle, false, now);
+            live.extend(sessionizer.take_pending_sessions());
+        }
+        drop(recorder);
+
+        let records = read_tape(&path).unwrap();
+        assert_eq!(records.len(), 7);
+        assert!(records.iter().all(|r| r.version == TAPE_VERSION));
+
+        let replayed = replay_file(&path, &Config {
+            foreground_debounce_ticks: 1,
+            ..config
+        })
+        .await
+        .unwrap();
+        let summary = |sessions: &[Session]| -> Vec<_> {
+            sessions
+                .iter()
+                .map(|s| (s.app_id.clone(), s.is_idle, s.start_time, s.end_time))
+                .collect()
+        };
+        assert_eq!(live.len(), 4);
+        assert_eq!(summary(&replayed), summary(&live));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_newer_tape_version_rejected() {
+        let path = std::env::temp_dir().join("timewarden_tape_version_test.jsonl");
+        std::fs::write(
+            &path,
+            format!(
+                "{{\"version\":{},\"timestamp\":\"2024-03-04T09:00:00Z\",\"app\":null,\"idle_seconds\":0}}\n",
+                TAPE_VERSION + 1
+            ),
+        )
+        .unwrap();
+        assert!(matches!(read_tape(&path), Err(TapeError::UnsupportedVersion { line: 1, .. })));
+        let _ = std::fs::remove_file(&path);
+    }
+}
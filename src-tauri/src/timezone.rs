@@ -0,0 +1,119 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// Which local day owns a session that crosses midnight
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DayAttribution {
+    /// The whole session counts on the day it started
+    StartDay,
+    /// The whole session counts on the day it ended
+    EndDay,
+    /// Each day gets the part of the session that falls within it (default)
+    #[default]
+    Split,
+}
+
+/// Timezone used for day boundaries and schedule windows.
+/// Defaults to the system local timezone.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ReportTimezone {
+    #[default]
+    Local,
+    /// A fixed IANA timezone, independent of the system setting
+    Named(Tz),
+}
+
+impl ReportTimezone {
+    /// Parse an IANA timezone name (e.g. "Europe/Berlin")
+    pub fn parse(name: &str) -> Result<Self, String> {
+        name.parse::<Tz>()
+            .map(ReportTimezone::Named)
+            .map_err(|_| format!("Unknown timezone: {}", name))
+    }
+
+    /// IANA name of the configured timezone, or None for system local
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            ReportTimezone::Local => None,
+            ReportTimezone::Named(tz) => Some(tz.name()),
+        }
+    }
+
+    /// Convert a UTC instant to wall-clock time in this timezone
+    pub fn to_local(&self, time: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            ReportTimezone::Local => time.with_timezone(&Local).naive_local(),
+            ReportTimezone::Named(tz) => time.with_timezone(tz).naive_local(),
+        }
+    }
+
+    /// Current wall-clock time in this timezone
+    pub fn now_local(&self) -> NaiveDateTime {
+        self.to_local(Utc::now())
+    }
+
+    /// Today's date in this timezone
+    pub fn today(&self) -> NaiveDate {
+        self.now_local().date()
+    }
+
+    /// Convert a wall-clock time in this timezone to UTC.
+    /// Times skipped by a DST change resolve to the following hour.
+    pub fn to_utc(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        match self {
+            ReportTimezone::Local => local_to_utc(&Local, local),
+            ReportTimezone::Named(tz) => local_to_utc(tz, local),
+        }
+    }
+
+    /// First and last second of a local day, as UTC instants
+    pub fn day_bounds(&self, day: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+        let start = self.to_utc(day.and_hms_opt(0, 0, 0).unwrap());
+        let next = self.to_utc(day.succ_opt().unwrap_or(day).and_hms_opt(0, 0, 0).unwrap());
+        (start, next - Duration::seconds(1))
+    }
+}
+
+fn local_to_utc<T: TimeZone>(tz: &T, local: NaiveDateTime) -> DateTime<Utc> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&local))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_day_bounds_in_named_timezone() {
+        let tz = ReportTimezone::parse("America/New_York").unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let (start, end) = tz.day_bounds(day);
+
+        assert_eq!(start.to_rfc3339(), "2024-01-15T05:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-01-16T04:59:59+00:00");
+    }
+
+    #[test]
+    fn test_pinned_timezone_bucketing_ignores_travel() {
+        // 2024-01-16T03:00:00Z is still the 15th at home but already the 16th in Tokyo
+        let instant = Utc.with_ymd_and_hms(2024, 1, 16, 3, 0, 0).unwrap();
+        let home = ReportTimezone::parse("America/New_York").unwrap();
+        let travel = ReportTimezone::parse("Asia/Tokyo").unwrap();
+
+        assert_eq!(home.to_local(instant).date(), NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+        assert_eq!(travel.to_local(instant).date(), NaiveDate::from_ymd_opt(2024, 1, 16).unwrap());
+
+        let (start, end) = home.day_bounds(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+        assert!(instant >= start && instant <= end);
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_timezone() {
+        assert!(ReportTimezone::parse("Mars/Olympus_Mons").is_err());
+        assert_eq!(ReportTimezone::default().name(), None);
+    }
+}
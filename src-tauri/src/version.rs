@@ -0,0 +1,39 @@
+//! Backend version details, for frontend compatibility checks and the about box.
+
+use crate::storage::db::{Database, DbError};
+use serde::Serialize;
+
+/// Commit the backend was built from, when built from a git checkout
+pub const GIT_HASH: Option<&str> = option_env!("TIMEWARDEN_GIT_HASH");
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppVersion {
+    /// Crate version from Cargo.toml
+    pub version: String,
+    pub git_hash: Option<String>,
+    /// Migrations applied to the open database
+    pub schema_version: usize,
+}
+
+impl AppVersion {
+    pub fn current(db: &Database) -> Result<Self, DbError> {
+        Ok(Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_hash: GIT_HASH.map(str::to_string),
+            schema_version: db.schema_version()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::db::SCHEMA_VERSION;
+
+    #[test]
+    fn test_current_version() {
+        let version = AppVersion::current(&Database::in_memory()).unwrap();
+        assert!(!version.version.is_empty());
+        assert_eq!(version.schema_version, SCHEMA_VERSION);
+    }
+}
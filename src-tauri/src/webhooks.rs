@@ -0,0 +1,110 @@
+//! Violation webhooks: a JSON POST to a schedule's `webhook_url` whenever one of
+//! its violation notifications fires, e.g. to flash a light via home automation.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long a webhook may take before it's abandoned
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// JSON body of a violation webhook
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViolationPayload {
+    pub schedule_id: i64,
+    pub schedule_name: String,
+    pub current_app: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Shared HTTP client for webhooks
+#[derive(Debug, Clone)]
+pub struct WebhookClient {
+    client: reqwest::Client,
+}
+
+impl Default for WebhookClient {
+    fn default() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self { client }
+    }
+}
+
+impl WebhookClient {
+    /// POST `payload` to `url`; non-2xx responses are errors
+    pub async fn post(&self, url: &str, payload: &ViolationPayload) -> Result<(), reqwest::Error> {
+        self.client.post(url).json(payload).send().await?.error_for_status()?;
+        Ok(())
+    }
+
+    /// Send in the background so a slow endpoint never holds up the polling loop.
+    /// Failures are logged and otherwise ignored.
+    pub fn spawn(&self, url: String, payload: ViolationPayload) {
+        let client = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = client.post(&url, &payload).await {
+                eprintln!("[Webhook] Failed to notify {} for '{}': {}", url, payload.schedule_name, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Accept one request, answer with `status` and return the request body
+    async fn serve_once(listener: TcpListener, status: &str) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 1024];
+        let body_start = loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+        };
+        let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+        let length: usize = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .map_or(0, |value| value.trim().parse().unwrap());
+        while request.len() < body_start + length {
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+        }
+        let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+        stream.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8(request[body_start..].to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_posts_violation_payload() {
+        let payload = ViolationPayload {
+            schedule_id: 3,
+            schedule_name: "Deep Work".to_string(),
+            current_app: "Steam.exe".to_string(),
+            timestamp: DateTime::from_timestamp(1_704_103_200, 0).unwrap(),
+        };
+        let client = WebhookClient::default();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_once(listener, "200 OK"));
+        client.post(&url, &payload).await.unwrap();
+        let body: ViolationPayload = serde_json::from_str(&server.await.unwrap()).unwrap();
+        assert_eq!(body, payload);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_once(listener, "500 Internal Server Error"));
+        assert!(client.post(&url, &payload).await.is_err());
+        server.await.unwrap();
+    }
+}
@@ -38,7 +38,7 @@ export function ScheduleItem({ schedule, onEdit, onDelete, onToggle }: ScheduleI
             <div className="flex items-center gap-1.5">
               <Clock className="w-3.5 h-3.5 text-indigo-400" />
               <span>
-                {schedule.start_time.slice(0, 5)} - {schedule.end_time.slice(0, 5)}
+                {schedule.start_time.slice(0, 5)} - {schedule.end_time?.slice(0, 5) ?? "end of day"}
               </span>
             </div>
             <span className="w-1 h-1 rounded-full bg-zinc-700" />
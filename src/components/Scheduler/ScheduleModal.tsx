@@ -1,6 +1,6 @@
 import { useState, useEffect } from "react";
 import { X, Plus, AlertCircle } from "lucide-react";
-import { Schedule, DAYS_OF_WEEK } from "../../types";
+import { Schedule, MatchMode, DAYS_OF_WEEK } from "../../types";
 import { clsx } from "clsx";
 
 interface ScheduleModalProps {
@@ -19,8 +19,21 @@ const DEFAULT_SCHEDULE: Schedule = {
   check_interval_secs: 5,
   grace_period_secs: 30,
   enabled: true,
+  match_mode: "Substring",
+  tag: null,
+  windows: [],
+  active_until: null,
+  end_notified: false,
+  webhook_url: null,
 };
 
+const MATCH_MODES: { value: MatchMode; label: string }[] = [
+  { value: "Substring", label: "Contains" },
+  { value: "Exact", label: "Exact name" },
+  { value: "Glob", label: "Glob (* and ?)" },
+  { value: "Regex", label: "Regex" },
+];
+
 export function ScheduleModal({ isOpen, onClose, onSave, initialData }: ScheduleModalProps) {
   const [formData, setFormData] = useState<Schedule>(DEFAULT_SCHEDULE);
   const [newApp, setNewApp] = useState("");
@@ -40,7 +53,7 @@ export function ScheduleModal({ isOpen, onClose, onSave, initialData }: Schedule
     const finalData = {
       ...formData,
       start_time: formData.start_time.length === 5 ? `${formData.start_time}:00` : formData.start_time,
-      end_time: formData.end_time.length === 5 ? `${formData.end_time}:00` : formData.end_time,
+      end_time: formData.end_time?.length === 5 ? `${formData.end_time}:00` : formData.end_time,
     };
     onSave(finalData);
   };
@@ -105,6 +118,18 @@ export function ScheduleModal({ isOpen, onClose, onSave, initialData }: Schedule
               />
             </div>
 
+            {/* Tag */}
+            <div className="space-y-2">
+              <label className="text-sm font-medium text-zinc-400">Tag (optional)</label>
+              <input
+                type="text"
+                value={formData.tag ?? ""}
+                onChange={(e) => setFormData((prev) => ({ ...prev, tag: e.target.value || null }))}
+                placeholder="e.g., work"
+                className="w-full px-4 py-2 bg-zinc-950 border border-zinc-800 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-indigo-500/50 focus:border-indigo-500 transition-all"
+              />
+            </div>
+
             {/* Time Range */}
             <div className="grid grid-cols-2 gap-4">
               <div className="space-y-2">
@@ -118,12 +143,11 @@ export function ScheduleModal({ isOpen, onClose, onSave, initialData }: Schedule
                 />
               </div>
               <div className="space-y-2">
-                <label className="text-sm font-medium text-zinc-400">End Time</label>
+                <label className="text-sm font-medium text-zinc-400">End Time (empty = end of day)</label>
                 <input
                   type="time"
-                  required
-                  value={formData.end_time.slice(0, 5)}
-                  onChange={(e) => setFormData((prev) => ({ ...prev, end_time: e.target.value }))}
+                  value={formData.end_time?.slice(0, 5) ?? ""}
+                  onChange={(e) => setFormData((prev) => ({ ...prev, end_time: e.target.value || null }))}
                   className="w-full px-4 py-2 bg-zinc-950 border border-zinc-800 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-indigo-500/50"
                 />
               </div>
@@ -174,6 +198,17 @@ export function ScheduleModal({ isOpen, onClose, onSave, initialData }: Schedule
                   <Plus className="w-5 h-5" />
                 </button>
               </div>
+              <select
+                value={formData.match_mode}
+                onChange={(e) => setFormData((prev) => ({ ...prev, match_mode: e.target.value as MatchMode }))}
+                className="w-full px-3 py-1.5 bg-zinc-950 border border-zinc-800 rounded-lg text-white text-sm focus:outline-none focus:ring-1 focus:ring-indigo-500/50"
+              >
+                {MATCH_MODES.map(({ value, label }) => (
+                  <option key={value} value={value}>
+                    {label}
+                  </option>
+                ))}
+              </select>
               <div className="flex flex-wrap gap-2 mt-2 min-h-8">
                 {formData.expected_apps.map((app, i) => (
                   <span
@@ -227,6 +262,16 @@ export function ScheduleModal({ isOpen, onClose, onSave, initialData }: Schedule
                   className="w-full px-3 py-1.5 bg-zinc-950 border border-zinc-800 rounded-lg text-white text-sm focus:outline-none focus:ring-1 focus:ring-indigo-500/50"
                 />
               </div>
+              <div className="col-span-2 space-y-2">
+                <label className="text-xs font-medium text-zinc-500">Violation Webhook (optional)</label>
+                <input
+                  type="url"
+                  value={formData.webhook_url ?? ""}
+                  onChange={(e) => setFormData((prev) => ({ ...prev, webhook_url: e.target.value.trim() || null }))}
+                  placeholder="https://example.com/hook"
+                  className="w-full px-3 py-1.5 bg-zinc-950 border border-zinc-800 rounded-lg text-white text-sm focus:outline-none focus:ring-1 focus:ring-indigo-500/50"
+                />
+              </div>
             </div>
           </div>
 
@@ -2,12 +2,56 @@ export interface Schedule {
   id?: number;
   name: string;
   start_time: string; // HH:MM:SS
-  end_time: string; // HH:MM:SS
+  end_time: string | null; // HH:MM:SS, null = until end of day
   days: string[]; // ["Mon", "Tue", ...]
   expected_apps: string[];
   check_interval_secs: number;
   grace_period_secs: number;
   enabled: boolean;
+  match_mode: MatchMode; // how expected_apps entries match the app name
+  tag: string | null; // group label for toggling schedules together
+  windows: ScheduleWindow[]; // time ranges with their own expected_apps, first match wins
+  active_until: string | null; // YYYY-MM-DD, last day the schedule applies
+  end_notified: boolean; // one-time end notice already sent
+  webhook_url: string | null; // receives a JSON POST on each violation notification
 }
 
+export interface ScheduleWindow {
+  start_time: string; // HH:MM:SS
+  end_time: string | null; // HH:MM:SS, null = until end of day
+  expected_apps: string[];
+}
+
+export type MatchMode = "Exact" | "Substring" | "Glob" | "Regex";
+
 export const DAYS_OF_WEEK = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+export interface Goal {
+  id?: number;
+  name: string;
+  apps: string[]; // app IDs whose time counts toward the goal
+  target_seconds: number;
+  days: string[]; // ["Mon", "Tue", ...]
+}
+
+export interface ProjectRule {
+  id?: number;
+  project: string;
+  process: string; // process name, case-insensitive
+  title_pattern: string | null; // regex the window title must match
+}
+
+export interface NeglectReminder {
+  id?: number;
+  app_id: string;
+  remind_after_secs: number; // gap since last use that triggers a reminder
+  window_start: string; // HH:MM:SS
+  window_end: string; // HH:MM:SS
+  enabled: boolean;
+}
+
+export interface AppVersion {
+  version: string;
+  git_hash: string | null;
+  schema_version: number; // migrations applied to the database
+}